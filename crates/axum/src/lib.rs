@@ -12,13 +12,13 @@ use axum::{
     AddExtensionLayer, Router, Server,
 };
 use azerust_game::{accounts::AccountService, realms::RealmList};
-use azerust_graphql::{create_schema, Mutation, Query};
+use azerust_graphql::{create_schema, CharacterServices, MailSender, Mutation, Query};
 
 async fn graphql_handler<
     A: 'static + AccountService + Send + Sync,
     R: 'static + RealmList + Send + Sync,
 >(
-    schema: Extension<Schema<Query<A, R>, Mutation<A>, EmptySubscription>>,
+    schema: Extension<Schema<Query<A, R>, Mutation<A, R>, EmptySubscription>>,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
     schema.execute(req.into_inner()).await.into()
@@ -35,8 +35,10 @@ pub async fn api<
     listen_addr: &SocketAddr,
     account: T,
     realms: R,
+    characters: CharacterServices,
+    mail: Box<dyn MailSender>,
 ) -> Result<(), ()> {
-    let schema = create_schema(account, realms);
+    let schema = create_schema(account, realms, characters, mail);
 
     let app = Router::new()
         .route("/", get(graphql_playground).post(graphql_handler::<T, R>))
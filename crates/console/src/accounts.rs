@@ -0,0 +1,511 @@
+//! accounts
+//!
+//! Console commands for managing accounts.
+
+use std::{fmt, time::Duration};
+
+use anyhow::{anyhow, Context, Result};
+use azerust_game::{
+    accounts::{Account, AccountId, AccountService, Ban},
+    audit::LoginAttempt,
+    realms::RealmId,
+};
+use serde::Serialize;
+use structopt::StructOpt;
+
+/// Commands for managing accounts
+#[derive(StructOpt, Debug)]
+pub enum AccountCommand {
+    /// Create a new account
+    Create {
+        /// The username of the new account
+        username: String,
+        /// The password to use
+        password: String,
+        /// The email address
+        email: String,
+    },
+    /// Delete an account and all of its characters
+    Delete {
+        /// The username of the account
+        username: String,
+    },
+    /// Change the details of an account
+    Set {
+        #[structopt(subcommand)]
+        command: SetCommand,
+    },
+    /// Ban an account
+    Ban {
+        /// The username of the account
+        username: String,
+        /// The length of the ban. If omitted, the ban is permanent
+        #[structopt(long)]
+        days: Option<u64>,
+        /// The reason for the ban
+        #[structopt(long)]
+        reason: Option<String>,
+        /// Who is issuing the ban
+        #[structopt(long, default_value = "console")]
+        author: String,
+    },
+    /// Lift the ban on an account
+    Unban {
+        /// The username of the account
+        username: String,
+    },
+    /// Prevent an account from chatting
+    Mute {
+        /// The username of the account
+        username: String,
+        /// The length of the mute
+        #[structopt(long)]
+        minutes: u64,
+        /// The reason for the mute
+        #[structopt(long)]
+        reason: Option<String>,
+        /// Who is issuing the mute
+        #[structopt(long, default_value = "console")]
+        author: String,
+    },
+    /// Lift the mute on an account
+    Unmute {
+        /// The username of the account
+        username: String,
+    },
+    /// List the current and past bans on an account
+    Bans {
+        /// The username of the account
+        username: String,
+    },
+    /// Lock an account to the address it last logged in from
+    Lock {
+        /// The username of the account
+        username: String,
+        /// Remove the lock instead
+        #[structopt(long)]
+        unlock: bool,
+    },
+    /// List the most recent login attempts
+    Logins {
+        /// Only show attempts for this account
+        username: Option<String>,
+        /// The number of attempts to show
+        #[structopt(long, default_value = "20")]
+        limit: u32,
+    },
+    /// List all accounts
+    List,
+    /// Show the details of an account
+    Show {
+        /// The username of the account
+        username: String,
+    },
+}
+
+/// Commands for changing the details of an account
+#[derive(StructOpt, Debug)]
+pub enum SetCommand {
+    /// Set the password of an account
+    Password { username: String, password: String },
+    /// Set the email address of an account
+    Email { username: String, email: String },
+    /// Set the GM level of an account
+    Gmlevel {
+        username: String,
+        level: u8,
+        /// The realm to set the level on. If omitted, applies to all realms
+        #[structopt(long)]
+        realm: Option<u32>,
+    },
+    /// Set the expansion an account has access to
+    Expansion { username: String, expansion: u8 },
+}
+
+/// A summary of an account that is safe to show to admins.
+#[derive(Serialize, Debug)]
+pub struct AccountSummary {
+    pub id: u32,
+    pub username: String,
+    pub email: String,
+    pub expansion: u8,
+    pub locked: bool,
+    pub online: bool,
+    pub banned: bool,
+    /// When a temporary ban ends
+    pub banned_until: Option<String>,
+    pub joindate: String,
+    pub last_login: Option<String>,
+}
+
+impl From<Account> for AccountSummary {
+    fn from(a: Account) -> Self {
+        Self {
+            id: a.id.0,
+            username: a.username,
+            email: a.email,
+            expansion: a.expansion,
+            locked: a.locked != 0,
+            online: a.online != 0,
+            banned: a.ban_status.is_some(),
+            banned_until: a
+                .ban_status
+                .and_then(|b| b.expires())
+                .map(|d| d.to_rfc3339()),
+            joindate: a.joindate.to_rfc3339(),
+            last_login: a.last_login.map(|d| d.to_rfc3339()),
+        }
+    }
+}
+
+impl fmt::Display for AccountSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({})", self.username, self.id)?;
+        writeln!(f, "  email: {}", self.email)?;
+        writeln!(f, "  expansion: {}", self.expansion)?;
+        writeln!(f, "  locked: {}", self.locked)?;
+        writeln!(f, "  online: {}", self.online)?;
+        match (self.banned, &self.banned_until) {
+            (true, Some(until)) => writeln!(f, "  banned: until {until}")?,
+            (banned, _) => writeln!(f, "  banned: {banned}")?,
+        }
+        writeln!(f, "  joined: {}", self.joindate)?;
+        write!(
+            f,
+            "  last login: {}",
+            self.last_login.as_deref().unwrap_or("never")
+        )
+    }
+}
+
+/// A ban on an account.
+#[derive(Serialize, Debug)]
+pub struct BanSummary {
+    pub banned_at: String,
+    pub expires: Option<String>,
+    pub author: String,
+    pub reason: String,
+    pub active: bool,
+}
+
+impl From<Ban> for BanSummary {
+    fn from(b: Ban) -> Self {
+        Self {
+            banned_at: b.banned_at.to_rfc3339(),
+            expires: b.expires.map(|d| d.to_rfc3339()),
+            author: b.author,
+            reason: b.reason,
+            active: b.active,
+        }
+    }
+}
+
+impl fmt::Display for BanSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} until {} by {}",
+            self.banned_at,
+            self.expires.as_deref().unwrap_or("forever"),
+            self.author
+        )?;
+        if !self.reason.is_empty() {
+            write!(f, ": {}", self.reason)?;
+        }
+        if self.active {
+            write!(f, " (active)")?;
+        }
+        Ok(())
+    }
+}
+
+/// An attempt to log in.
+#[derive(Serialize, Debug)]
+pub struct LoginSummary {
+    pub kind: String,
+    pub username: String,
+    pub account: Option<u32>,
+    pub ip: String,
+    pub build: u16,
+    pub os: String,
+    pub country: String,
+    pub result: u8,
+    pub success: bool,
+    pub time: String,
+}
+
+impl From<LoginAttempt> for LoginSummary {
+    fn from(l: LoginAttempt) -> Self {
+        Self {
+            kind: l.kind.to_string(),
+            success: l.succeeded(),
+            username: l.username,
+            account: l.account.map(|a| a.0),
+            ip: l.ip.to_string(),
+            build: l.build,
+            os: l.os,
+            country: l.country,
+            result: l.result,
+            time: l.time.to_rfc3339(),
+        }
+    }
+}
+
+impl fmt::Display for LoginSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} from {} ({} {} {}): ",
+            self.time, self.kind, self.username, self.ip, self.build, self.os, self.country
+        )?;
+        match self.success {
+            true => write!(f, "success"),
+            false => write!(f, "failed with code {}", self.result),
+        }
+    }
+}
+
+/// The result of an account command.
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Output {
+    Done { message: String },
+    Created { id: u32 },
+    Account(AccountSummary),
+    Accounts(Vec<AccountSummary>),
+    Bans(Vec<BanSummary>),
+    Logins(Vec<LoginSummary>),
+    Error { error: String },
+}
+
+impl Output {
+    /// Render the output, either for people or as JSON for scripts.
+    pub fn render(&self, json: bool) -> Result<String> {
+        if json {
+            Ok(serde_json::to_string(self)?)
+        } else {
+            Ok(self.to_string())
+        }
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Output::Done { message } => write!(f, "{message}"),
+            Output::Created { id } => write!(f, "created account {id}"),
+            Output::Account(account) => write!(f, "{account}"),
+            Output::Accounts(accounts) => {
+                write!(f, "{} accounts", accounts.len())?;
+                for a in accounts {
+                    write!(f, "\n{:>6} {}", a.id, a.username)?;
+                }
+                Ok(())
+            }
+            Output::Bans(bans) => {
+                write!(f, "{} bans", bans.len())?;
+                for b in bans {
+                    write!(f, "\n  {b}")?;
+                }
+                Ok(())
+            }
+            Output::Logins(logins) => {
+                write!(f, "{} login attempts", logins.len())?;
+                for l in logins {
+                    write!(f, "\n  {l}")?;
+                }
+                Ok(())
+            }
+            Output::Error { error } => write!(f, "error: {error}"),
+        }
+    }
+}
+
+fn done(message: String) -> Output {
+    Output::Done { message }
+}
+
+/// Run an account command against the given service.
+pub async fn run(command: AccountCommand, accounts: &dyn AccountService) -> Result<Output> {
+    Ok(match command {
+        AccountCommand::Create {
+            username,
+            password,
+            email,
+        } => {
+            let id = accounts
+                .create_account(&username, &password, &email)
+                .await
+                .context("failed to create account")?;
+            Output::Created { id: id.0 }
+        }
+        AccountCommand::Delete { username } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .delete_account(id)
+                .await
+                .context("failed to delete account")?;
+            done(format!("deleted account {username}"))
+        }
+        AccountCommand::Set { command } => set(command, accounts).await?,
+        AccountCommand::Ban {
+            username,
+            days,
+            reason,
+            author,
+        } => {
+            let id = lookup(accounts, &username).await?;
+            let duration = days.map(crate::days).transpose()?;
+            accounts
+                .set_ban(id, &author, duration, reason.as_deref())
+                .await
+                .context("failed to ban account")?;
+            match days {
+                Some(days) => done(format!("banned {username} for {days} days")),
+                None => done(format!("banned {username} permanently")),
+            }
+        }
+        AccountCommand::Unban { username } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .unban(id)
+                .await
+                .context("failed to unban account")?;
+            done(format!("unbanned {username}"))
+        }
+        AccountCommand::Mute {
+            username,
+            minutes,
+            reason,
+            author,
+        } => {
+            let duration = minutes
+                .checked_mul(60)
+                .map(Duration::from_secs)
+                .ok_or_else(|| anyhow!("{minutes} minutes is too long"))?;
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .set_mute(id, &author, duration, reason.as_deref())
+                .await
+                .context("failed to mute account")?;
+            done(format!("muted {username} for {minutes} minutes"))
+        }
+        AccountCommand::Unmute { username } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .unmute(id)
+                .await
+                .context("failed to unmute account")?;
+            done(format!("unmuted {username}"))
+        }
+        AccountCommand::Bans { username } => {
+            let id = lookup(accounts, &username).await?;
+            Output::Bans(
+                accounts
+                    .bans(id)
+                    .await
+                    .context("failed to get bans")?
+                    .into_iter()
+                    .map(BanSummary::from)
+                    .collect(),
+            )
+        }
+        AccountCommand::Lock { username, unlock } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .set_locked(id, !unlock)
+                .await
+                .context("failed to lock account")?;
+            match unlock {
+                true => done(format!("unlocked {username}")),
+                false => done(format!("locked {username}")),
+            }
+        }
+        AccountCommand::Logins { username, limit } => {
+            let id = match username {
+                Some(username) => Some(lookup(accounts, &username).await?),
+                None => None,
+            };
+            Output::Logins(
+                accounts
+                    .login_history(id, limit)
+                    .await
+                    .context("failed to get login history")?
+                    .into_iter()
+                    .map(LoginSummary::from)
+                    .collect(),
+            )
+        }
+        AccountCommand::List => Output::Accounts(
+            accounts
+                .list_account()
+                .await
+                .context("failed to list accounts")?
+                .into_iter()
+                .map(AccountSummary::from)
+                .collect(),
+        ),
+        AccountCommand::Show { username } => Output::Account(
+            accounts
+                .get_by_username(&username.to_ascii_uppercase())
+                .await
+                .context("failed to get account")?
+                .ok_or_else(|| anyhow!("no account named {username}"))?
+                .into(),
+        ),
+    })
+}
+
+async fn set(command: SetCommand, accounts: &dyn AccountService) -> Result<Output> {
+    Ok(match command {
+        SetCommand::Password { username, password } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .set_password(id, &password)
+                .await
+                .context("failed to set password")?;
+            done(format!("changed password for {username}"))
+        }
+        SetCommand::Email { username, email } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .set_email(id, &email)
+                .await
+                .context("failed to set email")?;
+            done(format!("changed email for {username}"))
+        }
+        SetCommand::Gmlevel {
+            username,
+            level,
+            realm,
+        } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .set_gm_level(id, level, realm.map(RealmId))
+                .await
+                .context("failed to set gm level")?;
+            done(format!("set gm level for {username} to {level}"))
+        }
+        SetCommand::Expansion {
+            username,
+            expansion,
+        } => {
+            let id = lookup(accounts, &username).await?;
+            accounts
+                .set_expansion(id, expansion)
+                .await
+                .context("failed to set expansion")?;
+            done(format!("set expansion for {username} to {expansion}"))
+        }
+    })
+}
+
+/// Find the id of the account with the given username.
+async fn lookup(accounts: &dyn AccountService, username: &str) -> Result<AccountId> {
+    accounts
+        .get_by_username(&username.to_ascii_uppercase())
+        .await
+        .context("failed to get account")?
+        .map(|a| a.id)
+        .ok_or_else(|| anyhow!("no account named {username}"))
+}
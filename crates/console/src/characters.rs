@@ -0,0 +1,185 @@
+//! characters
+//!
+//! Console commands for managing characters.
+
+use anyhow::{anyhow, bail, Context, Result};
+use azerust_game::characters::{AtLoginFlags, CharacterId, CharacterService};
+use structopt::StructOpt;
+
+/// Commands for managing characters
+#[derive(StructOpt, Debug)]
+pub enum CharacterCommand {
+    /// Ban a character from logging in
+    Ban {
+        /// The name of the character
+        name: String,
+        /// The length of the ban. If omitted, the ban is permanent
+        #[structopt(long)]
+        days: Option<u64>,
+        /// The reason for the ban
+        #[structopt(long)]
+        reason: Option<String>,
+        /// Who is issuing the ban
+        #[structopt(long, default_value = "console")]
+        author: String,
+    },
+    /// Lift the ban on a character
+    Unban {
+        /// The name of the character
+        name: String,
+    },
+    /// Make a character pick a new name the next time they log in
+    Rename {
+        /// The name of the character
+        name: String,
+    },
+    /// Let a character change their name and appearance the next time they log in
+    Customize {
+        /// The name of the character
+        name: String,
+    },
+    /// Let a character change faction the next time they log in
+    ChangeFaction {
+        /// The name of the character
+        name: String,
+    },
+    /// Let a character change race the next time they log in
+    ChangeRace {
+        /// The name of the character
+        name: String,
+    },
+    /// List the characters that were deleted and can still be restored
+    Deleted,
+    /// Return a deleted character to the account it was deleted from
+    Restore {
+        /// The name the character had when it was deleted
+        name: String,
+        /// A new name for the character, if their old one has been taken
+        #[structopt(long)]
+        rename: Option<String>,
+    },
+}
+
+/// Run a character command against the given service.
+pub async fn run(
+    command: CharacterCommand,
+    characters: &(dyn CharacterService + Sync),
+) -> Result<String> {
+    match command {
+        CharacterCommand::Ban {
+            name,
+            days,
+            reason,
+            author,
+        } => {
+            let id = lookup(characters, &name).await?;
+            let duration = days.map(crate::days).transpose()?;
+            characters
+                .ban_character(id, &author, duration, reason.as_deref())
+                .await
+                .context("failed to ban character")?;
+            Ok(match days {
+                Some(days) => format!("banned {name} for {days} days"),
+                None => format!("banned {name} permanently"),
+            })
+        }
+        CharacterCommand::Unban { name } => {
+            let id = lookup(characters, &name).await?;
+            characters
+                .unban_character(id)
+                .await
+                .context("failed to unban character")?;
+            Ok(format!("unbanned {name}"))
+        }
+        CharacterCommand::Rename { name } => {
+            flag(characters, &name, AtLoginFlags::Rename).await?;
+            Ok(format!("{name} will be asked for a new name"))
+        }
+        CharacterCommand::Customize { name } => {
+            flag(characters, &name, AtLoginFlags::Customize).await?;
+            Ok(format!("{name} can change their appearance"))
+        }
+        CharacterCommand::ChangeFaction { name } => {
+            flag(characters, &name, AtLoginFlags::ChangeFaction).await?;
+            Ok(format!("{name} can change their faction"))
+        }
+        CharacterCommand::ChangeRace { name } => {
+            flag(characters, &name, AtLoginFlags::ChangeRace).await?;
+            Ok(format!("{name} can change their race"))
+        }
+        CharacterCommand::Deleted => {
+            let deleted = characters
+                .deleted_characters()
+                .await
+                .context("failed to get deleted characters")?;
+            let mut output = format!("{} deleted characters", deleted.len());
+            for c in deleted {
+                output += &format!(
+                    "\n{:>6} {} level {} on account {}, deleted {}",
+                    c.id.low(),
+                    c.name,
+                    c.level,
+                    c.account,
+                    c.deleted.format("%Y-%m-%d %H:%M")
+                );
+            }
+            Ok(output)
+        }
+        CharacterCommand::Restore { name, rename } => {
+            // the most recently deleted character is the one most likely wanted back
+            let deleted = characters
+                .deleted_characters()
+                .await
+                .context("failed to get deleted characters")?
+                .into_iter()
+                .rev()
+                .find(|c| c.name.eq_ignore_ascii_case(&name))
+                .ok_or_else(|| anyhow!("no deleted character named {name}"))?;
+            let new_name = rename.unwrap_or_else(|| deleted.name.clone());
+            if !characters
+                .name_available(&new_name)
+                .await
+                .context("failed to check name")?
+            {
+                bail!("{new_name} is taken, restore them with --rename");
+            }
+            characters
+                .restore_character(deleted.id.try_into()?, &new_name)
+                .await
+                .context("failed to restore character")?;
+            Ok(format!(
+                "restored {new_name} to account {}",
+                deleted.account
+            ))
+        }
+    }
+}
+
+/// Add a change for a character to make the next time they log in.
+/// This is safe while they are online, as saving a character in the
+/// world leaves its flags alone.
+async fn flag(
+    characters: &(dyn CharacterService + Sync),
+    name: &str,
+    flag: AtLoginFlags,
+) -> Result<()> {
+    let character = characters
+        .get_by_name(name)
+        .await
+        .context("failed to get character")?
+        .ok_or_else(|| anyhow!("no character named {name}"))?;
+    characters
+        .set_at_login(character.id.try_into()?, character.at_login | flag)
+        .await
+        .context("failed to flag character")
+}
+
+/// Find the id of the character with the given name.
+async fn lookup(characters: &(dyn CharacterService + Sync), name: &str) -> Result<CharacterId> {
+    let character = characters
+        .get_by_name(name)
+        .await
+        .context("failed to get character")?
+        .ok_or_else(|| anyhow!("no character named {name}"))?;
+    Ok(character.id.try_into()?)
+}
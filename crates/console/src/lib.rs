@@ -0,0 +1,435 @@
+//! console
+//!
+//! This crate implements the admin console shared by the auth and
+//! world servers. Commands are parsed with the same parser as the
+//! `exec` subcommand, and can be issued on stdin or over a socket
+//! bound to localhost, which asks for a shared secret before it
+//! accepts any commands.
+
+use std::{
+    io::{BufRead, Write},
+    iter,
+    net::Ipv4Addr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use structopt::{clap::ArgMatches, StructOpt};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpListener,
+    sync::mpsc::unbounded_channel,
+    time::timeout,
+    try_join,
+};
+use tracing::{info, warn};
+
+pub mod accounts;
+pub mod characters;
+
+pub use accounts::AccountCommand;
+pub use characters::CharacterCommand;
+
+const PROMPT: &str = "> ";
+const SECRET_PROMPT: &str = "secret: ";
+/// How long a connection has to send the secret, so that one that
+/// never does can't hold up the console for everyone else.
+const SECRET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The commands that can be run against a server.
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    /// Manage accounts
+    Account {
+        #[structopt(subcommand)]
+        command: AccountCommand,
+        /// Print the output as JSON
+        #[structopt(long, global = true)]
+        json: bool,
+    },
+    /// Manage characters
+    Character {
+        #[structopt(subcommand)]
+        command: CharacterCommand,
+    },
+    /// List the players that are online
+    Online,
+    /// Disconnect a player from the server
+    Kick {
+        /// The name of the character to kick
+        name: String,
+    },
+    /// Send a message to every player
+    Announce {
+        /// The message to send
+        #[structopt(required = true)]
+        message: Vec<String>,
+    },
+    /// Shut down the server after a delay
+    Shutdown {
+        /// The number of seconds to wait before shutting down
+        #[structopt(default_value = "0")]
+        delay: u64,
+        /// Cancel a pending shutdown instead
+        #[structopt(long)]
+        cancel: bool,
+    },
+    /// Reload the realmlist
+    Reload,
+}
+
+impl Command {
+    /// Reads a command from its matches. Clap only records global flags
+    /// on the subcommands below the one they are passed to, so they are
+    /// read from the innermost subcommand.
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let mut command = Self::from_clap(matches);
+        if let Self::Account { json, .. } = &mut command {
+            let mut matches = matches;
+            while let (_, Some(inner)) = matches.subcommand() {
+                matches = inner;
+            }
+            *json = matches.is_present("json");
+        }
+        command
+    }
+}
+
+/// A server that can run console commands.
+#[async_trait]
+pub trait Console: Send + Sync {
+    /// Run a command, returning the output to show the user.
+    async fn run(&self, command: Command) -> Result<String>;
+}
+
+/// A local port to accept console connections on.
+pub struct Listener {
+    pub port: u16,
+    /// The secret that connections must send before running commands.
+    pub secret: String,
+}
+
+impl Listener {
+    /// The listener for a configured port, which must come with a secret.
+    pub fn from_config(port: Option<u16>, secret: Option<String>) -> Result<Option<Self>> {
+        match (port, secret) {
+            (Some(port), Some(secret)) if !secret.is_empty() => Ok(Some(Self { port, secret })),
+            (Some(_), _) => bail!("admin_secret must be set to accept admin console connections"),
+            (None, _) => Ok(None),
+        }
+    }
+}
+
+/// Runs the console on stdin, and on the given local port if provided.
+pub async fn run<C: Console>(console: &C, listener: Option<Listener>) -> Result<()> {
+    match listener {
+        Some(listener) => try_join!(stdin(console), listen(console, listener)).map(|_| ()),
+        None => stdin(console).await,
+    }
+}
+
+/// Reads commands from stdin until it is closed.
+pub async fn stdin<C: Console>(console: &C) -> Result<()> {
+    // stdin reads can't be cancelled, so read on a plain thread
+    // to avoid holding up the runtime when the server exits
+    let (sender, mut receiver) = unbounded_channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let sent = line.map(|l| sender.send(l).is_ok()).unwrap_or(false);
+            if !sent {
+                break;
+            }
+        }
+    });
+
+    prompt()?;
+    while let Some(line) = receiver.recv().await {
+        print!("{}", execute(console, &line).await);
+        prompt()?;
+    }
+
+    Ok(())
+}
+
+fn prompt() -> Result<()> {
+    print!("{PROMPT}");
+    std::io::stdout().flush()?;
+    Ok(())
+}
+
+/// Accepts console connections on the given port, bound to localhost.
+pub async fn listen<C: Console>(console: &C, listener: Listener) -> Result<()> {
+    let Listener { port, secret } = listener;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await?;
+    info!("admin console listening on {port}");
+
+    loop {
+        let (stream, addr) = listener.accept().await?;
+        info!("admin console connected from {addr}");
+        let (reader, writer) = stream.into_split();
+        if let Err(e) = serve(console, &secret, reader, writer).await {
+            warn!("admin console connection from {addr} failed: {e}");
+        }
+    }
+}
+
+/// Checks the secret sent on the first line, and then runs commands
+/// read line by line from the reader.
+async fn serve<C, R, W>(console: &C, secret: &str, reader: R, mut writer: W) -> Result<()>
+where
+    C: Console,
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    writer.write_all(SECRET_PROMPT.as_bytes()).await?;
+    let line = timeout(SECRET_TIMEOUT, lines.next_line())
+        .await
+        .map_err(|_| anyhow!("timed out waiting for the secret"))??;
+    match line {
+        Some(line) if secrets_match(line.as_bytes(), secret.as_bytes()) => {}
+        Some(_) => {
+            writer.write_all(b"invalid secret\n").await?;
+            bail!("invalid secret");
+        }
+        None => return Ok(()),
+    }
+
+    writer.write_all(PROMPT.as_bytes()).await?;
+    while let Some(line) = lines.next_line().await? {
+        writer
+            .write_all(execute(console, &line).await.as_bytes())
+            .await?;
+        writer.write_all(PROMPT.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Compares secrets in constant time, so that the secret can't be
+/// guessed from how long a comparison takes.
+fn secrets_match(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// A number of days as a duration, rejecting ones too long to represent.
+fn days(days: u64) -> Result<Duration> {
+    days.checked_mul(24 * 60 * 60)
+        .map(Duration::from_secs)
+        .ok_or_else(|| anyhow!("{days} days is too long"))
+}
+
+/// Parses and runs a single line, returning the output.
+async fn execute<C: Console>(console: &C, line: &str) -> String {
+    let output = match parse(line) {
+        Ok(None) => return String::new(),
+        Ok(Some(command)) => match console.run(command).await {
+            Ok(output) => output,
+            Err(e) => format!("error: {e:#}"),
+        },
+        Err(e) => e,
+    };
+
+    if output.is_empty() || output.ends_with('\n') {
+        output
+    } else {
+        output + "\n"
+    }
+}
+
+/// Parses a command from a line of input. Returns `None` if the line
+/// is empty, or the error / help text if it is not a valid command.
+pub fn parse(line: &str) -> Result<Option<Command>, String> {
+    let args = split(line)?;
+    if args.is_empty() {
+        return Ok(None);
+    }
+
+    Command::clap()
+        .get_matches_from_safe(iter::once("azerust".to_string()).chain(args))
+        .map(|matches| Some(Command::from_matches(&matches)))
+        .map_err(|e| e.message)
+}
+
+/// Splits a line into arguments, keeping quoted sections together.
+pub fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.get_or_insert_with(String::new).push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => args.extend(current.take()),
+            None => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+
+    args.extend(current);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use anyhow::Result;
+    use async_trait::async_trait;
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    use super::{parse, serve, split, AccountCommand, CharacterCommand, Command, Console};
+
+    struct Echo;
+
+    #[async_trait]
+    impl Console for Echo {
+        async fn run(&self, command: Command) -> Result<String> {
+            Ok(format!("{command:?}"))
+        }
+    }
+
+    /// Sends the input to a console connection, returning its output.
+    async fn session(input: &str) -> (Result<()>, String) {
+        let (mut client, server) = duplex(1024);
+        client.write_all(input.as_bytes()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let (reader, writer) = tokio::io::split(server);
+        let result = serve(&Echo, "hunter2", reader, writer).await;
+        let mut output = String::new();
+        client.read_to_string(&mut output).await.unwrap();
+        (result, output)
+    }
+
+    #[tokio::test]
+    pub async fn serve_with_secret() {
+        let (result, output) = session("hunter2\nonline\n").await;
+        assert!(result.is_ok());
+        assert_eq!(output, "secret: > Online\n> ");
+    }
+
+    #[tokio::test]
+    pub async fn serve_with_wrong_secret() {
+        let (result, output) = session("hunter3\nonline\n").await;
+        assert!(result.is_err());
+        assert_eq!(output, "secret: invalid secret\n");
+    }
+
+    #[tokio::test(start_paused = true)]
+    pub async fn serve_without_secret() {
+        // the client stays connected but never sends anything
+        let (_client, server) = duplex(1024);
+        let (reader, writer) = tokio::io::split(server);
+        assert!(serve(&Echo, "hunter2", reader, writer).await.is_err());
+    }
+
+    #[test]
+    pub fn split_words() {
+        assert_eq!(split("  kick   Arlyon ").unwrap(), ["kick", "Arlyon"]);
+    }
+
+    #[test]
+    pub fn split_quoted() {
+        assert_eq!(
+            split(r#"announce "back in 5" 'min'"#).unwrap(),
+            ["announce", "back in 5", "min"]
+        );
+        assert_eq!(split(r#"x """#).unwrap(), ["x", ""]);
+        assert!(split(r#"announce "oops"#).is_err());
+    }
+
+    #[test]
+    pub fn parse_empty() {
+        assert!(parse("   ").unwrap().is_none());
+    }
+
+    #[test]
+    pub fn parse_account_create() {
+        match parse("account create arlyon hunter2 a@b.c").unwrap() {
+            Some(Command::Account {
+                command:
+                    AccountCommand::Create {
+                        username,
+                        password,
+                        email,
+                    },
+                ..
+            }) => {
+                assert_eq!(username, "arlyon");
+                assert_eq!(password, "hunter2");
+                assert_eq!(email, "a@b.c");
+            }
+            c => panic!("unexpected command {c:?}"),
+        }
+    }
+
+    #[test]
+    pub fn parse_account_json() {
+        assert!(matches!(
+            parse("account list --json").unwrap(),
+            Some(Command::Account {
+                command: AccountCommand::List,
+                json: true
+            })
+        ));
+        assert!(matches!(
+            parse("account set gmlevel arlyon 3 --realm 1").unwrap(),
+            Some(Command::Account {
+                command: AccountCommand::Set { .. },
+                json: false
+            })
+        ));
+    }
+
+    #[test]
+    pub fn parse_character_change_faction() {
+        match parse("character change-faction Thrall").unwrap() {
+            Some(Command::Character {
+                command: CharacterCommand::ChangeFaction { name },
+            }) => assert_eq!(name, "Thrall"),
+            c => panic!("unexpected command {c:?}"),
+        }
+    }
+
+    #[test]
+    pub fn parse_character_restore() {
+        match parse("character restore Cairne --rename Baine").unwrap() {
+            Some(Command::Character {
+                command: CharacterCommand::Restore { name, rename },
+            }) => {
+                assert_eq!(name, "Cairne");
+                assert_eq!(rename.as_deref(), Some("Baine"));
+            }
+            c => panic!("unexpected command {c:?}"),
+        }
+    }
+
+    #[test]
+    pub fn parse_shutdown() {
+        assert!(matches!(
+            parse("shutdown 60").unwrap(),
+            Some(Command::Shutdown {
+                delay: 60,
+                cancel: false
+            })
+        ));
+        assert!(matches!(
+            parse("shutdown --cancel").unwrap(),
+            Some(Command::Shutdown { cancel: true, .. })
+        ));
+    }
+
+    #[test]
+    pub fn parse_invalid() {
+        assert!(parse("frobnicate").is_err());
+    }
+}
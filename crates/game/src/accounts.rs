@@ -6,7 +6,7 @@
 use std::time::Duration;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use derive_more::Display;
 use rand::Rng;
 use sha1::Digest;
@@ -14,6 +14,8 @@ use sqlx::Type;
 use thiserror::Error;
 use wow_srp::{Salt, Verifier, WowSRPServer};
 
+use crate::{audit::LoginAttempt, realms::RealmId};
+
 /// An id for an account.
 #[derive(Debug, Display, PartialEq, Type, Clone, Copy)]
 #[sqlx(transparent)]
@@ -34,16 +36,52 @@ pub struct Account {
     pub joindate: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
     pub online: u8,
+    pub locked: u8,
+    pub expansion: u8,
 }
 
 /// Models the status of someone's ban.
-#[derive(PartialEq, Eq, Debug, Type, Clone, Copy)]
-#[repr(u8)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum BanStatus {
-    Temporary,
+    Temporary { until: DateTime<Utc> },
     Permanent,
 }
 
+impl BanStatus {
+    /// Get the status of a ban from its start and end timestamps, returning
+    /// `None` if it has expired. Permanent bans are stored with an end equal
+    /// to their start.
+    pub fn from_timestamps(start: i64, end: i64) -> Option<Self> {
+        if start == end {
+            Some(BanStatus::Permanent)
+        } else {
+            let until = Utc.timestamp_opt(end, 0).single()?;
+            (until > Utc::now()).then_some(BanStatus::Temporary { until })
+        }
+    }
+
+    /// When the ban ends, if ever.
+    pub fn expires(&self) -> Option<DateTime<Utc>> {
+        match self {
+            BanStatus::Temporary { until } => Some(*until),
+            BanStatus::Permanent => None,
+        }
+    }
+}
+
+/// A ban on an account, either current or past.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Ban {
+    pub banned_at: DateTime<Utc>,
+    /// When the ban ends. If `None`, the ban is permanent.
+    pub expires: Option<DateTime<Utc>>,
+    pub author: String,
+    pub reason: String,
+    /// Whether the ban is still in place. Bans are deactivated
+    /// when they are lifted or once they expire.
+    pub active: bool,
+}
+
 #[derive(Copy, Debug, Clone, PartialEq)]
 /// Handles the verification step of logging in.
 pub struct ConnectToken {
@@ -189,8 +227,69 @@ pub trait AccountService: Send + Sync {
         duration: Option<Duration>,
         reason: Option<&str>,
     ) -> Result<(), AccountOpError>;
+
+    /// Lifts the active ban on an account.
+    async fn unban(&self, id: AccountId) -> Result<(), AccountOpError>;
+
+    /// Lists all bans on an account, most recent first.
+    async fn bans(&self, id: AccountId) -> Result<Vec<Ban>, AccountOpError>;
+
+    /// Deactivates all bans that have expired, returning how many there were.
+    async fn expire_bans(&self) -> Result<u64, AccountOpError>;
+
+    /// Prevents an account from chatting for the given duration.
+    async fn set_mute(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError>;
+
+    /// Lifts the mute on an account.
+    async fn unmute(&self, id: AccountId) -> Result<(), AccountOpError>;
+
+    /// When the account's mute ends, if it is muted.
+    async fn muted_until(&self, id: AccountId) -> Result<Option<DateTime<Utc>>, AccountOpError>;
+
+    /// Records an attempt to log in. The account is looked up from the username.
+    async fn record_login(&self, attempt: &LoginAttempt) -> Result<(), AccountOpError>;
+
+    /// Lists the most recent login attempts, optionally for a single account.
+    async fn login_history(
+        &self,
+        id: Option<AccountId>,
+        limit: u32,
+    ) -> Result<Vec<LoginAttempt>, AccountOpError>;
+
+    /// Deletes login attempts from before the given time, returning how many there were.
+    async fn prune_login_history(&self, before: DateTime<Utc>) -> Result<u64, AccountOpError>;
+
+    /// Changes the password of an account, ending any existing sessions.
+    async fn set_password(&self, id: AccountId, password: &str) -> Result<(), AccountOpError>;
+
+    async fn set_email(&self, id: AccountId, email: &str) -> Result<(), AccountOpError>;
+
+    /// Sets the GM level of an account on the given realm, or on all
+    /// realms if none is given. A level of 0 removes GM access, from
+    /// every realm if none is given.
+    async fn set_gm_level(
+        &self,
+        id: AccountId,
+        level: u8,
+        realm: Option<RealmId>,
+    ) -> Result<(), AccountOpError>;
+
+    /// Sets the newest expansion that an account can access.
+    async fn set_expansion(&self, id: AccountId, expansion: u8) -> Result<(), AccountOpError>;
+
+    /// Locks an account to the address it last logged in from.
+    async fn set_locked(&self, id: AccountId, locked: bool) -> Result<(), AccountOpError>;
 }
 
+/// The newest expansion an account can be given access to (Wrath of the Lich King).
+pub const MAX_EXPANSION: u8 = 2;
+
 /// Errors that may occur when running account operations.
 #[derive(Error, Debug, Display)]
 pub enum AccountOpError {
@@ -198,6 +297,9 @@ pub enum AccountOpError {
     PasswordTooLong,
     PersistError(String),
     InvalidAccount(AccountId),
+    InvalidExpansion(u8),
+    NotBanned(AccountId),
+    NotMuted(AccountId),
 }
 
 /// Errors that may occur when accessing accounts.
@@ -209,7 +311,7 @@ pub enum AccountFetchError {
 /// Errors that may occur when logging in.
 #[derive(Copy, Clone, Debug)]
 pub enum LoginFailure {
-    Suspended,
+    Suspended { until: DateTime<Utc> },
     Banned,
     UnknownAccount,
     IncorrectPassword,
@@ -0,0 +1,52 @@
+//! audit
+//!
+//! Records of attempts to log in to the auth server.
+
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use derive_more::Display;
+
+use crate::accounts::AccountId;
+
+/// How the client tried to log in.
+#[derive(Debug, Display, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum LoginKind {
+    #[display(fmt = "connect")]
+    Connect = 0,
+    #[display(fmt = "reconnect")]
+    Reconnect = 1,
+}
+
+impl LoginKind {
+    pub fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(LoginKind::Connect),
+            1 => Some(LoginKind::Reconnect),
+            _ => None,
+        }
+    }
+}
+
+/// A single attempt to log in, successful or not.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LoginAttempt {
+    pub kind: LoginKind,
+    pub username: String,
+    /// The account the username belongs to, if it exists.
+    pub account: Option<AccountId>,
+    pub ip: IpAddr,
+    pub build: u16,
+    pub os: String,
+    pub country: String,
+    /// The code returned to the client, where 0 is success.
+    pub result: u8,
+    pub time: DateTime<Utc>,
+}
+
+impl LoginAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.result == 0
+    }
+}
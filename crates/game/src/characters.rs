@@ -1,8 +1,16 @@
+use std::{sync::Arc, time::Duration};
+
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use enumflags2::{bitflags, BitFlags};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use sqlx::Type;
 use thiserror::Error;
 
-use crate::{accounts::AccountId, EntityType, WowId};
+use crate::{
+    accounts::{AccountId, BanStatus},
+    EntityType, WowId,
+};
 
 #[derive(Error, Debug, Clone, Copy)]
 pub enum TryFromWowIdError {
@@ -10,7 +18,7 @@ pub enum TryFromWowIdError {
     InvalidEntityType(u16),
 }
 
-#[derive(Debug, Clone, Copy, Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Type)]
 #[sqlx(transparent)]
 pub struct CharacterId(u64);
 
@@ -27,6 +35,68 @@ impl TryFrom<WowId> for CharacterId {
     }
 }
 
+impl From<CharacterId> for WowId {
+    fn from(id: CharacterId) -> Self {
+        WowId(id.0)
+    }
+}
+
+/// Changes a character has to go through the next time they log in.
+#[repr(u16)]
+#[bitflags]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AtLoginFlags {
+    Rename = 0x001,
+    ResetSpells = 0x002,
+    ResetTalents = 0x004,
+    Customize = 0x008,
+    ResetPetTalents = 0x010,
+    FirstLogin = 0x020,
+    ChangeFaction = 0x040,
+    ChangeRace = 0x080,
+    Resurrect = 0x100,
+}
+
+/// The player flags that are shown on the character screen.
+#[repr(u32)]
+#[bitflags]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerFlags {
+    Ghost = 0x0000_0010,
+    HideHelm = 0x0000_0400,
+    HideCloak = 0x0000_0800,
+}
+
+/// The flags a character is listed with on the character screen.
+#[repr(u32)]
+#[bitflags]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharacterFlags {
+    HideHelm = 0x0000_0400,
+    HideCloak = 0x0000_0800,
+    Ghost = 0x0000_2000,
+    Rename = 0x0000_4000,
+    LockedByBilling = 0x0100_0000,
+}
+
+/// The change the character screen offers before logging in.
+#[repr(u32)]
+#[bitflags]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CustomizeFlags {
+    Customize = 0x0000_0001,
+    ChangeFaction = 0x0001_0000,
+    ChangeRace = 0x0010_0000,
+}
+
+/// The pet a character has out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CharacterPet {
+    pub entry: u32,
+    pub display: u32,
+    pub level: u8,
+}
+
 #[derive(Debug, Clone)]
 pub struct Character {
     pub id: WowId,
@@ -48,14 +118,110 @@ pub struct Character {
     pub position_x: f32,
     pub position_y: f32,
     pub position_z: f32,
+
+    pub player_flags: BitFlags<PlayerFlags>,
+    pub at_login: BitFlags<AtLoginFlags>,
+    /// The guild the character is in, or 0
+    pub guild: u32,
+    pub pet: Option<CharacterPet>,
+}
+
+impl Character {
+    /// The flags to list the character with, where `locked` is
+    /// whether they are banned.
+    pub fn flags(&self, locked: bool) -> BitFlags<CharacterFlags> {
+        let mut flags = BitFlags::empty();
+        if self.player_flags.contains(PlayerFlags::HideHelm) {
+            flags |= CharacterFlags::HideHelm;
+        }
+        if self.player_flags.contains(PlayerFlags::HideCloak) {
+            flags |= CharacterFlags::HideCloak;
+        }
+        // characters that are resurrected on login are not shown as ghosts
+        if self.player_flags.contains(PlayerFlags::Ghost)
+            && !self.at_login.contains(AtLoginFlags::Resurrect)
+        {
+            flags |= CharacterFlags::Ghost;
+        }
+        if self.at_login.contains(AtLoginFlags::Rename) {
+            flags |= CharacterFlags::Rename;
+        }
+        if locked {
+            flags |= CharacterFlags::LockedByBilling;
+        }
+        flags
+    }
+
+    /// The change to offer on the character screen, of which the
+    /// client only shows one.
+    pub fn customize_flags(&self) -> BitFlags<CustomizeFlags> {
+        if self.at_login.contains(AtLoginFlags::Customize) {
+            CustomizeFlags::Customize.into()
+        } else if self.at_login.contains(AtLoginFlags::ChangeFaction) {
+            CustomizeFlags::ChangeFaction.into()
+        } else if self.at_login.contains(AtLoginFlags::ChangeRace) {
+            CustomizeFlags::ChangeRace.into()
+        } else {
+            BitFlags::empty()
+        }
+    }
 }
 
-// position_x: -8949.94f32, //
-// position_y: -132.50f32,  // human start zone
-// position_z: 83.53f32,    //
+/// An item a new character starts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartingItem {
+    pub id: WowId,
+    pub entry: u32,
+    pub count: u32,
+    /// The slot in the equipment or backpack, both of which are in bag 0
+    pub slot: u8,
+    pub durability: u16,
+}
+
+/// The number of slots shown on the character screen, which are
+/// the equipment slots followed by the bag slots.
+pub const EQUIPMENT_SLOTS: u8 = 23;
+
+/// An item in one of a character's equipment or bag slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EquippedItem {
+    pub slot: u8,
+    pub entry: u32,
+    /// The permanent enchantment on the item, or 0
+    pub enchant: u32,
+}
+
+/// A character that was deleted but is kept so that it can be restored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletedCharacter {
+    pub id: WowId,
+    /// The account the character was deleted from
+    pub account: AccountId,
+    /// The name the character had when it was deleted
+    pub name: String,
+    pub level: u8,
+    pub race: u8,
+    pub class: u8,
+    pub deleted: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharacterSkill {
+    pub skill: u16,
+    pub value: u16,
+    pub max: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActionButton {
+    pub button: u8,
+    pub action: u32,
+    pub action_type: u8,
+}
 
 #[derive(Debug, Clone)]
 pub struct CharacterCreate {
+    pub id: WowId,
     pub name: String,
     pub race: u8,
     pub class: u8,
@@ -72,6 +238,39 @@ pub struct CharacterCreate {
     pub position_x: f32,
     pub position_y: f32,
     pub position_z: f32,
+    pub orientation: f32,
+
+    pub items: Vec<StartingItem>,
+    pub spells: Vec<u32>,
+    pub skills: Vec<CharacterSkill>,
+    pub action_buttons: Vec<ActionButton>,
+}
+
+/// The kinds of data the client stores on the server, either
+/// once for the account or once for each character.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+pub enum AccountDataType {
+    GlobalConfig = 0,
+    CharacterConfig = 1,
+    GlobalBindings = 2,
+    CharacterBindings = 3,
+    GlobalMacros = 4,
+    CharacterMacros = 5,
+    CharacterLayout = 6,
+    CharacterChat = 7,
+}
+
+impl AccountDataType {
+    /// Whether the data is kept for the whole account rather than per character.
+    pub fn is_global(self) -> bool {
+        matches!(
+            self,
+            AccountDataType::GlobalConfig
+                | AccountDataType::GlobalBindings
+                | AccountDataType::GlobalMacros
+        )
+    }
 }
 
 #[derive(Debug, Default)]
@@ -90,6 +289,20 @@ pub struct AccountData {
 }
 
 impl AccountData {
+    /// The data of the given type, if any is stored.
+    pub fn get(&self, kind: AccountDataType) -> Option<&AccountDataCache> {
+        match kind {
+            AccountDataType::GlobalConfig => self.config.global.as_ref(),
+            AccountDataType::CharacterConfig => self.config.per_char.as_ref(),
+            AccountDataType::GlobalBindings => self.bindings.global.as_ref(),
+            AccountDataType::CharacterBindings => self.bindings.per_char.as_ref(),
+            AccountDataType::GlobalMacros => self.macros.global.as_ref(),
+            AccountDataType::CharacterMacros => self.macros.per_char.as_ref(),
+            AccountDataType::CharacterLayout => self.per_char_layout.as_ref(),
+            AccountDataType::CharacterChat => self.per_char_chat.as_ref(),
+        }
+    }
+
     pub fn items(self) -> [Option<AccountDataCache>; 8] {
         [
             self.config.global,
@@ -104,7 +317,7 @@ impl AccountData {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct AccountDataCache {
     pub time: u32,
     pub data: Vec<u8>,
@@ -114,15 +327,250 @@ pub struct AccountDataCache {
 pub trait CharacterService {
     async fn get(&self, id: CharacterId) -> Result<Character, CharacterServiceError>;
     async fn get_by_account(&self, id: AccountId) -> Result<Vec<Character>, CharacterServiceError>;
-    async fn account_data(&self, id: AccountId) -> Result<AccountData, CharacterServiceError>;
+    async fn get_by_name(&self, name: &str) -> Result<Option<Character>, CharacterServiceError>;
+
+    /// The data the client stored for an account, along with the per
+    /// character data of one of its characters if one is given.
+    async fn account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+    ) -> Result<AccountData, CharacterServiceError>;
+
+    /// Store data for an account, or for the given character if the
+    /// data is per character.
+    async fn set_account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    ) -> Result<(), CharacterServiceError>;
+
+    /// The tutorials an account has seen, one bit per tutorial.
+    async fn tutorials(&self, id: AccountId) -> Result<[u32; 8], CharacterServiceError>;
+
+    /// Store the tutorials an account has seen.
+    async fn set_tutorials(
+        &self,
+        id: AccountId,
+        tutorials: [u32; 8],
+    ) -> Result<(), CharacterServiceError>;
+
     async fn count_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError>;
     async fn name_available(&self, name: &str) -> Result<bool, CharacterServiceError>;
+
+    /// The items in a character's equipment and bag slots.
+    async fn equipment(&self, id: CharacterId) -> Result<Vec<EquippedItem>, CharacterServiceError>;
+
+    /// The highest low id in use for an entity type, or 0 if there
+    /// are none or they are not kept in this database.
+    async fn max_guid(&self, kind: EntityType) -> Result<u32, CharacterServiceError>;
+
     async fn create_character(
         &self,
         account: AccountId,
         create: CharacterCreate,
     ) -> Result<(), CharacterServiceError>;
     async fn delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError>;
+
+    /// Delete every character on an account, including those that were
+    /// soft deleted, along with the account's data and tutorials,
+    /// returning the number of characters deleted.
+    async fn delete_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError>;
+
+    /// Take a character off its account and free its name, keeping
+    /// everything else so that it can be restored.
+    async fn soft_delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError>;
+
+    /// The characters that have been soft deleted, oldest first.
+    async fn deleted_characters(&self) -> Result<Vec<DeletedCharacter>, CharacterServiceError>;
+
+    /// Return a soft deleted character to the account it was deleted
+    /// from, under the given name.
+    async fn restore_character(
+        &self,
+        id: CharacterId,
+        name: &str,
+    ) -> Result<(), CharacterServiceError>;
+
+    /// Delete the characters that were soft deleted before the given
+    /// time for good, returning the number of characters deleted.
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<usize, CharacterServiceError>;
+
+    /// Persist the state of a character that is in the world. Its at
+    /// login flags are left alone, as they may be changed while it is
+    /// online, see `set_at_login`.
+    async fn save_character(&self, character: &Character) -> Result<(), CharacterServiceError>;
+
+    /// Set the changes a character has to make the next time they log in.
+    async fn set_at_login(
+        &self,
+        id: CharacterId,
+        flags: BitFlags<AtLoginFlags>,
+    ) -> Result<(), CharacterServiceError>;
+
+    /// Persist a change to a character's name, race or appearance,
+    /// along with the at login flags that are left.
+    async fn customize_character(&self, character: &Character)
+        -> Result<(), CharacterServiceError>;
+
+    /// Ban a character from logging in. If `duration` is `None`, the ban is permanent.
+    async fn ban_character(
+        &self,
+        id: CharacterId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), CharacterServiceError>;
+
+    /// Lifts the active ban on a character.
+    async fn unban_character(&self, id: CharacterId) -> Result<(), CharacterServiceError>;
+
+    /// Get the ban currently in place on a character, if any.
+    async fn character_ban(
+        &self,
+        id: CharacterId,
+    ) -> Result<Option<BanStatus>, CharacterServiceError>;
+}
+
+/// Lets servers hold a character service whose backend is only
+/// known at runtime, such as one picked from the config.
+#[async_trait]
+impl<T: CharacterService + Send + Sync + ?Sized> CharacterService for Arc<T> {
+    async fn get(&self, id: CharacterId) -> Result<Character, CharacterServiceError> {
+        (**self).get(id).await
+    }
+
+    async fn get_by_account(&self, id: AccountId) -> Result<Vec<Character>, CharacterServiceError> {
+        (**self).get_by_account(id).await
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Character>, CharacterServiceError> {
+        (**self).get_by_name(name).await
+    }
+
+    async fn account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+    ) -> Result<AccountData, CharacterServiceError> {
+        (**self).account_data(id, character).await
+    }
+
+    async fn set_account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    ) -> Result<(), CharacterServiceError> {
+        (**self).set_account_data(id, character, kind, data).await
+    }
+
+    async fn tutorials(&self, id: AccountId) -> Result<[u32; 8], CharacterServiceError> {
+        (**self).tutorials(id).await
+    }
+
+    async fn set_tutorials(
+        &self,
+        id: AccountId,
+        tutorials: [u32; 8],
+    ) -> Result<(), CharacterServiceError> {
+        (**self).set_tutorials(id, tutorials).await
+    }
+
+    async fn count_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        (**self).count_by_account(id).await
+    }
+
+    async fn name_available(&self, name: &str) -> Result<bool, CharacterServiceError> {
+        (**self).name_available(name).await
+    }
+
+    async fn equipment(&self, id: CharacterId) -> Result<Vec<EquippedItem>, CharacterServiceError> {
+        (**self).equipment(id).await
+    }
+
+    async fn max_guid(&self, kind: EntityType) -> Result<u32, CharacterServiceError> {
+        (**self).max_guid(kind).await
+    }
+
+    async fn create_character(
+        &self,
+        account: AccountId,
+        create: CharacterCreate,
+    ) -> Result<(), CharacterServiceError> {
+        (**self).create_character(account, create).await
+    }
+
+    async fn delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        (**self).delete_character(id).await
+    }
+
+    async fn delete_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        (**self).delete_by_account(id).await
+    }
+
+    async fn soft_delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        (**self).soft_delete_character(id).await
+    }
+
+    async fn deleted_characters(&self) -> Result<Vec<DeletedCharacter>, CharacterServiceError> {
+        (**self).deleted_characters().await
+    }
+
+    async fn restore_character(
+        &self,
+        id: CharacterId,
+        name: &str,
+    ) -> Result<(), CharacterServiceError> {
+        (**self).restore_character(id, name).await
+    }
+
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<usize, CharacterServiceError> {
+        (**self).purge_deleted(before).await
+    }
+
+    async fn save_character(&self, character: &Character) -> Result<(), CharacterServiceError> {
+        (**self).save_character(character).await
+    }
+
+    async fn set_at_login(
+        &self,
+        id: CharacterId,
+        flags: BitFlags<AtLoginFlags>,
+    ) -> Result<(), CharacterServiceError> {
+        (**self).set_at_login(id, flags).await
+    }
+
+    async fn customize_character(
+        &self,
+        character: &Character,
+    ) -> Result<(), CharacterServiceError> {
+        (**self).customize_character(character).await
+    }
+
+    async fn ban_character(
+        &self,
+        id: CharacterId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), CharacterServiceError> {
+        (**self).ban_character(id, author, duration, reason).await
+    }
+
+    async fn unban_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        (**self).unban_character(id).await
+    }
+
+    async fn character_ban(
+        &self,
+        id: CharacterId,
+    ) -> Result<Option<BanStatus>, CharacterServiceError> {
+        (**self).character_ban(id).await
+    }
 }
 
 /// Errors that may occur when running character operations.
@@ -132,6 +580,8 @@ pub enum CharacterServiceError {
     NoSuchAccount(AccountId),
     #[error("no such character {0:?}")]
     NoSuchCharacter(CharacterId),
+    #[error("character {0:?} is not banned")]
+    NotBanned(CharacterId),
     #[error("persistence error {0:?}")]
     PersistError(String),
 }
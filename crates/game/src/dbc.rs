@@ -0,0 +1,114 @@
+//! dbc
+//!
+//! Reads the client's DBC files, the tables of static game data
+//! that ship with the client rather than the world database.
+
+use thiserror::Error;
+
+const MAGIC: &[u8; 4] = b"WDBC";
+const HEADER_SIZE: usize = 20;
+
+#[derive(Error, Debug, Clone, Copy)]
+pub enum DbcError {
+    #[error("not a dbc file")]
+    InvalidHeader,
+    #[error("dbc file is truncated")]
+    Truncated,
+}
+
+/// A DBC file, which is a header followed by a table of fixed size
+/// records and the strings they refer to.
+#[derive(Debug, Clone)]
+pub struct Dbc {
+    record_size: usize,
+    records: Vec<u8>,
+}
+
+impl Dbc {
+    pub fn parse(bytes: &[u8]) -> Result<Self, DbcError> {
+        if bytes.get(..4) != Some(&MAGIC[..]) {
+            return Err(DbcError::InvalidHeader);
+        }
+        let header = |field: usize| -> Result<usize, DbcError> {
+            let start = 4 + field * 4;
+            bytes
+                .get(start..start + 4)
+                .and_then(|b| b.try_into().ok())
+                .map(|b| u32::from_le_bytes(b) as usize)
+                .ok_or(DbcError::Truncated)
+        };
+        let (record_count, record_size) = (header(0)?, header(2)?);
+        if record_size == 0 {
+            return Err(DbcError::InvalidHeader);
+        }
+
+        let length = record_count
+            .checked_mul(record_size)
+            .ok_or(DbcError::Truncated)?;
+        let records = bytes
+            .get(HEADER_SIZE..HEADER_SIZE + length)
+            .ok_or(DbcError::Truncated)?
+            .to_vec();
+
+        Ok(Self {
+            record_size,
+            records,
+        })
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = Record<'_>> {
+        self.records.chunks_exact(self.record_size).map(Record)
+    }
+}
+
+/// A single record of a DBC file.
+#[derive(Debug, Clone, Copy)]
+pub struct Record<'a>(&'a [u8]);
+
+impl Record<'_> {
+    /// The field at the given index, if the record is long enough.
+    pub fn u32(&self, field: usize) -> Option<u32> {
+        let start = field.checked_mul(4)?;
+        self.0
+            .get(start..start + 4)
+            .and_then(|b| b.try_into().ok())
+            .map(u32::from_le_bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::{Dbc, DbcError};
+
+    /// A dbc file with the given records of two fields each.
+    fn dbc(records: &[[u32; 2]]) -> Vec<u8> {
+        let mut bytes = b"WDBC".to_vec();
+        for header in [records.len() as u32, 2, 8, 1] {
+            bytes.extend(header.to_le_bytes());
+        }
+        for field in records.iter().flatten() {
+            bytes.extend(field.to_le_bytes());
+        }
+        // the string block, which starts with an empty string
+        bytes.push(0);
+        bytes
+    }
+
+    #[test]
+    pub fn parse_records() {
+        let dbc = Dbc::parse(&dbc(&[[1, 10], [2, 20]])).unwrap();
+        let fields: Vec<_> = dbc.records().map(|r| (r.u32(0), r.u32(1))).collect();
+        assert_eq!(fields, [(Some(1), Some(10)), (Some(2), Some(20))]);
+        assert_eq!(dbc.records().next().unwrap().u32(2), None);
+    }
+
+    #[test]
+    pub fn parse_invalid() {
+        assert!(matches!(Dbc::parse(b"WDB2"), Err(DbcError::InvalidHeader)));
+        let mut truncated = dbc(&[[1, 10]]);
+        truncated.truncate(24);
+        assert!(matches!(Dbc::parse(&truncated), Err(DbcError::Truncated)));
+    }
+}
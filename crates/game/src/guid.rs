@@ -0,0 +1,69 @@
+//! guid
+//!
+//! Allocation of the low part of [`WowId`]s. Every entity type has
+//! its own counter, which starts after the highest id in use when
+//! the server starts and only ever goes up.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use strum::IntoEnumIterator;
+use thiserror::Error;
+
+use crate::{
+    characters::{CharacterService, CharacterServiceError},
+    EntityType, WowId,
+};
+
+#[derive(Error, Debug, Clone, Copy)]
+pub enum GuidError {
+    #[error("no guids left for {0:?}")]
+    Exhausted(EntityType),
+}
+
+/// Hands out unique ids, shared by everything that creates entities.
+#[derive(Debug)]
+pub struct GuidAllocator {
+    next: HashMap<EntityType, AtomicU32>,
+}
+
+impl Default for GuidAllocator {
+    fn default() -> Self {
+        Self::new([])
+    }
+}
+
+impl GuidAllocator {
+    /// Create an allocator that continues after the highest low
+    /// id in use for each entity type. Types that are not given
+    /// start at 1.
+    pub fn new(highest: impl IntoIterator<Item = (EntityType, u32)>) -> Self {
+        let mut next: HashMap<EntityType, AtomicU32> =
+            EntityType::iter().map(|k| (k, AtomicU32::new(1))).collect();
+        for (kind, highest) in highest {
+            next.insert(kind, AtomicU32::new(highest.saturating_add(1)));
+        }
+        Self { next }
+    }
+
+    /// Create an allocator for the entities kept in a character database.
+    pub async fn load<C: CharacterService + ?Sized>(
+        characters: &C,
+    ) -> Result<Self, CharacterServiceError> {
+        let mut highest = vec![];
+        for kind in [EntityType::Player, EntityType::ItemOrContainer] {
+            highest.push((kind, characters.max_guid(kind).await?));
+        }
+        Ok(Self::new(highest))
+    }
+
+    /// Allocate the next id for an entity type.
+    pub fn next(&self, kind: EntityType) -> Result<WowId, GuidError> {
+        self.next[&kind]
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_add(1))
+            .map(|low| WowId::new(kind, low, 0))
+            .map_err(|_| GuidError::Exhausted(kind))
+    }
+}
@@ -19,17 +19,22 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
+use strum_macros::EnumIter;
 
 pub mod accounts;
+pub mod audit;
 pub mod characters;
+pub mod dbc;
+pub mod guid;
 pub mod realms;
 pub mod types;
+pub mod world;
 
 #[derive(PartialEq, Debug, Eq, Clone, Copy, Type, Serialize, Deserialize)]
 #[sqlx(transparent)]
 pub struct WowId(u64);
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
 pub enum EntityType {
     Player = 0x0000,
     ItemOrContainer = 0x4000,
@@ -49,4 +54,9 @@ impl WowId {
     pub fn new(r#type: EntityType, low: u32, mid: u32) -> Self {
         Self((low as u64) | ((mid as u64) << 24) | ((r#type as u64) << 48))
     }
+
+    /// The low part of the id, which is unique among entities of the same type.
+    pub fn low(&self) -> u32 {
+        self.0 as u32
+    }
 }
@@ -7,13 +7,15 @@ use std::time::SystemTime;
 
 use async_trait::async_trait;
 use derive_more::{From, Into};
-use enumflags2::bitflags;
+use enumflags2::{bitflags, BitFlags};
 use num_enum::IntoPrimitive;
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
 use strum_macros::ToString;
 use thiserror::Error;
 
+use crate::accounts::AccountId;
+
 /// The various flags that a realm can have.
 /// They are implemented as BitFlags.
 #[repr(u8)]
@@ -33,7 +35,7 @@ pub enum RealmFlags {
 /// The various types of realm.
 /// For more, see <https://wow.tools/dbc/?dbc=cfg_configs&build=3.3.5.12340>
 #[repr(u8)]
-#[derive(Clone, Copy, Type, Debug, IntoPrimitive, ToString)]
+#[derive(Clone, Copy, Type, Debug, IntoPrimitive, ToString, Serialize, Deserialize)]
 pub enum RealmType {
     Normal = 0,
     PVP = 1,
@@ -57,18 +59,51 @@ pub struct Realm {
     pub local_address: String,
     pub local_subnet_mask: String,
     pub port: u16,
-    pub flags: u8, // BitFlags<RealmFlags>
+    pub flags: BitFlags<RealmFlags>,
     pub timezone: u8,
     pub population: f32,
 }
 
+impl Realm {
+    /// Whether the realm is currently accepting players.
+    pub fn is_online(&self) -> bool {
+        !self.flags.contains(RealmFlags::Offline)
+    }
+}
+
+/// The details a world server provides about itself when
+/// registering with the realmlist.
+#[derive(Clone, Debug)]
+pub struct RealmRegistration {
+    pub id: RealmId,
+    pub name: String,
+    pub realm_type: RealmType,
+    pub build: u32,
+    pub external_address: String,
+    pub local_address: String,
+    pub local_subnet_mask: String,
+    pub port: u16,
+    pub timezone: u8,
+}
+
 /// A trait that models a realmlist.
 #[async_trait]
 pub trait RealmList: Send + Sync {
     /// Return the list of realms sorted by id.
     async fn realms(&self) -> Vec<Realm>;
 
-    async fn update_status(&self, online: Vec<(u8, RealmFlags)>) -> Result<(), RealmListError>;
+    /// Add a realm to the realmlist, or update its details if it is
+    /// already present. The flags and population of an existing realm
+    /// are left untouched.
+    async fn register(&self, realm: &RealmRegistration) -> Result<(), RealmListError>;
+
+    /// Discard any cached realms, so that the next call to `realms`
+    /// reads them from storage.
+    async fn reload(&self);
+
+    /// Mark each of the given realms as online or offline. Any other
+    /// flags set on a realm, such as `New` or `Full`, are preserved.
+    async fn update_status(&self, status: Vec<(RealmId, bool)>) -> Result<(), RealmListError>;
 
     /// Update the uptime counter for a server that started
     /// at the given `start` time.
@@ -78,6 +113,30 @@ pub trait RealmList: Send + Sync {
         start: SystemTime,
         population: u32,
     ) -> Result<(), RealmListError>;
+
+    /// Ask the server running a realm to shut down at the given time,
+    /// or withdraw the request if `at` is `None`.
+    async fn request_shutdown(
+        &self,
+        id: RealmId,
+        at: Option<SystemTime>,
+    ) -> Result<(), RealmListError>;
+
+    /// The time the server running a realm has been asked to shut down
+    /// at, if any.
+    async fn requested_shutdown(&self, id: RealmId) -> Result<Option<SystemTime>, RealmListError>;
+
+    /// Record the number of characters an account has on a realm.
+    async fn set_character_count(
+        &self,
+        id: RealmId,
+        account: AccountId,
+        count: u8,
+    ) -> Result<(), RealmListError>;
+
+    /// The number of characters an account has across every realm,
+    /// as last recorded by each realm's server.
+    async fn character_count(&self, account: AccountId) -> Result<usize, RealmListError>;
 }
 
 /// Errors that may occur when running realmlist operations.
@@ -87,6 +146,8 @@ pub enum RealmListError {
     StartTimeInFuture,
     #[error("start time is too large to be stored")]
     StartTimeTooLarge,
+    #[error("shutdown time is too large to be stored")]
+    ShutdownTimeTooLarge,
     #[error("error in persistence layer: {0}")]
     PersistError(String),
 }
@@ -0,0 +1,415 @@
+//! world
+//!
+//! The static data that makes up the world, such as the
+//! creatures and objects in it and the items they drop.
+//! It is read once when the server starts.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::FromRow;
+use thiserror::Error;
+
+use crate::{
+    characters::{ActionButton, CharacterSkill, StartingItem},
+    dbc::Dbc,
+    guid::{GuidAllocator, GuidError},
+    EntityType,
+};
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CreatureTemplate {
+    pub entry: u32,
+    pub name: String,
+    pub subname: Option<String>,
+    pub min_level: u8,
+    pub max_level: u8,
+    pub faction: u16,
+    pub npc_flags: u32,
+    pub speed_walk: f32,
+    pub speed_run: f32,
+    pub scale: f32,
+    pub rank: u8,
+    pub unit_class: u8,
+    pub unit_flags: u32,
+    pub creature_type: u8,
+    /// The family of creatures that can be tamed or summoned as pets
+    pub family: i8,
+    pub type_flags: u32,
+}
+
+/// A creature placed in the world.
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct CreatureSpawn {
+    pub guid: u32,
+    pub entry: u32,
+    pub map: u16,
+    pub position_x: f32,
+    pub position_y: f32,
+    pub position_z: f32,
+    pub orientation: f32,
+    /// The time in seconds before the creature respawns
+    pub respawn_time: u32,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct GameObjectTemplate {
+    pub entry: u32,
+    pub object_type: u8,
+    pub display_id: u32,
+    pub name: String,
+    pub size: f32,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct ItemTemplate {
+    pub entry: u32,
+    pub class: u8,
+    pub subclass: u8,
+    pub name: String,
+    pub display_id: u32,
+    pub quality: u8,
+    pub flags: u32,
+    pub inventory_type: u8,
+    /// A mask of the classes that may use the item, or -1 for all
+    pub allowable_class: i32,
+    /// A mask of the races that may use the item, or -1 for all
+    pub allowable_race: i32,
+    pub item_level: u16,
+    pub required_level: u8,
+    pub max_count: i32,
+    pub stackable: i32,
+    pub container_slots: u8,
+    pub max_durability: u16,
+}
+
+/// Where a new character of a given race and class starts.
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct PlayerCreateInfo {
+    pub race: u8,
+    pub class: u8,
+    pub map: u16,
+    pub zone: u16,
+    pub position_x: f32,
+    pub position_y: f32,
+    pub position_z: f32,
+    pub orientation: f32,
+}
+
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct PlayerCreateItem {
+    pub race: u8,
+    pub class: u8,
+    pub item: u32,
+    pub amount: i32,
+}
+
+/// A spell given to new characters whose race and class are in the
+/// masks. A mask of zero matches everything.
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct PlayerCreateSpell {
+    pub race_mask: u32,
+    pub class_mask: u32,
+    pub spell: u32,
+}
+
+/// A skill given to new characters whose race and class are in the
+/// masks. A mask of zero matches everything.
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct PlayerCreateSkill {
+    pub race_mask: u32,
+    pub class_mask: u32,
+    pub skill: u16,
+    /// The tier of a skill that is learned in tiers, such as a
+    /// profession, or zero for one capped by the character's level.
+    pub rank: u16,
+}
+
+#[derive(Debug, Clone, Copy, FromRow)]
+pub struct PlayerCreateAction {
+    pub race: u8,
+    pub class: u8,
+    pub button: u8,
+    pub action: u32,
+    pub action_type: u8,
+}
+
+/// Everything a new character of a given race and class starts with.
+#[derive(Debug, Clone)]
+pub struct CharacterStart {
+    pub info: PlayerCreateInfo,
+    pub items: Vec<StartingItem>,
+    pub spells: Vec<u32>,
+    pub skills: Vec<CharacterSkill>,
+    pub action_buttons: Vec<ActionButton>,
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum WorldDatabaseError {
+    #[error("persistence error {0:?}")]
+    PersistError(String),
+}
+
+#[async_trait]
+pub trait WorldDatabase {
+    async fn creature_templates(&self) -> Result<Vec<CreatureTemplate>, WorldDatabaseError>;
+    async fn creature_spawns(&self) -> Result<Vec<CreatureSpawn>, WorldDatabaseError>;
+    async fn gameobject_templates(&self) -> Result<Vec<GameObjectTemplate>, WorldDatabaseError>;
+    async fn item_templates(&self) -> Result<Vec<ItemTemplate>, WorldDatabaseError>;
+    async fn player_create_info(&self) -> Result<Vec<PlayerCreateInfo>, WorldDatabaseError>;
+    async fn player_create_items(&self) -> Result<Vec<PlayerCreateItem>, WorldDatabaseError>;
+    async fn player_create_spells(&self) -> Result<Vec<PlayerCreateSpell>, WorldDatabaseError>;
+    async fn player_create_skills(&self) -> Result<Vec<PlayerCreateSkill>, WorldDatabaseError>;
+    async fn player_create_actions(&self) -> Result<Vec<PlayerCreateAction>, WorldDatabaseError>;
+}
+
+/// The start positions from a stock TrinityCore world database, used
+/// when there is none. Death knights start in Ebon Hold regardless
+/// of their race.
+const DEFAULT_STARTS: [(u8, u16, u16, f32, f32, f32, f32); 10] = [
+    (1, 0, 12, -8949.95, -132.493, 83.5312, 0.0),
+    (2, 1, 14, -618.518, -4251.67, 38.718, 0.0),
+    (3, 0, 1, -6240.32, 331.033, 382.758, 6.17716),
+    (4, 1, 141, 10311.3, 832.463, 1326.41, 5.69632),
+    (5, 0, 85, 1676.71, 1678.31, 121.67, 2.70526),
+    (6, 1, 215, -2917.58, -257.98, 52.9968, 0.0),
+    (7, 0, 1, -6240.32, 331.033, 382.758, 6.17716),
+    (8, 1, 14, -618.518, -4251.67, 38.718, 0.0),
+    (10, 530, 3431, 10349.6, -6357.29, 33.4026, 5.31605),
+    (11, 530, 3526, -3961.64, -13931.2, 100.615, 2.08364),
+];
+const DEATH_KNIGHT: u8 = 6;
+const DEATH_KNIGHT_START: (u16, u16, f32, f32, f32, f32) =
+    (609, 4298, 2355.84, -5664.77, 426.028, 3.65997);
+
+/// The equipment slots an item of each inventory type can go in.
+fn equipment_slots(inventory_type: u8) -> &'static [u8] {
+    match inventory_type {
+        1 => &[0],
+        2 => &[1],
+        3 => &[2],
+        4 => &[3],
+        5 | 20 => &[4],
+        6 => &[5],
+        7 => &[6],
+        8 => &[7],
+        9 => &[8],
+        10 => &[9],
+        11 => &[10, 11],
+        12 => &[12, 13],
+        13 => &[15, 16],
+        14 | 22 | 23 => &[16],
+        15 | 25 | 26 | 28 => &[17],
+        16 => &[14],
+        17 | 21 => &[15],
+        18 | 27 => &[19, 20, 21, 22],
+        19 => &[18],
+        _ => &[],
+    }
+}
+
+/// The slots of the backpack, after the equipment and bag slots.
+const BACKPACK_SLOTS: std::ops::Range<u8> = 23..39;
+
+/// The language skills, which are always at their maximum.
+const LANGUAGES: [u16; 14] = [
+    98, 109, 111, 113, 115, 137, 138, 139, 140, 141, 313, 315, 673, 759,
+];
+const MAX_LANGUAGE_SKILL: u16 = 300;
+/// How far each tier of a tiered skill raises its maximum.
+const SKILL_TIER_STEP: u16 = 75;
+/// How far each level raises the maximum of a skill such as a weapon.
+const SKILL_PER_LEVEL: u16 = 5;
+
+impl PlayerCreateSkill {
+    /// The skill as a new, level 1, character has it.
+    fn starting(&self) -> CharacterSkill {
+        let (value, max) = if LANGUAGES.contains(&self.skill) {
+            (MAX_LANGUAGE_SKILL, MAX_LANGUAGE_SKILL)
+        } else if self.rank > 0 {
+            (1, self.rank.saturating_mul(SKILL_TIER_STEP))
+        } else {
+            (1, SKILL_PER_LEVEL)
+        };
+        CharacterSkill {
+            skill: self.skill,
+            value,
+            max,
+        }
+    }
+}
+
+/// The field of SpellItemEnchantment.dbc with the aura an enchantment shows.
+const ENCHANT_AURA_FIELD: usize = 31;
+
+fn in_mask(mask: u32, id: u8) -> bool {
+    mask == 0 || (id > 0 && id <= 32 && mask & (1 << (id - 1)) != 0)
+}
+
+/// Everything loaded from the world database, indexed for lookups.
+#[derive(Debug, Default)]
+pub struct WorldData {
+    pub creature_templates: HashMap<u32, CreatureTemplate>,
+    pub creature_spawns: Vec<CreatureSpawn>,
+    pub gameobject_templates: HashMap<u32, GameObjectTemplate>,
+    pub item_templates: HashMap<u32, ItemTemplate>,
+    pub player_create_info: HashMap<(u8, u8), PlayerCreateInfo>,
+    pub player_create_items: Vec<PlayerCreateItem>,
+    pub player_create_spells: Vec<PlayerCreateSpell>,
+    pub player_create_skills: Vec<PlayerCreateSkill>,
+    pub player_create_actions: Vec<PlayerCreateAction>,
+    /// The aura shown on items with each enchantment, from the
+    /// client's SpellItemEnchantment.dbc.
+    pub enchant_auras: HashMap<u32, u32>,
+}
+
+impl WorldData {
+    pub async fn load<D: WorldDatabase + Sync>(db: &D) -> Result<Self, WorldDatabaseError> {
+        Ok(Self {
+            creature_templates: db
+                .creature_templates()
+                .await?
+                .into_iter()
+                .map(|c| (c.entry, c))
+                .collect(),
+            creature_spawns: db.creature_spawns().await?,
+            gameobject_templates: db
+                .gameobject_templates()
+                .await?
+                .into_iter()
+                .map(|g| (g.entry, g))
+                .collect(),
+            item_templates: db
+                .item_templates()
+                .await?
+                .into_iter()
+                .map(|i| (i.entry, i))
+                .collect(),
+            player_create_info: db
+                .player_create_info()
+                .await?
+                .into_iter()
+                .map(|p| ((p.race, p.class), p))
+                .collect(),
+            player_create_items: db.player_create_items().await?,
+            player_create_spells: db.player_create_spells().await?,
+            player_create_skills: db.player_create_skills().await?,
+            player_create_actions: db.player_create_actions().await?,
+            enchant_auras: HashMap::new(),
+        })
+    }
+
+    /// Read the aura shown for each enchantment from the client's
+    /// SpellItemEnchantment.dbc.
+    pub fn with_enchant_auras(self, spell_item_enchantment: &Dbc) -> Self {
+        let enchant_auras = spell_item_enchantment
+            .records()
+            .filter_map(|r| Some((r.u32(0)?, r.u32(ENCHANT_AURA_FIELD)?)))
+            .filter(|(_, aura)| *aura != 0)
+            .collect();
+        Self {
+            enchant_auras,
+            ..self
+        }
+    }
+
+    /// Where a new character starts. Without a world database, this
+    /// falls back to the stock start positions.
+    pub fn player_create_info(&self, race: u8, class: u8) -> Option<PlayerCreateInfo> {
+        if !self.player_create_info.is_empty() {
+            return self.player_create_info.get(&(race, class)).copied();
+        }
+
+        let (_, map, zone, position_x, position_y, position_z, orientation) =
+            *DEFAULT_STARTS.iter().find(|s| s.0 == race)?;
+        let (map, zone, position_x, position_y, position_z, orientation) = if class == DEATH_KNIGHT
+        {
+            DEATH_KNIGHT_START
+        } else {
+            (map, zone, position_x, position_y, position_z, orientation)
+        };
+
+        Some(PlayerCreateInfo {
+            race,
+            class,
+            map,
+            zone,
+            position_x,
+            position_y,
+            position_z,
+            orientation,
+        })
+    }
+
+    /// Everything a new character of the given race and class starts
+    /// with, or `None` if they have nowhere to start.
+    ///
+    /// Items are equipped if there is a free slot for them and put in
+    /// the backpack otherwise. Items without a template, or that do
+    /// not fit, are left out.
+    pub fn character_start(
+        &self,
+        race: u8,
+        class: u8,
+        guids: &GuidAllocator,
+    ) -> Result<Option<CharacterStart>, GuidError> {
+        let info = match self.player_create_info(race, class) {
+            Some(info) => info,
+            None => return Ok(None),
+        };
+
+        let mut items: Vec<StartingItem> = vec![];
+        for create in self
+            .player_create_items
+            .iter()
+            .filter(|i| i.race == race && i.class == class)
+        {
+            let template = match self.item_templates.get(&create.item) {
+                Some(t) => t,
+                None => continue,
+            };
+            let free = |slot: &u8| !items.iter().any(|i| i.slot == *slot);
+            let slot = equipment_slots(template.inventory_type)
+                .iter()
+                .copied()
+                .find(free)
+                .or_else(|| BACKPACK_SLOTS.clone().find(free));
+            if let Some(slot) = slot {
+                items.push(StartingItem {
+                    id: guids.next(EntityType::ItemOrContainer)?,
+                    entry: create.item,
+                    count: create.amount.max(1) as u32,
+                    slot,
+                    durability: template.max_durability,
+                });
+            }
+        }
+
+        Ok(Some(CharacterStart {
+            info,
+            items,
+            spells: self
+                .player_create_spells
+                .iter()
+                .filter(|s| in_mask(s.race_mask, race) && in_mask(s.class_mask, class))
+                .map(|s| s.spell)
+                .collect(),
+            skills: self
+                .player_create_skills
+                .iter()
+                .filter(|s| in_mask(s.race_mask, race) && in_mask(s.class_mask, class))
+                .map(PlayerCreateSkill::starting)
+                .collect(),
+            action_buttons: self
+                .player_create_actions
+                .iter()
+                .filter(|a| a.race == race && a.class == class)
+                .map(|a| ActionButton {
+                    button: a.button,
+                    action: a.action,
+                    action_type: a.action_type,
+                })
+                .collect(),
+        }))
+    }
+}
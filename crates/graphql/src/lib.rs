@@ -1,19 +1,33 @@
+use std::sync::Arc;
+
 use async_graphql::{EmptySubscription, Schema};
-use azerust_game::{accounts::AccountService, realms::RealmList};
+use azerust_game::{accounts::AccountService, characters::CharacterService, realms::RealmList};
+pub use mail::{FileMailSender, LogMailSender, Mail, MailConfig, MailError, MailSender};
+pub use resets::PasswordResets;
 pub use schemas::{Mutation, Query};
 
+mod mail;
 mod models;
+mod resets;
 mod schemas;
 
+/// The character services of every realm.
+pub type CharacterServices = Vec<Arc<dyn CharacterService + Send + Sync>>;
+
 pub fn create_schema<
     A: 'static + AccountService + Send + Sync,
     R: 'static + RealmList + Send + Sync,
 >(
     accounts: A,
     realms: R,
-) -> Schema<Query<A, R>, Mutation<A>, EmptySubscription> {
+    characters: CharacterServices,
+    mail: Box<dyn MailSender>,
+) -> Schema<Query<A, R>, Mutation<A, R>, EmptySubscription> {
     Schema::build(Query::new(), Mutation::new(), EmptySubscription)
         .data(accounts)
         .data(realms)
+        .data(characters)
+        .data(mail)
+        .data(PasswordResets::default())
         .finish()
 }
@@ -0,0 +1,105 @@
+//! mail
+//!
+//! A pluggable way to send mail to players. Real deployments can
+//! implement [`MailSender`] for their provider of choice, while the
+//! log and file sinks are useful when running locally.
+
+use std::{
+    fmt,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl fmt::Display for Mail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "To: {}", self.to)?;
+        writeln!(f, "Subject: {}", self.subject)?;
+        writeln!(f)?;
+        write!(f, "{}", self.body)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MailError {
+    #[error("could not write mail: {0}")]
+    IO(#[from] std::io::Error),
+}
+
+#[async_trait]
+pub trait MailSender: Send + Sync {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError>;
+}
+
+/// Writes mail to the log.
+#[derive(Debug, Clone, Copy)]
+pub struct LogMailSender;
+
+#[async_trait]
+impl MailSender for LogMailSender {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        info!("sending mail\n{mail}");
+        Ok(())
+    }
+}
+
+/// Writes each mail to a new file in a directory.
+#[derive(Debug, Clone)]
+pub struct FileMailSender {
+    directory: PathBuf,
+}
+
+impl FileMailSender {
+    pub fn new(directory: PathBuf) -> Self {
+        Self { directory }
+    }
+}
+
+#[async_trait]
+impl MailSender for FileMailSender {
+    async fn send(&self, mail: &Mail) -> Result<(), MailError> {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let path = self.directory.join(format!("{time}.eml"));
+        tokio::fs::create_dir_all(&self.directory).await?;
+        tokio::fs::write(&path, mail.to_string()).await?;
+        info!("wrote mail to {}", path.display());
+        Ok(())
+    }
+}
+
+/// Where mail should be sent.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MailConfig {
+    Log,
+    File { directory: PathBuf },
+}
+
+impl Default for MailConfig {
+    fn default() -> Self {
+        MailConfig::Log
+    }
+}
+
+impl MailConfig {
+    pub fn sender(&self) -> Box<dyn MailSender> {
+        match self {
+            MailConfig::Log => Box::new(LogMailSender),
+            MailConfig::File { directory } => Box::new(FileMailSender::new(directory.clone())),
+        }
+    }
+}
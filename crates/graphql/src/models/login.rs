@@ -0,0 +1,40 @@
+use async_graphql::Object;
+use azerust_game::audit;
+use chrono::{DateTime, Utc};
+
+pub struct LoginAttempt(pub audit::LoginAttempt);
+
+#[Object]
+impl LoginAttempt {
+    async fn kind(&self) -> String {
+        self.0.kind.to_string()
+    }
+    async fn username(&self) -> &str {
+        &self.0.username
+    }
+    async fn account_id(&self) -> Option<u32> {
+        self.0.account.map(|a| a.0)
+    }
+    async fn ip(&self) -> String {
+        self.0.ip.to_string()
+    }
+    async fn build(&self) -> u16 {
+        self.0.build
+    }
+    async fn os(&self) -> &str {
+        &self.0.os
+    }
+    async fn country(&self) -> &str {
+        &self.0.country
+    }
+    /// The code returned to the client, where 0 is success.
+    async fn result(&self) -> u8 {
+        self.0.result
+    }
+    async fn success(&self) -> bool {
+        self.0.succeeded()
+    }
+    async fn time(&self) -> &DateTime<Utc> {
+        &self.0.time
+    }
+}
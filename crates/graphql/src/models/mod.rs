@@ -1,5 +1,7 @@
 mod account;
+mod login;
 mod realm;
 
 pub use account::Account;
+pub use login::LoginAttempt;
 pub use realm::Realm;
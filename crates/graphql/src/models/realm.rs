@@ -23,4 +23,10 @@ impl Realm {
     async fn timezone(&self) -> u8 {
         self.0.timezone
     }
+    async fn flags(&self) -> Vec<String> {
+        self.0.flags.iter().map(|f| format!("{f:?}")).collect()
+    }
+    async fn online(&self) -> bool {
+        self.0.is_online()
+    }
 }
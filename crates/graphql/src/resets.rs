@@ -0,0 +1,97 @@
+//! resets
+//!
+//! One-time tokens used to reset a password.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use azerust_game::accounts::AccountId;
+use rand::Rng;
+
+/// How long a reset token stays valid.
+pub const RESET_TOKEN_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Outstanding password reset tokens. Tokens are kept in memory,
+/// so any that are pending are lost when the server restarts.
+#[derive(Debug)]
+pub struct PasswordResets {
+    ttl: Duration,
+    tokens: Mutex<HashMap<String, (AccountId, Instant)>>,
+}
+
+impl Default for PasswordResets {
+    fn default() -> Self {
+        Self::new(RESET_TOKEN_TTL)
+    }
+}
+
+impl PasswordResets {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            tokens: Default::default(),
+        }
+    }
+
+    /// Issue a new token for the account, replacing any existing one.
+    pub fn issue(&self, id: AccountId) -> String {
+        let token = {
+            let mut rng = rand::thread_rng();
+            (0..32)
+                .map(|_| format!("{:02x}", rng.gen::<u8>()))
+                .collect()
+        };
+
+        let now = Instant::now();
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        tokens.retain(|_, (account, expiry)| *account != id && *expiry > now);
+        tokens.insert(token.clone(), (id, now + self.ttl));
+        token
+    }
+
+    /// Consume a token, returning the account it was issued for if
+    /// it is valid. A token can only be redeemed once.
+    pub fn redeem(&self, token: &str) -> Option<AccountId> {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        match tokens.remove(token) {
+            Some((id, expiry)) if expiry > Instant::now() => Some(id),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use azerust_game::accounts::AccountId;
+
+    use super::PasswordResets;
+
+    #[test]
+    pub fn token_is_single_use() {
+        let resets = PasswordResets::default();
+        let token = resets.issue(AccountId(1));
+        assert_eq!(resets.redeem(&token), Some(AccountId(1)));
+        assert_eq!(resets.redeem(&token), None);
+    }
+
+    #[test]
+    pub fn reissue_replaces_token() {
+        let resets = PasswordResets::default();
+        let first = resets.issue(AccountId(1));
+        let second = resets.issue(AccountId(1));
+        assert_eq!(resets.redeem(&first), None);
+        assert_eq!(resets.redeem(&second), Some(AccountId(1)));
+    }
+
+    #[test]
+    pub fn expired_token_is_rejected() {
+        let resets = PasswordResets::new(Duration::ZERO);
+        let token = resets.issue(AccountId(1));
+        assert_eq!(resets.redeem(&token), None);
+    }
+}
@@ -1,10 +1,20 @@
-use std::{marker::PhantomData, time::Duration};
+use std::{
+    marker::PhantomData,
+    time::{Duration, SystemTime},
+};
 
 use async_graphql::{Context, FieldResult, InputObject, Object};
-use azerust_game::accounts::{AccountId, AccountService};
+use azerust_game::{
+    accounts::{AccountId, AccountOpError, AccountService},
+    realms::{RealmId, RealmList},
+};
+use tracing::error;
 
-pub struct Mutation<T> {
-    marker: PhantomData<T>,
+use crate::{CharacterServices, Mail, MailSender, PasswordResets};
+
+pub struct Mutation<A, R> {
+    account: PhantomData<A>,
+    realm: PhantomData<R>,
 }
 
 #[derive(InputObject)]
@@ -14,22 +24,24 @@ struct UserCreate {
     password: String,
 }
 
-impl<T> Mutation<T> {
+impl<A, R> Mutation<A, R> {
     pub fn new() -> Self {
         Self {
-            marker: PhantomData,
+            account: PhantomData,
+            realm: PhantomData,
         }
     }
 }
 
 #[Object]
-impl<T> Mutation<T>
+impl<A, R> Mutation<A, R>
 where
-    T: 'static + AccountService + Send + Sync,
+    A: 'static + AccountService + Send + Sync,
+    R: 'static + RealmList + Send + Sync,
 {
     /// Creates a new user account.
     async fn register_user(&self, ctx: &Context<'_>, user: UserCreate) -> FieldResult<u32> {
-        let service = ctx.data::<T>()?;
+        let service = ctx.data::<A>()?;
         let id = service
             .create_account(&user.username, &user.password, &user.email)
             .await?;
@@ -43,20 +55,170 @@ where
         duration: Option<BanDuration>,
         reason: Option<String>,
     ) -> FieldResult<bool> {
-        let service = ctx.data::<T>()?;
+        let duration = duration.map(|d| d.duration()).transpose()?;
+        let service = ctx.data::<A>()?;
         service
-            .set_ban(
-                AccountId(id),
-                "arlyon",
-                duration.map(|d| Duration::from_secs(d.days * 86400)),
-                reason.as_deref(),
-            )
+            .set_ban(AccountId(id), "arlyon", duration, reason.as_deref())
+            .await?;
+        Ok(true)
+    }
+
+    /// Prevents an account from chatting for the given number of minutes.
+    async fn set_mute(
+        &self,
+        ctx: &Context<'_>,
+        id: u32,
+        minutes: u64,
+        reason: Option<String>,
+    ) -> FieldResult<bool> {
+        let duration = minutes
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or("mute is too long")?;
+        let service = ctx.data::<A>()?;
+        service
+            .set_mute(AccountId(id), "graphql", duration, reason.as_deref())
+            .await?;
+        Ok(true)
+    }
+
+    async fn unmute(&self, ctx: &Context<'_>, id: u32) -> FieldResult<bool> {
+        let service = ctx.data::<A>()?;
+        service.unmute(AccountId(id)).await?;
+        Ok(true)
+    }
+
+    /// Bans the character with the given name on every realm,
+    /// returning the number of characters banned.
+    async fn set_character_ban(
+        &self,
+        ctx: &Context<'_>,
+        name: String,
+        duration: Option<BanDuration>,
+        reason: Option<String>,
+    ) -> FieldResult<u32> {
+        let duration = duration.map(|d| d.duration()).transpose()?;
+        let mut banned = 0;
+        for characters in ctx.data::<CharacterServices>()? {
+            if let Some(character) = characters.get_by_name(&name).await? {
+                characters
+                    .ban_character(
+                        character.id.try_into()?,
+                        "graphql",
+                        duration,
+                        reason.as_deref(),
+                    )
+                    .await?;
+                banned += 1;
+            }
+        }
+        Ok(banned)
+    }
+
+    /// Lifts the ban on the character with the given name on every
+    /// realm, returning the number of characters unbanned.
+    async fn unban_character(&self, ctx: &Context<'_>, name: String) -> FieldResult<u32> {
+        let mut unbanned = 0;
+        for characters in ctx.data::<CharacterServices>()? {
+            if let Some(character) = characters.get_by_name(&name).await? {
+                let id = character.id.try_into()?;
+                if characters.character_ban(id).await?.is_some() {
+                    characters.unban_character(id).await?;
+                    unbanned += 1;
+                }
+            }
+        }
+        Ok(unbanned)
+    }
+
+    /// Mails a one-time password reset token to the account's email
+    /// address. Always succeeds, so as not to reveal which accounts exist.
+    async fn request_password_reset(
+        &self,
+        ctx: &Context<'_>,
+        username: String,
+    ) -> FieldResult<bool> {
+        let service = ctx.data::<A>()?;
+        let account = match service
+            .get_by_username(&username.to_ascii_uppercase())
+            .await?
+        {
+            Some(a) => a,
+            None => return Ok(true),
+        };
+
+        let token = ctx.data::<PasswordResets>()?.issue(account.id);
+        let sent = ctx
+            .data::<Box<dyn MailSender>>()?
+            .send(&Mail {
+                to: account.email,
+                subject: "Password reset".to_string(),
+                body: format!(
+                    "A password reset was requested for {}. Use this token to choose a new password:\n\n{token}\n",
+                    account.username
+                ),
+            })
+            .await;
+
+        // failing here would reveal that the account exists
+        if let Err(e) = sent {
+            error!("could not send password reset for {}: {e}", account.id);
+        }
+
+        Ok(true)
+    }
+
+    /// Sets a new password using a token from `requestPasswordReset`.
+    async fn reset_password(
+        &self,
+        ctx: &Context<'_>,
+        token: String,
+        password: String,
+    ) -> FieldResult<bool> {
+        // check up front so that a bad password doesn't burn the token
+        if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong.into());
+        }
+
+        let id = ctx
+            .data::<PasswordResets>()?
+            .redeem(&token)
+            .ok_or("invalid or expired token")?;
+        ctx.data::<A>()?.set_password(id, &password).await?;
+        Ok(true)
+    }
+
+    /// Asks the server running a realm to shut down after the given
+    /// number of seconds, rescheduling any pending shutdown. The server
+    /// picks the request up within a few seconds.
+    async fn shutdown_realm(&self, ctx: &Context<'_>, id: u32, delay: u64) -> FieldResult<bool> {
+        let at = SystemTime::now()
+            .checked_add(Duration::from_secs(delay))
+            .ok_or("delay is too large")?;
+        ctx.data::<R>()?
+            .request_shutdown(RealmId(id), Some(at))
             .await?;
         Ok(true)
     }
+
+    /// Withdraws a shutdown requested with `shutdownRealm`.
+    async fn cancel_realm_shutdown(&self, ctx: &Context<'_>, id: u32) -> FieldResult<bool> {
+        ctx.data::<R>()?.request_shutdown(RealmId(id), None).await?;
+        Ok(true)
+    }
 }
 
 #[derive(InputObject)]
 struct BanDuration {
     days: u64,
 }
+
+impl BanDuration {
+    fn duration(&self) -> FieldResult<Duration> {
+        Ok(self
+            .days
+            .checked_mul(24 * 60 * 60)
+            .map(Duration::from_secs)
+            .ok_or("ban is too long")?)
+    }
+}
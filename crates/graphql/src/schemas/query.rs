@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 use async_graphql::{Context, FieldResult, Object};
 use azerust_game::{accounts::AccountService, realms::RealmList};
 
-use crate::models::{Account, Realm};
+use crate::models::{Account, LoginAttempt, Realm};
 
 pub struct Query<A, R> {
     account: PhantomData<A>,
@@ -37,6 +37,28 @@ where
         Ok(account.map(|a| Account(a)))
     }
 
+    /// Lists the most recent login attempts, optionally for a single user.
+    async fn get_login_history(
+        &self,
+        ctx: &Context<'_>,
+        username: Option<String>,
+        #[graphql(default = 20)] limit: u32,
+    ) -> FieldResult<Vec<LoginAttempt>> {
+        let service = ctx.data::<A>()?;
+        let id = match username {
+            Some(username) => match service
+                .get_by_username(&username.to_ascii_uppercase())
+                .await?
+            {
+                Some(account) => Some(account.id),
+                None => return Ok(vec![]),
+            },
+            None => None,
+        };
+        let history = service.login_history(id, limit).await?;
+        Ok(history.into_iter().map(LoginAttempt).collect())
+    }
+
     async fn get_realms(&self, ctx: &Context<'_>) -> FieldResult<Vec<Realm>> {
         let service = ctx.data::<R>()?;
         Ok(service.realms().await.into_iter().map(Realm).collect())
@@ -0,0 +1,463 @@
+use std::{cmp::Reverse, collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::{
+        Account, AccountFetchError, AccountId, AccountOpError, AccountService, Ban, ConnectToken,
+        LoginFailure, ReconnectToken, MAX_EXPANSION,
+    },
+    audit::LoginAttempt,
+    characters::CharacterService,
+    realms::RealmId,
+};
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+use wow_srp::WowSRPServer;
+
+use crate::active_ban;
+
+#[derive(Debug, Default)]
+struct State {
+    /// The accounts, sorted by id. Bans are kept separately.
+    accounts: Vec<Account>,
+    bans: HashMap<u32, Vec<Ban>>,
+    mutes: HashMap<u32, DateTime<Utc>>,
+    /// GM levels by account and realm, where `None` is every realm.
+    gm_levels: HashMap<(u32, Option<u32>), u8>,
+    logins: Vec<LoginAttempt>,
+    /// Logins that have been started but not completed, keyed
+    /// by the public key of the server for that login.
+    pending: HashMap<[u8; 32], u32>,
+}
+
+impl State {
+    fn account(&self, id: AccountId) -> Result<&Account, AccountOpError> {
+        self.accounts
+            .iter()
+            .find(|a| a.id == id)
+            .ok_or(AccountOpError::InvalidAccount(id))
+    }
+
+    fn account_mut(&mut self, id: AccountId) -> Result<&mut Account, AccountOpError> {
+        self.accounts
+            .iter_mut()
+            .find(|a| a.id == id)
+            .ok_or(AccountOpError::InvalidAccount(id))
+    }
+
+    /// Accounts are stored without a ban status, so fill it in.
+    fn with_ban(&self, account: &Account) -> Account {
+        Account {
+            ban_status: active_ban(self.bans.get(&account.id.0).into_iter().flatten()),
+            ..account.clone()
+        }
+    }
+}
+
+/// An account service that keeps everything in memory.
+#[derive(Clone, Default)]
+pub struct MemoryAccountService {
+    state: Arc<RwLock<State>>,
+    /// The character services of every realm, used to clean
+    /// up characters when an account is deleted.
+    characters: Vec<Arc<dyn CharacterService + Send + Sync>>,
+}
+
+impl fmt::Debug for MemoryAccountService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryAccountService")
+            .field("state", &self.state)
+            .field("characters", &self.characters.len())
+            .finish()
+    }
+}
+
+impl MemoryAccountService {
+    /// Create a service seeded with the given accounts. If an account
+    /// has a ban status, a matching ban is added for it.
+    pub fn new(accounts: Vec<Account>) -> Self {
+        let mut state = State::default();
+        for account in accounts {
+            if let Some(status) = account.ban_status {
+                state.bans.entry(account.id.0).or_default().push(Ban {
+                    banned_at: Utc::now(),
+                    expires: status.expires(),
+                    author: "fixture".to_string(),
+                    reason: "".to_string(),
+                    active: true,
+                });
+            }
+            state.accounts.push(Account {
+                ban_status: None,
+                ..account
+            });
+        }
+        state.accounts.sort_by_key(|a| a.id.0);
+
+        Self {
+            state: Arc::new(RwLock::new(state)),
+            characters: vec![],
+        }
+    }
+
+    /// Set the character services of the realms that should be
+    /// cleaned up when an account is deleted.
+    pub fn with_characters(self, characters: Vec<Arc<dyn CharacterService + Send + Sync>>) -> Self {
+        Self { characters, ..self }
+    }
+}
+
+#[async_trait]
+impl AccountService for MemoryAccountService {
+    async fn list_account(&self) -> Result<Vec<Account>, AccountFetchError> {
+        let state = self.state.read().await;
+        Ok(state.accounts.iter().map(|a| state.with_ban(a)).collect())
+    }
+
+    async fn create_account(
+        &self,
+        username: &str,
+        password: &str,
+        email: &str,
+    ) -> Result<AccountId, AccountOpError> {
+        if username.len() > 16 {
+            return Err(AccountOpError::UsernameTooLong);
+        } else if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        // convert to uppercase
+        let username = username.to_ascii_uppercase();
+        let password = password.to_ascii_uppercase();
+
+        let mut state = self.state.write().await;
+        if state.accounts.iter().any(|a| a.username == username) {
+            return Err(AccountOpError::PersistError(format!(
+                "username {username} is taken"
+            )));
+        }
+
+        let (verifier, salt) = WowSRPServer::register(&username, &password);
+        let id = AccountId(state.accounts.last().map(|a| a.id.0 + 1).unwrap_or(1));
+        state.accounts.push(Account {
+            id,
+            username,
+            email: email.to_string(),
+            ban_status: None,
+            salt,
+            verifier,
+            session_key: None,
+            joindate: Utc::now(),
+            last_login: None,
+            online: 0,
+            locked: 0,
+            expansion: MAX_EXPANSION,
+        });
+
+        Ok(id)
+    }
+
+    async fn delete_account(&self, id: AccountId) -> Result<(), AccountOpError> {
+        self.state.read().await.account(id)?;
+
+        for characters in &self.characters {
+            characters
+                .delete_by_account(id)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        }
+
+        let mut state = self.state.write().await;
+        state.accounts.retain(|a| a.id != id);
+        state.bans.remove(&id.0);
+        state.mutes.remove(&id.0);
+        state.gm_levels.retain(|(account, _), _| *account != id.0);
+
+        Ok(())
+    }
+
+    async fn get(&self, id: AccountId) -> Result<Account, AccountOpError> {
+        let state = self.state.read().await;
+        Ok(state.with_ban(state.account(id)?))
+    }
+
+    async fn get_by_username(&self, username: &str) -> Result<Option<Account>, AccountOpError> {
+        let state = self.state.read().await;
+        Ok(state
+            .accounts
+            .iter()
+            .find(|a| a.username.eq_ignore_ascii_case(username))
+            .map(|a| state.with_ban(a)))
+    }
+
+    async fn initiate_login(&self, username: &str) -> Result<ConnectToken, LoginFailure> {
+        let account = self
+            .get_by_username(username)
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?
+            .ok_or(LoginFailure::UnknownAccount)?;
+
+        if let Some(status) = account.ban_status {
+            return Err(match status.expires() {
+                Some(until) => LoginFailure::Suspended { until },
+                None => LoginFailure::Banned,
+            });
+        }
+
+        let token = ConnectToken::new(&account.username, account.salt, account.verifier);
+        self.state
+            .write()
+            .await
+            .pending
+            .insert(*token.get_b_pub(), account.id.0);
+
+        Ok(token)
+    }
+
+    async fn complete_login(
+        &self,
+        token: &ConnectToken,
+        public_key: &[u8; 32],
+        proof: &[u8; 20],
+    ) -> Result<[u8; 20], LoginFailure> {
+        let mut state = self.state.write().await;
+        let id = state
+            .pending
+            .remove(token.get_b_pub())
+            .ok_or(LoginFailure::DatabaseError)?;
+
+        let (server_proof, session_key) = token.accept(public_key, proof)?;
+
+        let account = state
+            .account_mut(AccountId(id))
+            .map_err(|_| LoginFailure::UnknownAccount)?;
+        account.session_key = Some(session_key);
+        account.last_login = Some(Utc::now());
+
+        Ok(server_proof)
+    }
+
+    async fn initiate_relogin(&self, username: &str) -> Result<ReconnectToken, LoginFailure> {
+        let account = self
+            .get_by_username(username)
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?
+            .ok_or(LoginFailure::UnknownAccount)?;
+
+        let session_key = account.session_key.ok_or(LoginFailure::UnknownAccount)?;
+
+        Ok(ReconnectToken::new(account, session_key))
+    }
+
+    async fn complete_relogin(
+        &self,
+        token: &ReconnectToken,
+        proof_data: &[u8; 16],
+        client_proof: &[u8; 20],
+    ) -> Result<[u8; 20], LoginFailure> {
+        token
+            .accept(proof_data, client_proof)
+            .map(|_| client_proof.to_owned())
+    }
+
+    async fn set_ban(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        let mut state = self.state.write().await;
+        state.account(id)?;
+
+        let banned_at = Utc::now();
+        let expires = duration.map(|d| {
+            chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| banned_at.checked_add_signed(d))
+                .unwrap_or(chrono::MAX_DATETIME)
+        });
+
+        state.bans.entry(id.0).or_default().push(Ban {
+            banned_at,
+            expires,
+            author: author.to_string(),
+            reason: reason.unwrap_or("").to_string(),
+            active: true,
+        });
+
+        Ok(())
+    }
+
+    async fn unban(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let mut state = self.state.write().await;
+        let mut lifted = false;
+        for ban in state.bans.get_mut(&id.0).into_iter().flatten() {
+            lifted |= ban.active;
+            ban.active = false;
+        }
+
+        if lifted {
+            Ok(())
+        } else {
+            Err(AccountOpError::NotBanned(id))
+        }
+    }
+
+    async fn bans(&self, id: AccountId) -> Result<Vec<Ban>, AccountOpError> {
+        let state = self.state.read().await;
+        state.account(id)?;
+
+        let mut bans = state.bans.get(&id.0).cloned().unwrap_or_default();
+        bans.sort_by_key(|b| Reverse(b.banned_at));
+        Ok(bans)
+    }
+
+    async fn expire_bans(&self) -> Result<u64, AccountOpError> {
+        let now = Utc::now();
+        let mut expired = 0;
+        for ban in self.state.write().await.bans.values_mut().flatten() {
+            if ban.active && ban.expires.map(|e| e <= now).unwrap_or(false) {
+                ban.active = false;
+                expired += 1;
+            }
+        }
+        Ok(expired)
+    }
+
+    async fn set_mute(
+        &self,
+        id: AccountId,
+        _author: &str,
+        duration: Duration,
+        _reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        let mut state = self.state.write().await;
+        state.account(id)?;
+
+        let until = chrono::Duration::from_std(duration)
+            .ok()
+            .and_then(|d| Utc::now().checked_add_signed(d))
+            .unwrap_or(chrono::MAX_DATETIME);
+        state.mutes.insert(id.0, until);
+
+        Ok(())
+    }
+
+    async fn unmute(&self, id: AccountId) -> Result<(), AccountOpError> {
+        if self.muted_until(id).await?.is_none() {
+            return Err(AccountOpError::NotMuted(id));
+        }
+
+        self.state.write().await.mutes.remove(&id.0);
+        Ok(())
+    }
+
+    async fn muted_until(&self, id: AccountId) -> Result<Option<DateTime<Utc>>, AccountOpError> {
+        let state = self.state.read().await;
+        state.account(id)?;
+        Ok(state
+            .mutes
+            .get(&id.0)
+            .copied()
+            .filter(|&until| until > Utc::now()))
+    }
+
+    async fn record_login(&self, attempt: &LoginAttempt) -> Result<(), AccountOpError> {
+        let mut state = self.state.write().await;
+        let account = state
+            .accounts
+            .iter()
+            .find(|a| a.username.eq_ignore_ascii_case(&attempt.username))
+            .map(|a| a.id);
+        state.logins.push(LoginAttempt {
+            account,
+            ..attempt.clone()
+        });
+        Ok(())
+    }
+
+    async fn login_history(
+        &self,
+        id: Option<AccountId>,
+        limit: u32,
+    ) -> Result<Vec<LoginAttempt>, AccountOpError> {
+        let state = self.state.read().await;
+        // newest first, breaking ties by the order they were recorded in
+        let mut logins = state
+            .logins
+            .iter()
+            .rev()
+            .filter(|l| id.is_none() || l.account == id)
+            .cloned()
+            .collect::<Vec<_>>();
+        logins.sort_by_key(|l| Reverse(l.time));
+        logins.truncate(limit as usize);
+        Ok(logins)
+    }
+
+    async fn prune_login_history(&self, before: DateTime<Utc>) -> Result<u64, AccountOpError> {
+        let mut state = self.state.write().await;
+        let count = state.logins.len();
+        state.logins.retain(|l| l.time >= before);
+        Ok((count - state.logins.len()) as u64)
+    }
+
+    async fn set_password(&self, id: AccountId, password: &str) -> Result<(), AccountOpError> {
+        if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        let mut state = self.state.write().await;
+        let account = state.account_mut(id)?;
+        let (verifier, salt) =
+            WowSRPServer::register(&account.username, &password.to_ascii_uppercase());
+
+        // clearing the session key forces the client to log in again
+        account.salt = salt;
+        account.verifier = verifier;
+        account.session_key = None;
+
+        Ok(())
+    }
+
+    async fn set_email(&self, id: AccountId, email: &str) -> Result<(), AccountOpError> {
+        self.state.write().await.account_mut(id)?.email = email.to_string();
+        Ok(())
+    }
+
+    async fn set_gm_level(
+        &self,
+        id: AccountId,
+        level: u8,
+        realm: Option<RealmId>,
+    ) -> Result<(), AccountOpError> {
+        let mut state = self.state.write().await;
+        state.account(id)?;
+
+        let key = (id.0, realm.map(|r| r.0));
+        if level == 0 && realm.is_none() {
+            state.gm_levels.retain(|(account, _), _| *account != id.0);
+        } else if level == 0 {
+            state.gm_levels.remove(&key);
+        } else {
+            state.gm_levels.insert(key, level);
+        }
+
+        Ok(())
+    }
+
+    async fn set_expansion(&self, id: AccountId, expansion: u8) -> Result<(), AccountOpError> {
+        if expansion > MAX_EXPANSION {
+            return Err(AccountOpError::InvalidExpansion(expansion));
+        }
+
+        self.state.write().await.account_mut(id)?.expansion = expansion;
+        Ok(())
+    }
+
+    async fn set_locked(&self, id: AccountId, locked: bool) -> Result<(), AccountOpError> {
+        self.state.write().await.account_mut(id)?.locked = locked as u8;
+        Ok(())
+    }
+}
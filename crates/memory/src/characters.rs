@@ -0,0 +1,499 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::{AccountId, Ban, BanStatus},
+    characters::{
+        AccountData, AccountDataCache, AccountDataType, AtLoginFlags, Character, CharacterCreate,
+        CharacterId, CharacterService, CharacterServiceError, DeletedCharacter, DualDataCache,
+        EquippedItem, EQUIPMENT_SLOTS,
+    },
+    EntityType,
+};
+use chrono::{DateTime, Utc};
+use enumflags2::BitFlags;
+use tokio::sync::RwLock;
+
+use crate::active_ban;
+
+#[derive(Debug, Default)]
+struct State {
+    characters: Vec<Character>,
+    bans: HashMap<CharacterId, Vec<Ban>>,
+    equipment: HashMap<CharacterId, Vec<EquippedItem>>,
+    /// Account data by account and type.
+    account_data: HashMap<(u32, u8), AccountDataCache>,
+    /// Per character account data by character and type.
+    character_data: HashMap<(CharacterId, u8), AccountDataCache>,
+    /// The tutorials each account has seen.
+    tutorials: HashMap<u32, [u32; 8]>,
+    /// Where soft deleted characters came from, and when they were deleted.
+    deleted: HashMap<CharacterId, (AccountId, String, DateTime<Utc>)>,
+}
+
+impl State {
+    fn character(&self, id: CharacterId) -> Result<&Character, CharacterServiceError> {
+        self.characters
+            .iter()
+            .find(|c| CharacterId::try_from(c.id).ok() == Some(id))
+            .ok_or(CharacterServiceError::NoSuchCharacter(id))
+    }
+
+    fn character_mut(&mut self, id: CharacterId) -> Result<&mut Character, CharacterServiceError> {
+        self.characters
+            .iter_mut()
+            .find(|c| CharacterId::try_from(c.id).ok() == Some(id))
+            .ok_or(CharacterServiceError::NoSuchCharacter(id))
+    }
+
+    /// Remove the characters that match a predicate along with
+    /// everything they own, returning how many were removed.
+    fn remove_characters(&mut self, remove: impl Fn(&Self, CharacterId) -> bool) -> usize {
+        let ids = self
+            .characters
+            .iter()
+            .filter_map(|c| CharacterId::try_from(c.id).ok())
+            .filter(|id| remove(self, *id))
+            .collect::<Vec<_>>();
+        self.characters
+            .retain(|c| !matches!(CharacterId::try_from(c.id), Ok(id) if ids.contains(&id)));
+        for id in &ids {
+            self.bans.remove(id);
+            self.equipment.remove(id);
+            self.deleted.remove(id);
+            self.character_data
+                .retain(|(character, _), _| character != id);
+        }
+        ids.len()
+    }
+}
+
+/// A character service that keeps everything in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryCharacterService {
+    state: Arc<RwLock<State>>,
+}
+
+impl MemoryCharacterService {
+    /// Create a service seeded with the given characters.
+    pub fn new(characters: Vec<Character>) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(State {
+                characters,
+                ..Default::default()
+            })),
+        }
+    }
+}
+
+#[async_trait]
+impl CharacterService for MemoryCharacterService {
+    async fn get(&self, id: CharacterId) -> Result<Character, CharacterServiceError> {
+        self.state.read().await.character(id).cloned()
+    }
+
+    async fn get_by_account(&self, id: AccountId) -> Result<Vec<Character>, CharacterServiceError> {
+        Ok(self
+            .state
+            .read()
+            .await
+            .characters
+            .iter()
+            .filter(|c| c.account == id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Character>, CharacterServiceError> {
+        Ok(self
+            .state
+            .read()
+            .await
+            .characters
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+            .cloned())
+    }
+
+    async fn account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+    ) -> Result<AccountData, CharacterServiceError> {
+        let state = self.state.read().await;
+        let get = |kind: AccountDataType| {
+            if kind.is_global() {
+                state.account_data.get(&(id.0, kind.into()))
+            } else {
+                character.and_then(|c| state.character_data.get(&(c, kind.into())))
+            }
+            .cloned()
+        };
+
+        Ok(AccountData {
+            config: DualDataCache {
+                global: get(AccountDataType::GlobalConfig),
+                per_char: get(AccountDataType::CharacterConfig),
+            },
+            bindings: DualDataCache {
+                global: get(AccountDataType::GlobalBindings),
+                per_char: get(AccountDataType::CharacterBindings),
+            },
+            macros: DualDataCache {
+                global: get(AccountDataType::GlobalMacros),
+                per_char: get(AccountDataType::CharacterMacros),
+            },
+            per_char_layout: get(AccountDataType::CharacterLayout),
+            per_char_chat: get(AccountDataType::CharacterChat),
+        })
+    }
+
+    async fn set_account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    ) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        if kind.is_global() {
+            state.account_data.insert((id.0, kind.into()), data);
+        } else {
+            let character = character.ok_or_else(|| {
+                CharacterServiceError::PersistError(format!("{kind:?} is kept per character"))
+            })?;
+            state.character_data.insert((character, kind.into()), data);
+        }
+        Ok(())
+    }
+
+    async fn tutorials(&self, id: AccountId) -> Result<[u32; 8], CharacterServiceError> {
+        Ok(self
+            .state
+            .read()
+            .await
+            .tutorials
+            .get(&id.0)
+            .copied()
+            .unwrap_or_default())
+    }
+
+    async fn set_tutorials(
+        &self,
+        id: AccountId,
+        tutorials: [u32; 8],
+    ) -> Result<(), CharacterServiceError> {
+        self.state.write().await.tutorials.insert(id.0, tutorials);
+        Ok(())
+    }
+
+    async fn count_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        Ok(self
+            .state
+            .read()
+            .await
+            .characters
+            .iter()
+            .filter(|c| c.account == id)
+            .count())
+    }
+
+    async fn name_available(&self, name: &str) -> Result<bool, CharacterServiceError> {
+        Ok(self.get_by_name(name).await?.is_none())
+    }
+
+    async fn equipment(&self, id: CharacterId) -> Result<Vec<EquippedItem>, CharacterServiceError> {
+        let state = self.state.read().await;
+        state.character(id)?;
+        Ok(state.equipment.get(&id).cloned().unwrap_or_default())
+    }
+
+    async fn max_guid(&self, kind: EntityType) -> Result<u32, CharacterServiceError> {
+        Ok(match kind {
+            EntityType::Player => self
+                .state
+                .read()
+                .await
+                .characters
+                .iter()
+                .map(|c| c.id.low())
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        })
+    }
+
+    async fn create_character(
+        &self,
+        account: AccountId,
+        create: CharacterCreate,
+    ) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        if state
+            .characters
+            .iter()
+            .any(|c| c.name.eq_ignore_ascii_case(&create.name))
+        {
+            return Err(CharacterServiceError::PersistError(format!(
+                "name {} is taken",
+                create.name
+            )));
+        }
+        if state.characters.iter().any(|c| c.id == create.id) {
+            return Err(CharacterServiceError::PersistError(format!(
+                "id {:?} is taken",
+                create.id
+            )));
+        }
+
+        if let Ok(id) = CharacterId::try_from(create.id) {
+            let equipment = create
+                .items
+                .iter()
+                .filter(|i| i.slot < EQUIPMENT_SLOTS)
+                .map(|i| EquippedItem {
+                    slot: i.slot,
+                    entry: i.entry,
+                    enchant: 0,
+                })
+                .collect();
+            state.equipment.insert(id, equipment);
+        }
+
+        state.characters.push(Character {
+            id: create.id,
+            account,
+            name: create.name,
+            level: 1,
+            race: create.race,
+            class: create.class,
+            gender: create.gender,
+            skin_color: create.skin_color,
+            face: create.face,
+            hair_style: create.hair_style,
+            hair_color: create.hair_color,
+            facial_style: create.facial_style,
+            zone: create.zone,
+            map: create.map,
+            position_x: create.position_x,
+            position_y: create.position_y,
+            position_z: create.position_z,
+            player_flags: BitFlags::empty(),
+            at_login: AtLoginFlags::FirstLogin.into(),
+            guild: 0,
+            pet: None,
+        });
+
+        Ok(())
+    }
+
+    async fn delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        state.character(id)?;
+        state.remove_characters(|_, c| c == id);
+        Ok(())
+    }
+
+    async fn delete_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        let mut state = self.state.write().await;
+        let deleted = state.remove_characters(|state, c| {
+            matches!(state.character(c), Ok(character) if character.account == id)
+                || matches!(state.deleted.get(&c), Some((account, _, _)) if *account == id)
+        });
+        state
+            .account_data
+            .retain(|(account, _), _| *account != id.0);
+        state.tutorials.remove(&id.0);
+
+        Ok(deleted)
+    }
+
+    async fn soft_delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        if state.deleted.contains_key(&id) {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        let character = state.character_mut(id)?;
+        let account = std::mem::replace(&mut character.account, AccountId(0));
+        let name = std::mem::take(&mut character.name);
+        character.guild = 0;
+        state.deleted.insert(id, (account, name, Utc::now()));
+        Ok(())
+    }
+
+    async fn deleted_characters(&self) -> Result<Vec<DeletedCharacter>, CharacterServiceError> {
+        let state = self.state.read().await;
+        let mut deleted = state
+            .deleted
+            .iter()
+            .map(|(id, (account, name, deleted))| {
+                let character = state.character(*id)?;
+                Ok(DeletedCharacter {
+                    id: character.id,
+                    account: *account,
+                    name: name.clone(),
+                    level: character.level,
+                    race: character.race,
+                    class: character.class,
+                    deleted: *deleted,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        deleted.sort_by_key(|c| (c.deleted, c.id.low()));
+        Ok(deleted)
+    }
+
+    async fn restore_character(
+        &self,
+        id: CharacterId,
+        name: &str,
+    ) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        if state
+            .characters
+            .iter()
+            .any(|c| c.name.eq_ignore_ascii_case(name))
+        {
+            return Err(CharacterServiceError::PersistError(format!(
+                "name {name} is taken"
+            )));
+        }
+        let (account, _, _) = state
+            .deleted
+            .remove(&id)
+            .ok_or(CharacterServiceError::NoSuchCharacter(id))?;
+        let character = state.character_mut(id)?;
+        character.account = account;
+        character.name = name.to_string();
+        Ok(())
+    }
+
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<usize, CharacterServiceError> {
+        let mut state = self.state.write().await;
+        Ok(state.remove_characters(
+            |state, c| matches!(state.deleted.get(&c), Some((_, _, deleted)) if *deleted < before),
+        ))
+    }
+
+    async fn save_character(&self, character: &Character) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        let saved = state
+            .characters
+            .iter_mut()
+            .find(|c| c.id == character.id)
+            .ok_or_else(|| match CharacterId::try_from(character.id) {
+                Ok(id) => CharacterServiceError::NoSuchCharacter(id),
+                Err(e) => CharacterServiceError::PersistError(e.to_string()),
+            })?;
+
+        saved.level = character.level;
+        saved.zone = character.zone;
+        saved.map = character.map;
+        saved.position_x = character.position_x;
+        saved.position_y = character.position_y;
+        saved.position_z = character.position_z;
+
+        Ok(())
+    }
+
+    async fn set_at_login(
+        &self,
+        id: CharacterId,
+        flags: BitFlags<AtLoginFlags>,
+    ) -> Result<(), CharacterServiceError> {
+        self.state.write().await.character_mut(id)?.at_login = flags;
+        Ok(())
+    }
+
+    async fn customize_character(
+        &self,
+        character: &Character,
+    ) -> Result<(), CharacterServiceError> {
+        let id = CharacterId::try_from(character.id)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        let mut state = self.state.write().await;
+        if state
+            .characters
+            .iter()
+            .any(|c| c.id != character.id && c.name.eq_ignore_ascii_case(&character.name))
+        {
+            return Err(CharacterServiceError::PersistError(format!(
+                "name {} is taken",
+                character.name
+            )));
+        }
+
+        let saved = state.character_mut(id)?;
+        saved.name = character.name.clone();
+        saved.race = character.race;
+        saved.gender = character.gender;
+        saved.skin_color = character.skin_color;
+        saved.face = character.face;
+        saved.hair_style = character.hair_style;
+        saved.hair_color = character.hair_color;
+        saved.facial_style = character.facial_style;
+        saved.at_login = character.at_login;
+
+        Ok(())
+    }
+
+    async fn ban_character(
+        &self,
+        id: CharacterId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        state.character(id)?;
+
+        let banned_at = Utc::now();
+        let expires = match duration {
+            Some(d) => Some(
+                chrono::Duration::from_std(d)
+                    .ok()
+                    .and_then(|d| banned_at.checked_add_signed(d))
+                    .ok_or_else(|| {
+                        CharacterServiceError::PersistError(format!(
+                            "ban duration {d:?} is too long"
+                        ))
+                    })?,
+            ),
+            None => None,
+        };
+
+        state.bans.entry(id).or_default().push(Ban {
+            banned_at,
+            expires,
+            author: author.to_string(),
+            reason: reason.unwrap_or("").to_string(),
+            active: true,
+        });
+
+        Ok(())
+    }
+
+    async fn unban_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut state = self.state.write().await;
+        let mut lifted = false;
+        for ban in state.bans.get_mut(&id).into_iter().flatten() {
+            lifted |= ban.active;
+            ban.active = false;
+        }
+
+        if lifted {
+            Ok(())
+        } else {
+            Err(CharacterServiceError::NotBanned(id))
+        }
+    }
+
+    async fn character_ban(
+        &self,
+        id: CharacterId,
+    ) -> Result<Option<BanStatus>, CharacterServiceError> {
+        Ok(active_ban(
+            self.state.read().await.bans.get(&id).into_iter().flatten(),
+        ))
+    }
+}
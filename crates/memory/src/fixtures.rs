@@ -0,0 +1,78 @@
+//! fixtures
+//!
+//! Helpers for building the accounts, realms and characters
+//! that the in-memory services are seeded with.
+
+use azerust_game::{
+    accounts::{Account, AccountId, MAX_EXPANSION},
+    characters::Character,
+    realms::{Realm, RealmId, RealmType},
+    EntityType, WowId,
+};
+use chrono::Utc;
+use enumflags2::BitFlags;
+use wow_srp::WowSRPServer;
+
+/// An account that can log in with the given username and password.
+pub fn account(id: u32, username: &str, password: &str) -> Account {
+    let username = username.to_ascii_uppercase();
+    let (verifier, salt) = WowSRPServer::register(&username, &password.to_ascii_uppercase());
+    Account {
+        id: AccountId(id),
+        email: format!("{}@example.com", username.to_ascii_lowercase()),
+        username,
+        ban_status: None,
+        salt,
+        verifier,
+        session_key: None,
+        joindate: Utc::now(),
+        last_login: None,
+        online: 0,
+        locked: 0,
+        expansion: MAX_EXPANSION,
+    }
+}
+
+/// An online realm listening on the local machine.
+pub fn realm(id: u32, name: &str, port: u16) -> Realm {
+    Realm {
+        id: RealmId(id),
+        name: name.to_string(),
+        realm_type: RealmType::Normal,
+        build: 12340,
+        external_address: "127.0.0.1".to_string(),
+        local_address: "127.0.0.1".to_string(),
+        local_subnet_mask: "255.255.255.0".to_string(),
+        port,
+        flags: BitFlags::empty(),
+        timezone: 1,
+        population: 0.0,
+    }
+}
+
+/// A level 1 human warrior standing in Northshire.
+pub fn character(id: u32, account: AccountId, name: &str) -> Character {
+    Character {
+        id: WowId::new(EntityType::Player, id, 0),
+        account,
+        name: name.to_string(),
+        level: 1,
+        race: 1,
+        class: 1,
+        gender: 0,
+        skin_color: 0,
+        face: 0,
+        hair_style: 0,
+        hair_color: 0,
+        facial_style: 0,
+        zone: 12,
+        map: 0,
+        position_x: -8949.95,
+        position_y: -132.493,
+        position_z: 83.5312,
+        player_flags: BitFlags::empty(),
+        at_login: BitFlags::empty(),
+        guild: 0,
+        pet: None,
+    }
+}
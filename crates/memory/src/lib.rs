@@ -0,0 +1,28 @@
+//! memory
+//!
+//! In-memory implementations of the account, realm and character
+//! services. Nothing is persisted, which makes them a good fit for
+//! tests and for trying out a server without setting up a database.
+
+pub mod accounts;
+pub mod characters;
+pub mod fixtures;
+pub mod realms;
+
+pub use accounts::MemoryAccountService;
+use azerust_game::accounts::{Ban, BanStatus};
+pub use characters::MemoryCharacterService;
+pub use realms::MemoryRealmList;
+
+/// Get the ban currently in place from a list of bans, if any. If
+/// there are several, a permanent ban wins over the longest temporary one.
+pub(crate) fn active_ban<'a>(bans: impl IntoIterator<Item = &'a Ban>) -> Option<BanStatus> {
+    let now = chrono::Utc::now();
+    bans.into_iter()
+        .filter(|b| b.active && b.expires.map(|e| e > now).unwrap_or(true))
+        .map(|b| match b.expires {
+            Some(until) => BanStatus::Temporary { until },
+            None => BanStatus::Permanent,
+        })
+        .max_by_key(|b| (b.expires().is_none(), b.expires()))
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::AccountId,
+    realms::{Realm, RealmFlags, RealmId, RealmList, RealmListError, RealmRegistration},
+};
+use enumflags2::BitFlags;
+use tokio::sync::RwLock;
+
+/// A realmlist that keeps its realms in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryRealmList {
+    realms: Arc<RwLock<Vec<Realm>>>,
+    /// When each realm has been asked to shut down.
+    shutdowns: Arc<RwLock<HashMap<u32, SystemTime>>>,
+    /// The number of characters each account has on each realm.
+    characters: Arc<RwLock<HashMap<(u32, u32), u8>>>,
+}
+
+impl MemoryRealmList {
+    /// Create a realmlist seeded with the given realms.
+    pub fn new(mut realms: Vec<Realm>) -> Self {
+        realms.sort_by_key(|r| r.id.0);
+        Self {
+            realms: Arc::new(RwLock::new(realms)),
+            shutdowns: Default::default(),
+            characters: Default::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl RealmList for MemoryRealmList {
+    async fn realms(&self) -> Vec<Realm> {
+        self.realms.read().await.clone()
+    }
+
+    async fn register(&self, realm: &RealmRegistration) -> Result<(), RealmListError> {
+        let mut realms = self.realms.write().await;
+        let (flags, population) = realms
+            .iter()
+            .find(|r| r.id == realm.id)
+            .map(|r| (r.flags, r.population))
+            .unwrap_or((BitFlags::from(RealmFlags::Offline), 0.0));
+
+        realms.retain(|r| r.id != realm.id);
+        realms.push(Realm {
+            id: realm.id,
+            name: realm.name.clone(),
+            realm_type: realm.realm_type,
+            build: realm.build,
+            external_address: realm.external_address.clone(),
+            local_address: realm.local_address.clone(),
+            local_subnet_mask: realm.local_subnet_mask.clone(),
+            port: realm.port,
+            flags,
+            timezone: realm.timezone,
+            population,
+        });
+        realms.sort_by_key(|r| r.id.0);
+
+        Ok(())
+    }
+
+    async fn reload(&self) {}
+
+    async fn update_status(&self, status: Vec<(RealmId, bool)>) -> Result<(), RealmListError> {
+        let mut realms = self.realms.write().await;
+        for (id, online) in status {
+            if let Some(realm) = realms.iter_mut().find(|r| r.id == id) {
+                realm.flags.set(RealmFlags::Offline, !online);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn set_uptime(
+        &self,
+        _id: RealmId,
+        start: SystemTime,
+        _population: u32,
+    ) -> Result<(), RealmListError> {
+        let _: u32 = start
+            .duration_since(UNIX_EPOCH)
+            .expect("no time can be smaller than UNIX_EPOCH")
+            .as_secs()
+            .try_into()
+            .map_err(|_| RealmListError::StartTimeTooLarge)?;
+
+        SystemTime::now()
+            .duration_since(start)
+            .map_err(|_| RealmListError::StartTimeInFuture)?;
+
+        Ok(())
+    }
+
+    async fn request_shutdown(
+        &self,
+        id: RealmId,
+        at: Option<SystemTime>,
+    ) -> Result<(), RealmListError> {
+        let mut shutdowns = self.shutdowns.write().await;
+        match at {
+            Some(at) => shutdowns.insert(id.0, at),
+            None => shutdowns.remove(&id.0),
+        };
+        Ok(())
+    }
+
+    async fn requested_shutdown(&self, id: RealmId) -> Result<Option<SystemTime>, RealmListError> {
+        Ok(self.shutdowns.read().await.get(&id.0).copied())
+    }
+
+    async fn set_character_count(
+        &self,
+        id: RealmId,
+        account: AccountId,
+        count: u8,
+    ) -> Result<(), RealmListError> {
+        self.characters
+            .write()
+            .await
+            .insert((id.0, account.0), count);
+        Ok(())
+    }
+
+    async fn character_count(&self, account: AccountId) -> Result<usize, RealmListError> {
+        Ok(self
+            .characters
+            .read()
+            .await
+            .iter()
+            .filter(|((_, a), _)| *a == account.0)
+            .map(|(_, count)| *count as usize)
+            .sum())
+    }
+}
@@ -0,0 +1,16 @@
+-- Every attempt to log in to the auth server, used for auditing.
+CREATE TABLE IF NOT EXISTS `account_login_log` (
+  `id` bigint unsigned NOT NULL AUTO_INCREMENT,
+  `kind` tinyint unsigned NOT NULL COMMENT '0 = connect, 1 = reconnect',
+  `username` varchar(32) NOT NULL,
+  `account_id` int unsigned DEFAULT NULL,
+  `ip` varchar(45) NOT NULL,
+  `build` smallint unsigned NOT NULL,
+  `os` varchar(4) NOT NULL,
+  `country` varchar(4) NOT NULL,
+  `result` tinyint unsigned NOT NULL COMMENT 'the auth return code, 0 = success',
+  `time` bigint NOT NULL,
+  PRIMARY KEY (`id`),
+  KEY `idx_account_time` (`account_id`, `time`),
+  KEY `idx_time` (`time`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
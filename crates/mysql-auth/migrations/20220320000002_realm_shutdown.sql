@@ -0,0 +1,6 @@
+-- The time each realm's server has been asked to shut down at.
+CREATE TABLE IF NOT EXISTS `realm_shutdown` (
+  `realmid` int unsigned NOT NULL,
+  `shutdown_at` int unsigned NOT NULL COMMENT 'unix seconds',
+  PRIMARY KEY (`realmid`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4;
@@ -1,37 +1,164 @@
-use std::{convert::TryInto, time::Duration};
+use std::{
+    collections::HashMap, convert::TryInto, fmt, net::AddrParseError, sync::Arc, time::Duration,
+};
 
 use async_trait::async_trait;
 use azerust_game::{
     accounts::{
-        Account, AccountFetchError, AccountId, AccountOpError, AccountService, BanStatus,
-        ConnectToken, LoginFailure, ReconnectToken,
+        Account, AccountFetchError, AccountId, AccountOpError, AccountService, Ban, BanStatus,
+        ConnectToken, LoginFailure, ReconnectToken, MAX_EXPANSION,
     },
+    audit::{LoginAttempt, LoginKind},
+    characters::CharacterService,
+    realms::RealmId,
     types::Locale,
 };
-use chrono::Utc;
+use chrono::{DateTime, TimeZone, Utc};
 use sqlx::MySqlPool;
 use tracing::{debug, error, info, instrument, warn};
 use wow_srp::{Salt, Verifier, WowSRPServer};
 
-#[derive(Debug, Clone)]
+/// An account as it is stored in the database. The ban
+/// status is stored separately, in the `account_banned` table.
+struct AccountRow {
+    id: u32,
+    username: String,
+    email: String,
+    salt: Vec<u8>,
+    verifier: Vec<u8>,
+    session_key: Option<Vec<u8>>,
+    joindate: DateTime<Utc>,
+    last_login: Option<DateTime<Utc>>,
+    online: u8,
+    locked: u8,
+    expansion: u8,
+}
+
+impl AccountRow {
+    fn with_ban(self, ban_status: Option<BanStatus>) -> Result<Account, AccountOpError> {
+        Ok(Account {
+            id: AccountId(self.id),
+            username: self.username,
+            email: self.email,
+            ban_status,
+            salt: Salt(
+                self.salt
+                    .try_into()
+                    .map_err(|_| AccountOpError::PersistError("invalid salt".to_string()))?,
+            ),
+            verifier: Verifier(
+                self.verifier
+                    .try_into()
+                    .map_err(|_| AccountOpError::PersistError("invalid verifier".to_string()))?,
+            ),
+            session_key: self.session_key.and_then(|k| k.try_into().ok()),
+            joindate: self.joindate,
+            last_login: self.last_login,
+            online: self.online,
+            locked: self.locked,
+            expansion: self.expansion,
+        })
+    }
+}
+
+/// A unix timestamp read from the database as a date.
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, AccountOpError> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| AccountOpError::PersistError(format!("invalid timestamp {secs}")))
+}
+
+#[derive(Clone)]
 pub struct MySQLAccountService {
     pool: sqlx::MySqlPool,
+    /// The character services of every realm, used to clean
+    /// up characters when an account is deleted.
+    characters: Vec<Arc<dyn CharacterService + Send + Sync>>,
+}
+
+impl fmt::Debug for MySQLAccountService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MySQLAccountService")
+            .field("pool", &self.pool)
+            .field("characters", &self.characters.len())
+            .finish()
+    }
 }
 
 impl MySQLAccountService {
     pub fn new(pool: MySqlPool) -> Self {
         debug!("Starting accounts service");
-        Self { pool }
+        Self {
+            pool,
+            characters: vec![],
+        }
+    }
+
+    /// Set the character services of the realms that should be
+    /// cleaned up when an account is deleted.
+    pub fn with_characters(self, characters: Vec<Arc<dyn CharacterService + Send + Sync>>) -> Self {
+        Self { characters, ..self }
+    }
+
+    /// Check that an account with the given id exists.
+    async fn exists(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let exists = sqlx::query!("SELECT id FROM account WHERE id = ?", id.0,)
+            .fetch_optional(&self.pool)
+            .await
+            .map(|r| r.is_some())
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if exists {
+            Ok(())
+        } else {
+            Err(AccountOpError::InvalidAccount(id))
+        }
+    }
+
+    /// Get the ban currently in place on an account, if any. If there
+    /// are several, a permanent ban wins over the longest temporary one.
+    async fn active_ban(&self, id: AccountId) -> Result<Option<BanStatus>, sqlx::Error> {
+        let ban = sqlx::query!(
+            "SELECT bandate, unbandate FROM account_banned WHERE id = ? AND active = 1 AND (unbandate > UNIX_TIMESTAMP() OR unbandate = bandate) ORDER BY unbandate = bandate DESC, unbandate DESC LIMIT 1",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(ban.and_then(|b| BanStatus::from_timestamps(b.bandate.into(), b.unbandate.into())))
     }
 }
 
 #[async_trait]
 impl AccountService for MySQLAccountService {
     async fn list_account(&self) -> Result<Vec<Account>, AccountFetchError> {
-        sqlx::query_as!(Account, r#"SELECT id as "id: _", username, session_key_auth as "session_key: _", salt as "salt: _", verifier as "verifier: _", email, joindate, last_login, NULL as "ban_status: _", online from account"#)
-            .fetch_all(&self.pool)
-            .await
-            .map_err(|e| AccountFetchError::IO(e.to_string()))
+        let accounts = sqlx::query_as!(
+            AccountRow,
+            "SELECT id, username, email, salt, verifier, session_key_auth AS session_key, joindate, last_login, online, locked, expansion FROM account ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountFetchError::IO(e.to_string()))?;
+
+        // permanent bans sort last so that they take precedence
+        let bans = sqlx::query!(
+            "SELECT id, bandate, unbandate FROM account_banned WHERE active = 1 AND (unbandate > UNIX_TIMESTAMP() OR unbandate = bandate) ORDER BY unbandate = bandate, unbandate"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountFetchError::IO(e.to_string()))?
+        .into_iter()
+        .filter_map(|b| Some((b.id, BanStatus::from_timestamps(b.bandate.into(), b.unbandate.into())?)))
+        .collect::<HashMap<_, _>>();
+
+        accounts
+            .into_iter()
+            .map(|a| {
+                let ban = bans.get(&a.id).copied();
+                a.with_ban(ban)
+                    .map_err(|e| AccountFetchError::IO(e.to_string()))
+            })
+            .collect()
     }
 
     #[instrument(skip(self))]
@@ -71,75 +198,89 @@ impl AccountService for MySQLAccountService {
         Ok(id)
     }
 
+    /// Deletes the account and everything attached to it. Characters
+    /// are deleted on every realm before the account itself, so if any
+    /// realm fails the account is left in place and can be retried.
     #[instrument(skip(self))]
     async fn delete_account(&self, id: AccountId) -> Result<(), AccountOpError> {
-        let exists = sqlx::query!("SELECT id FROM account WHERE id = ?", id.0,)
-            .fetch_optional(&self.pool)
+        self.exists(id).await?;
+
+        let mut tx = self
+            .pool
+            .begin()
             .await
-            .map(|r| r.is_some())
             .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
 
-        if !exists {
-            return Err(AccountOpError::InvalidAccount(id));
-        };
-
-        let characters: Vec<u8> = vec![];
-
-        for _character in characters {
-            // delete
+        for statement in [
+            "DELETE FROM account_access WHERE AccountID = ?",
+            "DELETE FROM account_banned WHERE id = ?",
+            "DELETE FROM account_muted WHERE guid = ?",
+            "DELETE FROM realmcharacters WHERE acctid = ?",
+            "DELETE FROM account WHERE id = ?",
+        ] {
+            sqlx::query(statement)
+                .bind(id)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
         }
 
-        // delete tutorials
-        // delete account data
-        // delete character bans
+        for characters in &self.characters {
+            let deleted = characters
+                .delete_by_account(id)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+            debug!("deleted {deleted} characters for account {id}");
+        }
 
-        // delete account
-        // delete access history
-        // delete characters
-        // delete bans
-        // delete muted
+        tx.commit()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
 
+        info!("deleted account {id}");
         Ok(())
     }
 
     #[instrument(skip(self))]
     async fn get(&self, id: AccountId) -> Result<Account, AccountOpError> {
-        sqlx::query_as!(
-            Account,
-            r#"SELECT id as "id: _", username, session_key_auth as "session_key: _",salt as "salt: _", verifier as "verifier: _", email, joindate, last_login, NULL as "ban_status: _", online FROM account WHERE id = ?"#,
+        let account = sqlx::query_as!(
+            AccountRow,
+            "SELECT id, username, email, salt, verifier, session_key_auth AS session_key, joindate, last_login, online, locked, expansion FROM account WHERE id = ?",
             id
         )
         .fetch_one(&self.pool)
         .await
-        .map_err(|e| AccountOpError::PersistError(e.to_string()))
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        let ban = self
+            .active_ban(id)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        account.with_ban(ban)
     }
 
     #[instrument(skip(self))]
     async fn get_by_username(&self, username: &str) -> Result<Option<Account>, AccountOpError> {
-        let mut account = match sqlx::query_as!(
-            Account,
-            r#"SELECT id as "id: _", username, session_key_auth as "session_key: _",salt as "salt: _", verifier as "verifier: _", email, joindate, last_login, NULL as "ban_status: _", online FROM account WHERE username = ?"#,
+        let account = match sqlx::query_as!(
+            AccountRow,
+            "SELECT id, username, email, salt, verifier, session_key_auth AS session_key, joindate, last_login, online, locked, expansion FROM account WHERE username = ?",
             username
         )
         .fetch_optional(&self.pool)
         .await
-        .map_err(|e| AccountOpError::PersistError(e.to_string()))? {
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+        {
             Some(a) => a,
             None => return Ok(None),
         };
 
-        if let Some(ban) = sqlx::query!(
-                "SELECT  (ab.unbandate > UNIX_TIMESTAMP() OR ab.unbandate = ab.bandate) as 'is_banned: bool', (ab.unbandate = ab.bandate) as 'is_permabanned: bool' FROM account_banned ab where ab.id = ?", 
-                account.id
-            ).fetch_optional(&self.pool).await.map_err(|e| AccountOpError::PersistError(e.to_string()))? {
-                account.ban_status = match (ban.is_banned, ban.is_permabanned) {
-                    (_, true) => Some(BanStatus::Permanent),
-                    (Some(true), _) => Some(BanStatus::Temporary),
-                    _ => None,
-                };
-            }
+        let ban = self
+            .active_ban(AccountId(account.id))
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
 
-        Ok(Some(account))
+        account.with_ban(ban).map(Some)
     }
 
     async fn initiate_login(&self, username: &str) -> Result<ConnectToken, LoginFailure> {
@@ -154,9 +295,9 @@ impl AccountService for MySQLAccountService {
                 warn!("permanently banned user {username} tried to login");
                 Err(LoginFailure::Banned)
             }
-            Some(BanStatus::Temporary) => {
-                warn!("banned user {username} tried to login");
-                Err(LoginFailure::Suspended)
+            Some(BanStatus::Temporary { until }) => {
+                warn!("banned user {username} tried to login, suspended until {until}");
+                Err(LoginFailure::Suspended { until })
             }
             None => Ok(ConnectToken::new(
                 &account.username,
@@ -168,15 +309,14 @@ impl AccountService for MySQLAccountService {
 
     async fn initiate_relogin(&self, username: &str) -> Result<ReconnectToken, LoginFailure> {
         let request = sqlx::query!(
-            "SELECT a.id, a.username, a.locked, a.lock_country, a.last_ip, a.failed_logins, (ab.unbandate > UNIX_TIMESTAMP() OR ab.unbandate = ab.bandate) as 'is_banned: bool', (ab.unbandate = ab.bandate) as 'is_permabanned: bool', aa.SecurityLevel as security_level, a.session_key_auth as session_key FROM account a LEFT JOIN account_access aa ON a.id = aa.AccountID LEFT JOIN account_banned ab ON ab.id = a.id AND ab.active = 1 WHERE a.username = ? AND a.session_key_auth IS NOT NULL", 
+            "SELECT id, username, locked, session_key_auth FROM account WHERE username = ? AND session_key_auth IS NOT NULL",
             username
         ).fetch_one(&self.pool).await.map_err(|_| LoginFailure::DatabaseError)?;
 
-        let ban_status = match (request.is_banned, request.is_permabanned) {
-            (_, Some(true)) => Some(BanStatus::Permanent),
-            (Some(true), _) => Some(BanStatus::Temporary),
-            _ => None,
-        };
+        let ban_status = self
+            .active_ban(AccountId(request.id))
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?;
 
         let account = Account {
             id: AccountId(request.id),
@@ -185,10 +325,13 @@ impl AccountService for MySQLAccountService {
             verifier: Verifier([0u8; 32]),
             ban_status,
 
+            locked: request.locked,
+
             // todo(arlyon): fill in
             session_key: None,
             email: "".to_string(),
             online: 0,
+            expansion: 0,
             joindate: Utc::now(),
             last_login: None,
         };
@@ -198,7 +341,7 @@ impl AccountService for MySQLAccountService {
         Ok(ReconnectToken::new(
             account,
             request
-                .session_key
+                .session_key_auth
                 .and_then(|k| k.as_slice().try_into().ok())
                 .ok_or(LoginFailure::DatabaseError)?,
         ))
@@ -256,7 +399,12 @@ impl AccountService for MySQLAccountService {
     ) -> Result<(), AccountOpError> {
         let bandate = Utc::now();
         let unbandate = match duration {
-            Some(d) => bandate + chrono::Duration::from_std(d).unwrap(),
+            Some(d) => chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| bandate.checked_add_signed(d))
+                .ok_or_else(|| {
+                    AccountOpError::PersistError(format!("ban duration {d:?} is too long"))
+                })?,
             None => bandate,
         };
 
@@ -276,4 +424,328 @@ impl AccountService for MySQLAccountService {
 
         Ok(())
     }
+
+    #[instrument(skip(self))]
+    async fn unban(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let done = sqlx::query!(
+            "UPDATE account_banned SET active = 0 WHERE id = ? AND active = 1",
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() == 0 {
+            return Err(AccountOpError::NotBanned(id));
+        }
+
+        info!("unbanned {id}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn bans(&self, id: AccountId) -> Result<Vec<Ban>, AccountOpError> {
+        self.exists(id).await?;
+        let bans = sqlx::query!(
+            "SELECT bandate, unbandate, bannedby, banreason, active FROM account_banned WHERE id = ? ORDER BY bandate DESC",
+            id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        bans.into_iter()
+            .map(|b| {
+                Ok(Ban {
+                    banned_at: timestamp(b.bandate.into())?,
+                    expires: (b.unbandate != b.bandate)
+                        .then(|| timestamp(b.unbandate.into()))
+                        .transpose()?,
+                    author: b.bannedby,
+                    reason: b.banreason,
+                    active: b.active != 0,
+                })
+            })
+            .collect()
+    }
+
+    async fn expire_bans(&self) -> Result<u64, AccountOpError> {
+        let done = sqlx::query!(
+            "UPDATE account_banned SET active = 0 WHERE active = 1 AND unbandate <> bandate AND unbandate <= UNIX_TIMESTAMP()"
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() > 0 {
+            info!("expired {} bans", done.rows_affected());
+        }
+
+        Ok(done.rows_affected())
+    }
+
+    /// Mute an account. The mute is recorded in `account_muted`
+    /// so that there is a history of who muted the account and why.
+    #[instrument(skip(self))]
+    async fn set_mute(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+
+        let now = Utc::now();
+        let until = chrono::Duration::from_std(duration)
+            .ok()
+            .and_then(|d| now.checked_add_signed(d))
+            .unwrap_or(chrono::MAX_DATETIME);
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        sqlx::query!(
+            "UPDATE account SET mutetime = ? WHERE id = ?",
+            until.timestamp(),
+            id
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        sqlx::query!(
+            "INSERT INTO account_muted (guid, mutedate, mutetime, mutedby, mutereason) VALUES (?, ?, ?, ?, ?)",
+            id,
+            now.timestamp(),
+            duration.as_secs() / 60,
+            author,
+            reason.unwrap_or("")
+        )
+        .execute(&mut tx)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("muted {id} until {until}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unmute(&self, id: AccountId) -> Result<(), AccountOpError> {
+        if self.muted_until(id).await?.is_none() {
+            return Err(AccountOpError::NotMuted(id));
+        }
+
+        sqlx::query!("UPDATE account SET mutetime = 0 WHERE id = ?", id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("unmuted {id}");
+
+        Ok(())
+    }
+
+    async fn muted_until(&self, id: AccountId) -> Result<Option<DateTime<Utc>>, AccountOpError> {
+        let mutetime = sqlx::query!("SELECT mutetime FROM account WHERE id = ?", id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+            .ok_or(AccountOpError::InvalidAccount(id))?
+            .mutetime;
+
+        Ok(Utc
+            .timestamp_opt(mutetime, 0)
+            .single()
+            .filter(|until| *until > Utc::now()))
+    }
+
+    async fn record_login(&self, attempt: &LoginAttempt) -> Result<(), AccountOpError> {
+        sqlx::query!(
+            "INSERT INTO account_login_log (kind, username, account_id, ip, build, os, country, result, time) VALUES (?, ?, (SELECT id FROM account WHERE username = ?), ?, ?, ?, ?, ?, ?)",
+            attempt.kind as u8,
+            attempt.username,
+            attempt.username,
+            attempt.ip.to_string(),
+            attempt.build,
+            attempt.os,
+            attempt.country,
+            attempt.result,
+            attempt.time.timestamp()
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))
+    }
+
+    async fn login_history(
+        &self,
+        id: Option<AccountId>,
+        limit: u32,
+    ) -> Result<Vec<LoginAttempt>, AccountOpError> {
+        let rows = sqlx::query!(
+            "SELECT kind, username, account_id, ip, build, os, country, result, time FROM account_login_log WHERE ? IS NULL OR account_id = ? ORDER BY time DESC, id DESC LIMIT ?",
+            id.map(|i| i.0),
+            id.map(|i| i.0),
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|r| {
+                Ok(LoginAttempt {
+                    kind: LoginKind::from_u8(r.kind).ok_or_else(|| {
+                        AccountOpError::PersistError(format!("unknown login kind {}", r.kind))
+                    })?,
+                    username: r.username,
+                    account: r.account_id.map(AccountId),
+                    ip: r
+                        .ip
+                        .parse()
+                        .map_err(|e: AddrParseError| AccountOpError::PersistError(e.to_string()))?,
+                    build: r.build,
+                    os: r.os,
+                    country: r.country,
+                    result: r.result,
+                    time: timestamp(r.time)?,
+                })
+            })
+            .collect()
+    }
+
+    async fn prune_login_history(&self, before: DateTime<Utc>) -> Result<u64, AccountOpError> {
+        let done = sqlx::query!(
+            "DELETE FROM account_login_log WHERE time < ?",
+            before.timestamp()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() > 0 {
+            info!("pruned {} login attempts", done.rows_affected());
+        }
+
+        Ok(done.rows_affected())
+    }
+
+    #[instrument(skip(self, password))]
+    async fn set_password(&self, id: AccountId, password: &str) -> Result<(), AccountOpError> {
+        if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        let account = self.get(id).await?;
+        let (verifier, salt) =
+            WowSRPServer::register(&account.username, &password.to_ascii_uppercase());
+
+        // clearing the session key forces the client to log in again
+        sqlx::query!(
+            "UPDATE account SET salt = ?, verifier = ?, session_key_auth = NULL WHERE id = ?",
+            &salt.0[..],
+            &verifier.0[..],
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("changed password for {id}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_email(&self, id: AccountId, email: &str) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        sqlx::query!("UPDATE account SET email = ? WHERE id = ?", email, id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Set the GM level for an account.
+    ///
+    /// note: a realm id of -1 grants the level on all realms.
+    #[instrument(skip(self))]
+    async fn set_gm_level(
+        &self,
+        id: AccountId,
+        level: u8,
+        realm: Option<RealmId>,
+    ) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        let realm = realm.map(|r| r.0 as i32).unwrap_or(-1);
+
+        let done = if level == 0 {
+            // removing the level from all realms removes it from each realm too
+            sqlx::query!(
+                "DELETE FROM account_access WHERE AccountID = ? AND (RealmID = ? OR ? = -1)",
+                id,
+                realm,
+                realm
+            )
+            .execute(&self.pool)
+            .await
+        } else {
+            sqlx::query!(
+                "INSERT INTO account_access (AccountID, SecurityLevel, RealmID) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE SecurityLevel = VALUES(SecurityLevel)",
+                id,
+                level,
+                realm
+            )
+            .execute(&self.pool)
+            .await
+        };
+        done.map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("set gm level for {id} on realm {realm} to {level}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_expansion(&self, id: AccountId, expansion: u8) -> Result<(), AccountOpError> {
+        if expansion > MAX_EXPANSION {
+            return Err(AccountOpError::InvalidExpansion(expansion));
+        }
+
+        self.exists(id).await?;
+        sqlx::query!(
+            "UPDATE account SET expansion = ? WHERE id = ?",
+            expansion,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_locked(&self, id: AccountId, locked: bool) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        sqlx::query!(
+            "UPDATE account SET locked = ? WHERE id = ?",
+            locked as u8,
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
 }
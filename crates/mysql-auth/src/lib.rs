@@ -1,2 +1,7 @@
+use sqlx::migrate::Migrator;
+
 pub mod accounts;
 pub mod realms;
+
+/// The migrations for the auth database.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
@@ -4,11 +4,50 @@ use std::{
 };
 
 use async_trait::async_trait;
-use azerust_game::realms::{Realm, RealmFlags, RealmId, RealmList, RealmListError};
+use azerust_game::{
+    accounts::AccountId,
+    realms::{
+        Realm, RealmFlags, RealmId, RealmList, RealmListError, RealmRegistration, RealmType,
+    },
+};
+use enumflags2::BitFlags;
 use sqlx::{query, query_as, MySqlPool};
 use tokio::sync::RwLock;
 use tracing::{debug, trace};
 
+/// A realm as it is stored in the database.
+struct RealmRow {
+    id: RealmId,
+    name: String,
+    realm_type: RealmType,
+    build: u32,
+    external_address: String,
+    local_address: String,
+    local_subnet_mask: String,
+    port: u16,
+    flags: u8,
+    timezone: u8,
+    population: f32,
+}
+
+impl From<RealmRow> for Realm {
+    fn from(r: RealmRow) -> Self {
+        Self {
+            id: r.id,
+            name: r.name,
+            realm_type: r.realm_type,
+            build: r.build,
+            external_address: r.external_address,
+            local_address: r.local_address,
+            local_subnet_mask: r.local_subnet_mask,
+            port: r.port,
+            flags: BitFlags::from_bits_truncate(r.flags),
+            timezone: r.timezone,
+            population: r.population,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct MySQLRealmList {
     next_update: Arc<RwLock<SystemTime>>,
@@ -35,14 +74,18 @@ impl RealmList for MySQLRealmList {
         let now = SystemTime::now();
         if now > *self.next_update.read().await {
             debug!("Refreshing realm list");
+            // realms that are both invalid and offline are hidden
+            let hidden = BitFlags::from(RealmFlags::Invalid) | RealmFlags::Offline;
             if let Ok(realms) = query_as!(
-                Realm,
-                "SELECT id as 'id: _', name, icon as 'realm_type: _', gamebuild as build, address as 'external_address', localAddress as 'local_address: _', localSubnetMask as 'local_subnet_mask: _', port, flag as 'flags: _', timezone, population FROM realmlist WHERE flag <> 3 ORDER BY id"
+                RealmRow,
+                "SELECT id as 'id: _', name, icon as 'realm_type: _', gamebuild as build, address as 'external_address', localAddress as 'local_address: _', localSubnetMask as 'local_subnet_mask: _', port, flag as 'flags: _', timezone, population FROM realmlist WHERE flag & ? <> ? ORDER BY id",
+                hidden.bits(),
+                hidden.bits()
             )
             .fetch_all(&self.pool)
             .await {
                 let (mut self_realms, mut self_next_update) = tokio::join!(self.realms.write(), self.next_update.write());
-                *self_realms = realms;
+                *self_realms = realms.into_iter().map(Realm::from).collect();
                 *self_next_update = now + self.update_interval;
             }
         };
@@ -50,12 +93,57 @@ impl RealmList for MySQLRealmList {
         self.realms.read().await.clone()
     }
 
-    async fn update_status(&self, online: Vec<(u8, RealmFlags)>) -> Result<(), RealmListError> {
-        for (id, flag) in online {
+    async fn reload(&self) {
+        *self.next_update.write().await = SystemTime::now();
+    }
+
+    async fn register(&self, realm: &RealmRegistration) -> Result<(), RealmListError> {
+        debug!("Registering realm {:?} ({})", realm.id, realm.name);
+        query!(
+            "INSERT INTO realmlist
+        (
+            id, name, address, localAddress, localSubnetMask, port, icon, flag, timezone, gamebuild
+        )
+    VALUES
+        (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON DUPLICATE KEY UPDATE
+        name = VALUES(name), address = VALUES(address), localAddress = VALUES(localAddress),
+        localSubnetMask = VALUES(localSubnetMask), port = VALUES(port), icon = VALUES(icon),
+        timezone = VALUES(timezone), gamebuild = VALUES(gamebuild)
+    ",
+            realm.id,
+            realm.name,
+            realm.external_address,
+            realm.local_address,
+            realm.local_subnet_mask,
+            realm.port,
+            u8::from(realm.realm_type),
+            RealmFlags::Offline as u8,
+            realm.timezone,
+            realm.build
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        // make sure the next request sees the new details
+        self.reload().await;
+
+        Ok(())
+    }
+
+    async fn update_status(&self, status: Vec<(RealmId, bool)>) -> Result<(), RealmListError> {
+        let offline = RealmFlags::Offline as u8;
+        for (id, online) in status {
             query!(
-                "insert into realmlist(id, flag) values(?, ?) on duplicate key update flag = values(`flag`)",
-                id, flag as u8
-            ).execute(&self.pool).await.map_err(|e| RealmListError::PersistError(e.to_string()))?;
+                "UPDATE realmlist SET flag = (flag & ~?) | ? WHERE id = ?",
+                offline,
+                if online { 0 } else { offline },
+                id
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
         }
 
         Ok(())
@@ -105,4 +193,75 @@ impl RealmList for MySQLRealmList {
 
         Ok(())
     }
+
+    async fn request_shutdown(
+        &self,
+        id: RealmId,
+        at: Option<SystemTime>,
+    ) -> Result<(), RealmListError> {
+        let at: u32 = match at {
+            Some(at) => at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .try_into()
+                .map_err(|_| RealmListError::ShutdownTimeTooLarge)?,
+            None => {
+                query("DELETE FROM realm_shutdown WHERE realmid = ?")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+                return Ok(());
+            }
+        };
+
+        query("REPLACE INTO realm_shutdown (realmid, shutdown_at) VALUES (?, ?)")
+            .bind(id)
+            .bind(at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn requested_shutdown(&self, id: RealmId) -> Result<Option<SystemTime>, RealmListError> {
+        let at = query_as::<_, (u32,)>("SELECT shutdown_at FROM realm_shutdown WHERE realmid = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(at.map(|(at,)| UNIX_EPOCH + Duration::from_secs(at.into())))
+    }
+
+    async fn set_character_count(
+        &self,
+        id: RealmId,
+        account: AccountId,
+        count: u8,
+    ) -> Result<(), RealmListError> {
+        query("REPLACE INTO realmcharacters (realmid, acctid, numchars) VALUES (?, ?, ?)")
+            .bind(id)
+            .bind(account)
+            .bind(count)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn character_count(&self, account: AccountId) -> Result<usize, RealmListError> {
+        let (count,) = query_as::<_, (u64,)>(
+            "SELECT CAST(COALESCE(SUM(numchars), 0) AS UNSIGNED) FROM realmcharacters WHERE acctid = ?",
+        )
+        .bind(account)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(count.try_into().unwrap_or_default())
+    }
 }
@@ -0,0 +1,154 @@
+-- The subset of the TrinityCore 3.3.5 characters schema that azerust uses.
+-- Tables are only created if missing, so an existing TrinityCore
+-- database can be adopted by running the migrations against it.
+
+CREATE TABLE IF NOT EXISTS `characters` (
+  `guid` int unsigned NOT NULL DEFAULT '0' COMMENT 'Global Unique Identifier',
+  `account` int unsigned NOT NULL DEFAULT '0' COMMENT 'Account Identifier',
+  `name` varchar(12) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL,
+  `race` tinyint unsigned NOT NULL DEFAULT '0',
+  `class` tinyint unsigned NOT NULL DEFAULT '0',
+  `gender` tinyint unsigned NOT NULL DEFAULT '0',
+  `level` tinyint unsigned NOT NULL DEFAULT '0',
+  `xp` int unsigned NOT NULL DEFAULT '0',
+  `money` int unsigned NOT NULL DEFAULT '0',
+  `skin` tinyint unsigned NOT NULL DEFAULT '0',
+  `face` tinyint unsigned NOT NULL DEFAULT '0',
+  `hairStyle` tinyint unsigned NOT NULL DEFAULT '0',
+  `hairColor` tinyint unsigned NOT NULL DEFAULT '0',
+  `facialStyle` tinyint unsigned NOT NULL DEFAULT '0',
+  `bankSlots` tinyint unsigned NOT NULL DEFAULT '0',
+  `restState` tinyint unsigned NOT NULL DEFAULT '0',
+  `playerFlags` int unsigned NOT NULL DEFAULT '0',
+  `position_x` float NOT NULL DEFAULT '0',
+  `position_y` float NOT NULL DEFAULT '0',
+  `position_z` float NOT NULL DEFAULT '0',
+  `map` smallint unsigned NOT NULL DEFAULT '0' COMMENT 'Map Identifier',
+  `instance_id` int unsigned NOT NULL DEFAULT '0',
+  `instance_mode_mask` tinyint unsigned NOT NULL DEFAULT '0',
+  `orientation` float NOT NULL DEFAULT '0',
+  `taximask` text NOT NULL,
+  `online` tinyint unsigned NOT NULL DEFAULT '0',
+  `cinematic` tinyint unsigned NOT NULL DEFAULT '0',
+  `totaltime` int unsigned NOT NULL DEFAULT '0',
+  `leveltime` int unsigned NOT NULL DEFAULT '0',
+  `logout_time` int unsigned NOT NULL DEFAULT '0',
+  `is_logout_resting` tinyint unsigned NOT NULL DEFAULT '0',
+  `rest_bonus` float NOT NULL DEFAULT '0',
+  `resettalents_cost` int unsigned NOT NULL DEFAULT '0',
+  `resettalents_time` int unsigned NOT NULL DEFAULT '0',
+  `trans_x` float NOT NULL DEFAULT '0',
+  `trans_y` float NOT NULL DEFAULT '0',
+  `trans_z` float NOT NULL DEFAULT '0',
+  `trans_o` float NOT NULL DEFAULT '0',
+  `transguid` int unsigned NOT NULL DEFAULT '0',
+  `extra_flags` smallint unsigned NOT NULL DEFAULT '0',
+  `stable_slots` tinyint unsigned NOT NULL DEFAULT '0',
+  `at_login` smallint unsigned NOT NULL DEFAULT '0',
+  `zone` smallint unsigned NOT NULL DEFAULT '0',
+  `death_expire_time` int unsigned NOT NULL DEFAULT '0',
+  `taxi_path` text,
+  `arenaPoints` int unsigned NOT NULL DEFAULT '0',
+  `totalHonorPoints` int unsigned NOT NULL DEFAULT '0',
+  `todayHonorPoints` int unsigned NOT NULL DEFAULT '0',
+  `yesterdayHonorPoints` int unsigned NOT NULL DEFAULT '0',
+  `totalKills` int unsigned NOT NULL DEFAULT '0',
+  `todayKills` smallint unsigned NOT NULL DEFAULT '0',
+  `yesterdayKills` smallint unsigned NOT NULL DEFAULT '0',
+  `chosenTitle` int unsigned NOT NULL DEFAULT '0',
+  `knownCurrencies` bigint unsigned NOT NULL DEFAULT '0',
+  `watchedFaction` int unsigned NOT NULL DEFAULT '0',
+  `drunk` tinyint unsigned NOT NULL DEFAULT '0',
+  `health` int unsigned NOT NULL DEFAULT '0',
+  `power1` int unsigned NOT NULL DEFAULT '0',
+  `power2` int unsigned NOT NULL DEFAULT '0',
+  `power3` int unsigned NOT NULL DEFAULT '0',
+  `power4` int unsigned NOT NULL DEFAULT '0',
+  `power5` int unsigned NOT NULL DEFAULT '0',
+  `power6` int unsigned NOT NULL DEFAULT '0',
+  `power7` int unsigned NOT NULL DEFAULT '0',
+  `latency` int unsigned NOT NULL DEFAULT '0',
+  `talentGroupsCount` tinyint unsigned NOT NULL DEFAULT '1',
+  `activeTalentGroup` tinyint unsigned NOT NULL DEFAULT '0',
+  `exploredZones` longtext,
+  `equipmentCache` longtext,
+  `ammoId` int unsigned NOT NULL DEFAULT '0',
+  `knownTitles` longtext,
+  `actionBars` tinyint unsigned NOT NULL DEFAULT '0',
+  `grantableLevels` tinyint unsigned NOT NULL DEFAULT '0',
+  `deleteInfos_Account` int unsigned DEFAULT NULL,
+  `deleteInfos_Name` varchar(12) DEFAULT NULL,
+  `deleteDate` int unsigned DEFAULT NULL,
+  PRIMARY KEY (`guid`),
+  KEY `idx_account` (`account`),
+  KEY `idx_online` (`online`),
+  KEY `idx_name` (`name`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Player System';
+
+CREATE TABLE IF NOT EXISTS `character_banned` (
+  `guid` int unsigned NOT NULL DEFAULT '0' COMMENT 'Global Unique Identifier',
+  `bandate` int unsigned NOT NULL DEFAULT '0',
+  `unbandate` int unsigned NOT NULL DEFAULT '0',
+  `bannedby` varchar(50) NOT NULL,
+  `banreason` varchar(255) NOT NULL,
+  `active` tinyint unsigned NOT NULL DEFAULT '1',
+  PRIMARY KEY (`guid`, `bandate`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Ban List';
+
+CREATE TABLE IF NOT EXISTS `item_instance` (
+  `guid` int unsigned NOT NULL DEFAULT '0',
+  `itemEntry` mediumint unsigned NOT NULL DEFAULT '0',
+  `owner_guid` int unsigned NOT NULL DEFAULT '0',
+  `creatorGuid` int unsigned NOT NULL DEFAULT '0',
+  `giftCreatorGuid` int unsigned NOT NULL DEFAULT '0',
+  `count` int unsigned NOT NULL DEFAULT '1',
+  `duration` int NOT NULL DEFAULT '0',
+  `charges` tinytext,
+  `flags` mediumint unsigned NOT NULL DEFAULT '0',
+  `enchantments` text NOT NULL,
+  `randomPropertyId` smallint NOT NULL DEFAULT '0',
+  `durability` smallint unsigned NOT NULL DEFAULT '0',
+  `playedTime` int unsigned NOT NULL DEFAULT '0',
+  `text` text,
+  PRIMARY KEY (`guid`),
+  KEY `idx_owner_guid` (`owner_guid`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Item System';
+
+CREATE TABLE IF NOT EXISTS `character_inventory` (
+  `guid` int unsigned NOT NULL DEFAULT '0' COMMENT 'Global Unique Identifier',
+  `bag` int unsigned NOT NULL DEFAULT '0',
+  `slot` tinyint unsigned NOT NULL DEFAULT '0',
+  `item` int unsigned NOT NULL DEFAULT '0' COMMENT 'Item Global Unique Identifier',
+  PRIMARY KEY (`item`),
+  UNIQUE KEY `guid` (`guid`, `bag`, `slot`),
+  KEY `idx_guid` (`guid`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Player System';
+
+CREATE TABLE IF NOT EXISTS `character_account_data` (
+  `guid` int unsigned NOT NULL DEFAULT '0',
+  `type` tinyint unsigned NOT NULL DEFAULT '0',
+  `time` int unsigned NOT NULL DEFAULT '0',
+  `data` blob NOT NULL,
+  PRIMARY KEY (`guid`, `type`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `account_data` (
+  `accountId` int unsigned NOT NULL DEFAULT '0' COMMENT 'Account Identifier',
+  `type` tinyint unsigned NOT NULL DEFAULT '0',
+  `time` int unsigned NOT NULL DEFAULT '0',
+  `data` blob NOT NULL,
+  PRIMARY KEY (`accountId`, `type`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
+
+CREATE TABLE IF NOT EXISTS `account_tutorial` (
+  `accountId` int unsigned NOT NULL DEFAULT '0' COMMENT 'Account Identifier',
+  `tut0` int unsigned NOT NULL DEFAULT '0',
+  `tut1` int unsigned NOT NULL DEFAULT '0',
+  `tut2` int unsigned NOT NULL DEFAULT '0',
+  `tut3` int unsigned NOT NULL DEFAULT '0',
+  `tut4` int unsigned NOT NULL DEFAULT '0',
+  `tut5` int unsigned NOT NULL DEFAULT '0',
+  `tut6` int unsigned NOT NULL DEFAULT '0',
+  `tut7` int unsigned NOT NULL DEFAULT '0',
+  PRIMARY KEY (`accountId`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
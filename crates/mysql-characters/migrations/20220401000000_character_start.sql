@@ -0,0 +1,26 @@
+-- The spells, skills and action bar of a character, as in TrinityCore.
+
+CREATE TABLE IF NOT EXISTS `character_spell` (
+  `guid` int unsigned NOT NULL DEFAULT '0' COMMENT 'Global Unique Identifier',
+  `spell` mediumint unsigned NOT NULL DEFAULT '0' COMMENT 'Spell Identifier',
+  `active` tinyint unsigned NOT NULL DEFAULT '1',
+  `disabled` tinyint unsigned NOT NULL DEFAULT '0',
+  PRIMARY KEY (`guid`, `spell`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Player System';
+
+CREATE TABLE IF NOT EXISTS `character_skills` (
+  `guid` int unsigned NOT NULL COMMENT 'Global Unique Identifier',
+  `skill` smallint unsigned NOT NULL,
+  `value` smallint unsigned NOT NULL,
+  `max` smallint unsigned NOT NULL,
+  PRIMARY KEY (`guid`, `skill`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Player System';
+
+CREATE TABLE IF NOT EXISTS `character_action` (
+  `guid` int unsigned NOT NULL DEFAULT '0',
+  `spec` tinyint unsigned NOT NULL DEFAULT '0',
+  `button` tinyint unsigned NOT NULL DEFAULT '0',
+  `action` int unsigned NOT NULL DEFAULT '0',
+  `type` tinyint unsigned NOT NULL DEFAULT '0',
+  PRIMARY KEY (`guid`, `spec`, `button`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci;
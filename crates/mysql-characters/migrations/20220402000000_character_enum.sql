@@ -0,0 +1,36 @@
+-- The guild members and pets shown on the character screen, as in
+-- TrinityCore.
+
+CREATE TABLE IF NOT EXISTS `guild_member` (
+  `guildid` int unsigned NOT NULL COMMENT 'Guild Identificator',
+  `guid` int unsigned NOT NULL,
+  `rank` tinyint unsigned NOT NULL,
+  `pnote` varchar(31) NOT NULL DEFAULT '',
+  `offnote` varchar(31) NOT NULL DEFAULT '',
+  PRIMARY KEY (`guid`),
+  KEY `guildid_key` (`guildid`),
+  KEY `guildid_rank_key` (`guildid`, `rank`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Guild System';
+
+CREATE TABLE IF NOT EXISTS `character_pet` (
+  `id` int unsigned NOT NULL DEFAULT '0',
+  `entry` int unsigned NOT NULL DEFAULT '0',
+  `owner` int unsigned NOT NULL DEFAULT '0',
+  `modelid` int unsigned DEFAULT '0',
+  `CreatedBySpell` mediumint unsigned NOT NULL DEFAULT '0',
+  `PetType` tinyint unsigned NOT NULL DEFAULT '0',
+  `level` smallint unsigned NOT NULL DEFAULT '1',
+  `exp` int unsigned NOT NULL DEFAULT '0',
+  `Reactstate` tinyint unsigned NOT NULL DEFAULT '0',
+  `name` varchar(21) NOT NULL DEFAULT 'Pet',
+  `renamed` tinyint unsigned NOT NULL DEFAULT '0',
+  `slot` tinyint unsigned NOT NULL DEFAULT '0',
+  `curhealth` int unsigned NOT NULL DEFAULT '1',
+  `curmana` int unsigned NOT NULL DEFAULT '0',
+  `curhappiness` int unsigned NOT NULL DEFAULT '0',
+  `savetime` int unsigned NOT NULL DEFAULT '0',
+  `abdata` text,
+  PRIMARY KEY (`id`),
+  KEY `owner` (`owner`),
+  KEY `idx_slot` (`slot`)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='Pet System';
@@ -1,17 +1,117 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 
 use async_trait::async_trait;
 use azerust_game::{
-    accounts::AccountId,
+    accounts::{AccountId, BanStatus},
     characters::{
-        AccountData, AccountDataCache, Character, CharacterCreate, CharacterId, CharacterService,
-        CharacterServiceError, DualDataCache,
+        AccountData, AccountDataCache, AccountDataType, AtLoginFlags, Character, CharacterCreate,
+        CharacterId, CharacterPet, CharacterService, CharacterServiceError, DeletedCharacter,
+        DualDataCache, EquippedItem, StartingItem, EQUIPMENT_SLOTS,
     },
     EntityType, WowId,
 };
-use rand::Rng;
-use sqlx::{query, query_as, MySqlPool};
-use tracing::{debug, instrument};
+use chrono::{DateTime, TimeZone, Utc};
+use enumflags2::BitFlags;
+use sqlx::{query, query_as, FromRow, MySqlPool};
+use tracing::{debug, info, instrument};
+
+/// A character as it is stored in the database, along with their
+/// guild and the pet they have out.
+#[derive(FromRow)]
+struct CharacterRow {
+    guid: u32,
+    account: AccountId,
+    name: String,
+    level: u8,
+    race: u8,
+    class: u8,
+    gender: u8,
+    skin_color: u8,
+    face: u8,
+    hair_style: u8,
+    hair_color: u8,
+    facial_style: u8,
+    zone: u16,
+    map: u16,
+    position_x: f32,
+    position_y: f32,
+    position_z: f32,
+    player_flags: u32,
+    at_login: u16,
+    guild: u32,
+    pet_entry: Option<u32>,
+    pet_display: Option<u32>,
+    pet_level: Option<u16>,
+}
+
+impl From<CharacterRow> for Character {
+    fn from(r: CharacterRow) -> Self {
+        Self {
+            id: WowId::new(EntityType::Player, r.guid, 0),
+            account: r.account,
+            name: r.name,
+            level: r.level,
+            race: r.race,
+            class: r.class,
+            gender: r.gender,
+            skin_color: r.skin_color,
+            face: r.face,
+            hair_style: r.hair_style,
+            hair_color: r.hair_color,
+            facial_style: r.facial_style,
+            zone: r.zone,
+            map: r.map,
+            position_x: r.position_x,
+            position_y: r.position_y,
+            position_z: r.position_z,
+            player_flags: BitFlags::from_bits_truncate(r.player_flags),
+            at_login: BitFlags::from_bits_truncate(r.at_login),
+            guild: r.guild,
+            pet: r.pet_entry.map(|entry| CharacterPet {
+                entry,
+                display: r.pet_display.unwrap_or(0),
+                level: r.pet_level.unwrap_or(1) as u8,
+            }),
+        }
+    }
+}
+
+/// Read an `equipmentCache`, which has an item entry and its
+/// enchantments for every slot on the character screen. The
+/// permanent enchantment is in the low 16 bits.
+fn parse_equipment_cache(cache: &str) -> Option<Vec<EquippedItem>> {
+    let values = cache
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<u32>, _>>()
+        .ok()?;
+    if values.len() < usize::from(EQUIPMENT_SLOTS) * 2 {
+        return None;
+    }
+
+    Some(
+        values
+            .chunks(2)
+            .zip(0..EQUIPMENT_SLOTS)
+            .filter(|(item, _)| item[0] != 0)
+            .map(|(item, slot)| EquippedItem {
+                slot,
+                entry: item[0],
+                enchant: item[1] & 0xFFFF,
+            })
+            .collect(),
+    )
+}
+
+/// Write the `equipmentCache` for a character with the given items.
+fn equipment_cache(items: &[StartingItem]) -> String {
+    (0..EQUIPMENT_SLOTS)
+        .map(|slot| {
+            let entry = items.iter().find(|i| i.slot == slot).map_or(0, |i| i.entry);
+            format!("{entry} 0 ")
+        })
+        .collect()
+}
 
 pub struct MySQLCharacterService {
     pool: MySqlPool,
@@ -27,25 +127,36 @@ impl MySQLCharacterService {
 #[async_trait]
 impl CharacterService for MySQLCharacterService {
     async fn get(&self, id: CharacterId) -> Result<Character, CharacterServiceError> {
-        query_as!(
-            Character,
-            "SELECT guid as 'id: _', account as 'account: _', name, level, race, class, gender, skin as skin_color, face, hairStyle as hair_style, hairColor as hair_color, facialStyle as facial_style, zone, map, position_x, position_y, position_z FROM characters where guid = ?",
-            id
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hairStyle AS hair_style, c.hairColor AS hair_color, c.facialStyle AS facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.playerFlags AS player_flags, c.at_login, CAST(COALESCE(g.guildid, 0) AS UNSIGNED) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.guid = ?",
         )
+        .bind(id)
         .fetch_one(&self.pool)
         .await
+        .map(Character::from)
         .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
     }
 
     #[instrument(skip(self))]
     async fn get_by_account(&self, id: AccountId) -> Result<Vec<Character>, CharacterServiceError> {
-        query_as!(
-            Character,
-            "SELECT guid as 'id: _', account as 'account: _', name, level, race, class, gender, skin as skin_color, face, hairStyle as hair_style, hairColor as hair_color, facialStyle as facial_style, zone, map, position_x, position_y, position_z FROM characters where account = ?",
-            id
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hairStyle AS hair_style, c.hairColor AS hair_color, c.facialStyle AS facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.playerFlags AS player_flags, c.at_login, CAST(COALESCE(g.guildid, 0) AS UNSIGNED) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.account = ?",
         )
+        .bind(id)
         .fetch_all(&self.pool)
         .await
+        .map(|rows| rows.into_iter().map(Character::from).collect())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Character>, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hairStyle AS hair_style, c.hairColor AS hair_color, c.facialStyle AS facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.playerFlags AS player_flags, c.at_login, CAST(COALESCE(g.guildid, 0) AS UNSIGNED) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.name = ?",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|row| row.map(Character::from))
         .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
     }
 
@@ -65,12 +176,66 @@ impl CharacterService for MySQLCharacterService {
             .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
     }
 
+    async fn equipment(&self, id: CharacterId) -> Result<Vec<EquippedItem>, CharacterServiceError> {
+        let (cache,) = query_as::<_, (Option<String>,)>(
+            "SELECT equipmentCache FROM characters WHERE guid = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+        .ok_or(CharacterServiceError::NoSuchCharacter(id))?;
+        if let Some(equipment) = cache.as_deref().and_then(parse_equipment_cache) {
+            return Ok(equipment);
+        }
+
+        // the enchantments are stored as an id, duration and charges
+        // for each enchantment slot, starting with the permanent one
+        query_as::<_, (u8, u32, String)>(
+            "SELECT i.slot, ii.itemEntry, ii.enchantments FROM character_inventory i JOIN item_instance ii ON ii.guid = i.item WHERE i.guid = ? AND i.bag = 0 AND i.slot < ? ORDER BY i.slot",
+        )
+        .bind(id)
+        .bind(EQUIPMENT_SLOTS)
+        .fetch_all(&self.pool)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|(slot, entry, enchantments)| EquippedItem {
+                    slot,
+                    entry,
+                    enchant: enchantments
+                        .split_whitespace()
+                        .next()
+                        .and_then(|e| e.parse().ok())
+                        .unwrap_or(0),
+                })
+                .collect()
+        })
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn max_guid(&self, kind: EntityType) -> Result<u32, CharacterServiceError> {
+        let statement = match kind {
+            EntityType::Player => "SELECT CAST(COALESCE(MAX(guid), 0) AS UNSIGNED) FROM characters",
+            EntityType::ItemOrContainer => {
+                "SELECT CAST(COALESCE(MAX(guid), 0) AS UNSIGNED) FROM item_instance"
+            }
+            _ => return Ok(0),
+        };
+        query_as::<_, (u64,)>(statement)
+            .fetch_one(&self.pool)
+            .await
+            .map(|(max,)| max as u32)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
     async fn create_character(
         &self,
         account: AccountId,
         create: CharacterCreate,
     ) -> Result<(), CharacterServiceError> {
         let CharacterCreate {
+            id,
             name,
             race,
             class,
@@ -83,51 +248,441 @@ impl CharacterService for MySQLCharacterService {
             position_x,
             position_y,
             position_z,
+            orientation,
             skin_color,
             zone,
+            items,
+            spells,
+            skills,
+            action_buttons,
         } = create;
-        let id = {
-            let mut rng = rand::thread_rng();
-            WowId::new(EntityType::Player, rng.gen(), 0)
-        };
 
-        // todo taximask, taxi_path, exploredZones, equipmentCache, knownTitles
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
 
-        query!(
-            "INSERT INTO characters (account, guid, level, name, race, class, gender, skin, face, hairStyle, hairColor, facialStyle, zone, map, position_x, position_y, position_z, taximask, taxi_path, exploredZones, equipmentCache, knownTitles) values (?, ?, 1, ?,?,?,?, ?, ?, ?, ?, ?, ?,?,?,?,?, '','', '', '', '')", 
-            account, id, name, race, class, gender, skin_color, face, hair_style, hair_color, facial_style, zone, map, position_x, position_y, position_z)
-            .execute(&self.pool)
+        // todo taximask, taxi_path, exploredZones, knownTitles
+
+        query(
+            "INSERT INTO characters (account, guid, level, name, race, class, gender, skin, face, hairStyle, hairColor, facialStyle, zone, map, position_x, position_y, position_z, orientation, taximask, taxi_path, exploredZones, equipmentCache, knownTitles, at_login) values (?, ?, 1, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, '', '', '', ?, '', ?)",
+        )
+        .bind(account)
+        .bind(id)
+        .bind(name)
+        .bind(race)
+        .bind(class)
+        .bind(gender)
+        .bind(skin_color)
+        .bind(face)
+        .bind(hair_style)
+        .bind(hair_color)
+        .bind(facial_style)
+        .bind(zone)
+        .bind(map)
+        .bind(position_x)
+        .bind(position_y)
+        .bind(position_z)
+        .bind(orientation)
+        .bind(equipment_cache(&items))
+        .bind(AtLoginFlags::FirstLogin as u16)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for item in items {
+            query(
+                "INSERT INTO item_instance (guid, itemEntry, owner_guid, count, durability, enchantments) VALUES (?, ?, ?, ?, ?, '')",
+            )
+            .bind(item.id.low())
+            .bind(item.entry)
+            .bind(id.low())
+            .bind(item.count)
+            .bind(item.durability)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+            query("INSERT INTO character_inventory (guid, bag, slot, item) VALUES (?, 0, ?, ?)")
+                .bind(id.low())
+                .bind(item.slot)
+                .bind(item.id.low())
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for spell in spells {
+            query("INSERT IGNORE INTO character_spell (guid, spell) VALUES (?, ?)")
+                .bind(id.low())
+                .bind(spell)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for skill in skills {
+            query(
+                "INSERT IGNORE INTO character_skills (guid, skill, value, max) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id.low())
+            .bind(skill.skill)
+            .bind(skill.value)
+            .bind(skill.max)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for button in action_buttons {
+            query("REPLACE INTO character_action (guid, button, action, type) VALUES (?, ?, ?, ?)")
+                .bind(id.low())
+                .bind(button.button)
+                .bind(button.action)
+                .bind(button.action_type)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
             .await
-            .map(|_| ())
             .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
     }
 
     async fn delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
-        query!("DELETE FROM characters where guid = ?", id)
-            .execute(&self.pool)
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid = ?",
+            "DELETE FROM character_inventory WHERE guid = ?",
+            "DELETE FROM character_spell WHERE guid = ?",
+            "DELETE FROM character_skills WHERE guid = ?",
+            "DELETE FROM character_action WHERE guid = ?",
+            "DELETE FROM character_pet WHERE owner = ?",
+            "DELETE FROM guild_member WHERE guid = ?",
+            "DELETE FROM character_banned WHERE guid = ?",
+            "DELETE FROM character_account_data WHERE guid = ?",
+            "DELETE FROM characters WHERE guid = ?",
+        ] {
+            query(statement)
+                .bind(id)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_inventory WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_spell WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_skills WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_action WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_pet WHERE owner IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM guild_member WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_account_data WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM character_banned WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, deleteInfos_Account))",
+            "DELETE FROM account_data WHERE accountId = ?",
+            "DELETE FROM account_tutorial WHERE accountId = ?",
+        ] {
+            query(statement)
+                .bind(id)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        let deleted = query("DELETE FROM characters WHERE ? IN (account, deleteInfos_Account)")
+            .bind(id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+            .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(deleted as usize)
+    }
+
+    #[instrument(skip(self))]
+    async fn soft_delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        let done = query(
+            "UPDATE characters SET deleteInfos_Account = account, deleteInfos_Name = name, deleteDate = UNIX_TIMESTAMP(), account = 0, name = '' WHERE guid = ? AND deleteDate IS NULL",
+        )
+        .bind(id)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+
+        query("DELETE FROM guild_member WHERE guid = ?")
+            .bind(id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        tx.commit()
             .await
-            .map(|_| ())
             .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
     }
 
-    async fn account_data(&self, id: AccountId) -> Result<AccountData, CharacterServiceError> {
-        let mut rows = query!(
-            "SELECT type, time, data FROM account_data WHERE accountId = ?",
+    async fn deleted_characters(&self) -> Result<Vec<DeletedCharacter>, CharacterServiceError> {
+        query_as::<_, (u32, AccountId, String, u8, u8, u8, u32)>(
+            "SELECT guid, deleteInfos_Account, deleteInfos_Name, level, race, class, deleteDate FROM characters WHERE deleteDate IS NOT NULL ORDER BY deleteDate, guid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(
+                    |(guid, account, name, level, race, class, deleted)| DeletedCharacter {
+                        id: WowId::new(EntityType::Player, guid, 0),
+                        account,
+                        name,
+                        level,
+                        race,
+                        class,
+                        deleted: Utc.timestamp(deleted.into(), 0),
+                    },
+                )
+                .collect()
+        })
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    #[instrument(skip(self))]
+    async fn restore_character(
+        &self,
+        id: CharacterId,
+        name: &str,
+    ) -> Result<(), CharacterServiceError> {
+        let done = query(
+            "UPDATE characters SET account = deleteInfos_Account, name = ?, deleteInfos_Account = NULL, deleteInfos_Name = NULL, deleteDate = NULL WHERE guid = ? AND deleteDate IS NOT NULL",
+        )
+        .bind(name)
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<usize, CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_inventory WHERE guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_spell WHERE guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_skills WHERE guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_action WHERE guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_pet WHERE owner IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_account_data WHERE guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+            "DELETE FROM character_banned WHERE guid IN (SELECT guid FROM characters WHERE deleteDate < ?)",
+        ] {
+            query(statement)
+                .bind(before.timestamp())
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        let deleted = query("DELETE FROM characters WHERE deleteDate < ?")
+            .bind(before.timestamp())
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+            .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        info!("purged {deleted} deleted characters");
+        Ok(deleted as usize)
+    }
+
+    async fn save_character(&self, character: &Character) -> Result<(), CharacterServiceError> {
+        query(
+            "UPDATE characters SET level = ?, zone = ?, map = ?, position_x = ?, position_y = ?, position_z = ? WHERE guid = ?",
+        )
+        .bind(character.level)
+        .bind(character.zone)
+        .bind(character.map)
+        .bind(character.position_x)
+        .bind(character.position_y)
+        .bind(character.position_z)
+        .bind(character.id)
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn set_at_login(
+        &self,
+        id: CharacterId,
+        flags: BitFlags<AtLoginFlags>,
+    ) -> Result<(), CharacterServiceError> {
+        let done = query("UPDATE characters SET at_login = ? WHERE guid = ?")
+            .bind(flags.bits())
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        Ok(())
+    }
+
+    async fn customize_character(
+        &self,
+        character: &Character,
+    ) -> Result<(), CharacterServiceError> {
+        query(
+            "UPDATE characters SET name = ?, race = ?, gender = ?, skin = ?, face = ?, hairStyle = ?, hairColor = ?, facialStyle = ?, at_login = ? WHERE guid = ?",
+        )
+        .bind(&character.name)
+        .bind(character.race)
+        .bind(character.gender)
+        .bind(character.skin_color)
+        .bind(character.face)
+        .bind(character.hair_style)
+        .bind(character.hair_color)
+        .bind(character.facial_style)
+        .bind(character.at_login.bits())
+        .bind(character.id)
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    /// Ban a character from logging in.
+    ///
+    /// note: permabans are represented by a unbandate of the same value as the bandate.
+    #[instrument(skip(self))]
+    async fn ban_character(
+        &self,
+        id: CharacterId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), CharacterServiceError> {
+        let bandate = Utc::now();
+        let unbandate = match duration {
+            Some(d) => chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| bandate.checked_add_signed(d))
+                .ok_or_else(|| {
+                    CharacterServiceError::PersistError(format!("ban duration {d:?} is too long"))
+                })?,
+            None => bandate,
+        };
+
+        query!(
+            "INSERT INTO character_banned (guid, bandate, unbandate, bannedby, banreason, active) VALUES (?, ?, ?, ?, ?, 1)",
+            id,
+            bandate.timestamp(),
+            unbandate.timestamp(),
+            author,
+            reason.unwrap_or("")
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        info!("banned character {id:?} for {duration:?}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unban_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let done = query!(
+            "UPDATE character_banned SET active = 0 WHERE guid = ? AND active = 1",
+            id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NotBanned(id));
+        }
+
+        info!("unbanned character {id:?}");
+
+        Ok(())
+    }
+
+    async fn character_ban(
+        &self,
+        id: CharacterId,
+    ) -> Result<Option<BanStatus>, CharacterServiceError> {
+        let ban = query!(
+            "SELECT bandate, unbandate FROM character_banned WHERE guid = ? AND active = 1 AND (unbandate > UNIX_TIMESTAMP() OR unbandate = bandate) ORDER BY unbandate = bandate DESC, unbandate DESC LIMIT 1",
             id
         )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(ban.and_then(|b| BanStatus::from_timestamps(b.bandate.into(), b.unbandate.into())))
+    }
+
+    async fn account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+    ) -> Result<AccountData, CharacterServiceError> {
+        let mut rows = query_as::<_, (u8, u32, Vec<u8>)>(
+            "SELECT type, time, data FROM account_data WHERE accountId = ? UNION ALL SELECT type, time, data FROM character_account_data WHERE guid = ?",
+        )
+        .bind(id)
+        .bind(character)
         .fetch_all(&self.pool)
         .await
         .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
         .into_iter()
-        .map(|r| {
-            (
-                r.r#type,
-                AccountDataCache {
-                    time: r.time,
-                    data: r.data,
-                },
-            )
-        })
+        .map(|(kind, time, data)| (kind, AccountDataCache { time, data }))
         .collect::<HashMap<_, _>>();
 
         Ok(AccountData {
@@ -147,4 +702,66 @@ impl CharacterService for MySQLCharacterService {
             per_char_chat: rows.remove(&7),
         })
     }
+
+    async fn set_account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    ) -> Result<(), CharacterServiceError> {
+        let statement = if kind.is_global() {
+            query("REPLACE INTO account_data (accountId, type, time, data) VALUES (?, ?, ?, ?)")
+                .bind(id)
+        } else {
+            let character = character.ok_or_else(|| {
+                CharacterServiceError::PersistError(format!("{kind:?} is kept per character"))
+            })?;
+            query(
+                "REPLACE INTO character_account_data (guid, type, time, data) VALUES (?, ?, ?, ?)",
+            )
+            .bind(character)
+        };
+
+        statement
+            .bind(u8::from(kind))
+            .bind(data.time)
+            .bind(data.data)
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn tutorials(&self, id: AccountId) -> Result<[u32; 8], CharacterServiceError> {
+        let tutorials = query_as::<_, (u32, u32, u32, u32, u32, u32, u32, u32)>(
+            "SELECT tut0, tut1, tut2, tut3, tut4, tut5, tut6, tut7 FROM account_tutorial WHERE accountId = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(tutorials
+            .map(|(t0, t1, t2, t3, t4, t5, t6, t7)| [t0, t1, t2, t3, t4, t5, t6, t7])
+            .unwrap_or_default())
+    }
+
+    async fn set_tutorials(
+        &self,
+        id: AccountId,
+        tutorials: [u32; 8],
+    ) -> Result<(), CharacterServiceError> {
+        tutorials
+            .iter()
+            .fold(
+                query("REPLACE INTO account_tutorial (accountId, tut0, tut1, tut2, tut3, tut4, tut5, tut6, tut7) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)")
+                    .bind(id),
+                |statement, t| statement.bind(*t),
+            )
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
 }
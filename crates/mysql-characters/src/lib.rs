@@ -1,3 +1,8 @@
+use sqlx::migrate::Migrator;
+
 pub mod characters;
 
 pub use characters::MySQLCharacterService;
+
+/// The migrations for the characters database.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
@@ -0,0 +1,107 @@
+//! mysql-world
+//!
+//! Read access to a TrinityCore world database.
+
+use async_trait::async_trait;
+use azerust_game::world::{
+    CreatureSpawn, CreatureTemplate, GameObjectTemplate, ItemTemplate, PlayerCreateAction,
+    PlayerCreateInfo, PlayerCreateItem, PlayerCreateSkill, PlayerCreateSpell, WorldDatabase,
+    WorldDatabaseError,
+};
+use sqlx::{query_as, MySqlPool};
+use tracing::debug;
+
+/// A world database stored in MySQL.
+#[derive(Clone, Debug)]
+pub struct MySQLWorldDatabase {
+    pool: MySqlPool,
+}
+
+impl MySQLWorldDatabase {
+    pub fn new(pool: MySqlPool) -> Self {
+        debug!("Starting world database");
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl WorldDatabase for MySQLWorldDatabase {
+    async fn creature_templates(&self) -> Result<Vec<CreatureTemplate>, WorldDatabaseError> {
+        query_as(
+            "SELECT entry, name, subname, minlevel AS min_level, maxlevel AS max_level, faction, npcflag AS npc_flags, speed_walk, speed_run, scale, `rank`, unit_class, unit_flags, type AS creature_type, family, type_flags FROM creature_template",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn creature_spawns(&self) -> Result<Vec<CreatureSpawn>, WorldDatabaseError> {
+        query_as(
+            "SELECT guid, id AS entry, map, position_x, position_y, position_z, orientation, spawntimesecs AS respawn_time FROM creature",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn gameobject_templates(&self) -> Result<Vec<GameObjectTemplate>, WorldDatabaseError> {
+        query_as(
+            "SELECT entry, type AS object_type, displayId AS display_id, name, size FROM gameobject_template",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn item_templates(&self) -> Result<Vec<ItemTemplate>, WorldDatabaseError> {
+        query_as(
+            "SELECT entry, class, subclass, name, displayid AS display_id, Quality AS quality, Flags AS flags, InventoryType AS inventory_type, AllowableClass AS allowable_class, AllowableRace AS allowable_race, ItemLevel AS item_level, RequiredLevel AS required_level, maxcount AS max_count, stackable, ContainerSlots AS container_slots, MaxDurability AS max_durability FROM item_template",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn player_create_info(&self) -> Result<Vec<PlayerCreateInfo>, WorldDatabaseError> {
+        query_as(
+            "SELECT race, class, map, zone, position_x, position_y, position_z, orientation FROM playercreateinfo",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn player_create_items(&self) -> Result<Vec<PlayerCreateItem>, WorldDatabaseError> {
+        query_as("SELECT race, class, itemid AS item, amount FROM playercreateinfo_item")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn player_create_spells(&self) -> Result<Vec<PlayerCreateSpell>, WorldDatabaseError> {
+        query_as(
+            "SELECT racemask AS race_mask, classmask AS class_mask, Spell AS spell FROM playercreateinfo_spell_custom",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn player_create_skills(&self) -> Result<Vec<PlayerCreateSkill>, WorldDatabaseError> {
+        query_as(
+            "SELECT raceMask AS race_mask, classMask AS class_mask, skill, `rank` FROM playercreateinfo_skills",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+
+    async fn player_create_actions(&self) -> Result<Vec<PlayerCreateAction>, WorldDatabaseError> {
+        query_as(
+            "SELECT race, class, button, action, type AS action_type FROM playercreateinfo_action",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| WorldDatabaseError::PersistError(e.to_string()))
+    }
+}
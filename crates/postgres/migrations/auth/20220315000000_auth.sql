@@ -0,0 +1,96 @@
+-- Accounts, bans and the realmlist.
+--
+-- Postgres has no unsigned integers, so u8 columns are stored
+-- as smallint, u16 as integer and u32 as bigint.
+
+CREATE TABLE account (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    username TEXT NOT NULL,
+    salt BYTEA NOT NULL,
+    verifier BYTEA NOT NULL,
+    session_key BYTEA,
+    email TEXT NOT NULL DEFAULT '',
+    joindate BIGINT NOT NULL,
+    last_ip TEXT NOT NULL DEFAULT '127.0.0.1',
+    last_login BIGINT,
+    online SMALLINT NOT NULL DEFAULT 0,
+    locked SMALLINT NOT NULL DEFAULT 0,
+    expansion SMALLINT NOT NULL DEFAULT 2,
+    -- when the account's mute ends, as a unix timestamp
+    mutetime BIGINT NOT NULL DEFAULT 0,
+    locale SMALLINT NOT NULL DEFAULT 0,
+    os TEXT NOT NULL DEFAULT ''
+);
+
+-- usernames are case insensitive
+CREATE UNIQUE INDEX idx_account_username ON account (upper(username));
+
+-- gm levels, where a realm id of -1 applies to every realm
+CREATE TABLE account_access (
+    account_id BIGINT NOT NULL,
+    security_level SMALLINT NOT NULL,
+    realm_id BIGINT NOT NULL DEFAULT -1,
+    PRIMARY KEY (account_id, realm_id)
+);
+
+-- permanent bans have an unbandate equal to their bandate
+CREATE TABLE account_banned (
+    id BIGINT NOT NULL,
+    bandate BIGINT NOT NULL,
+    unbandate BIGINT NOT NULL,
+    bannedby TEXT NOT NULL,
+    banreason TEXT NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE INDEX idx_account_banned_id ON account_banned (id, active);
+
+CREATE TABLE account_muted (
+    account_id BIGINT NOT NULL,
+    mutedate BIGINT NOT NULL,
+    -- the length of the mute in minutes
+    mutetime BIGINT NOT NULL,
+    mutedby TEXT NOT NULL,
+    mutereason TEXT NOT NULL
+);
+
+CREATE TABLE account_login_log (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    -- 0 = connect, 1 = reconnect
+    kind SMALLINT NOT NULL,
+    username TEXT NOT NULL,
+    account_id BIGINT,
+    ip TEXT NOT NULL,
+    build INTEGER NOT NULL,
+    os TEXT NOT NULL,
+    country TEXT NOT NULL,
+    -- the auth return code, 0 = success
+    result SMALLINT NOT NULL,
+    time BIGINT NOT NULL
+);
+
+CREATE INDEX idx_account_login_log_account_time ON account_login_log (account_id, time);
+CREATE INDEX idx_account_login_log_time ON account_login_log (time);
+
+CREATE TABLE realmlist (
+    id BIGINT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    address TEXT NOT NULL DEFAULT '127.0.0.1',
+    local_address TEXT NOT NULL DEFAULT '127.0.0.1',
+    local_subnet_mask TEXT NOT NULL DEFAULT '255.255.255.0',
+    port INTEGER NOT NULL DEFAULT 8085,
+    icon SMALLINT NOT NULL DEFAULT 0,
+    flag SMALLINT NOT NULL DEFAULT 2,
+    timezone SMALLINT NOT NULL DEFAULT 0,
+    gamebuild BIGINT NOT NULL DEFAULT 12340,
+    population REAL NOT NULL DEFAULT 0
+);
+
+CREATE TABLE uptime (
+    realm_id BIGINT NOT NULL,
+    starttime BIGINT NOT NULL,
+    uptime BIGINT NOT NULL DEFAULT 0,
+    maxplayers BIGINT NOT NULL DEFAULT 0,
+    revision TEXT NOT NULL,
+    PRIMARY KEY (realm_id, starttime)
+);
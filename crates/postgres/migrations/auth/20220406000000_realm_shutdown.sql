@@ -0,0 +1,7 @@
+-- The time each realm's server has been asked to shut down at, in unix seconds.
+
+CREATE TABLE realm_shutdown (
+    realm_id BIGINT NOT NULL,
+    shutdown_at BIGINT NOT NULL,
+    PRIMARY KEY (realm_id)
+);
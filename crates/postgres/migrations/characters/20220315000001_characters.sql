@@ -0,0 +1,45 @@
+-- Characters and the data the client stores per account.
+
+CREATE TABLE characters (
+    guid BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    account BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    level SMALLINT NOT NULL DEFAULT 1,
+    race SMALLINT NOT NULL,
+    class SMALLINT NOT NULL,
+    gender SMALLINT NOT NULL,
+    skin SMALLINT NOT NULL,
+    face SMALLINT NOT NULL,
+    hair_style SMALLINT NOT NULL,
+    hair_color SMALLINT NOT NULL,
+    facial_style SMALLINT NOT NULL,
+    zone INTEGER NOT NULL,
+    map INTEGER NOT NULL,
+    position_x REAL NOT NULL,
+    position_y REAL NOT NULL,
+    position_z REAL NOT NULL
+);
+
+-- names are case insensitive
+CREATE UNIQUE INDEX idx_characters_name ON characters (upper(name));
+CREATE INDEX idx_characters_account ON characters (account);
+
+-- permanent bans have an unbandate equal to their bandate
+CREATE TABLE character_banned (
+    guid BIGINT NOT NULL,
+    bandate BIGINT NOT NULL,
+    unbandate BIGINT NOT NULL,
+    bannedby TEXT NOT NULL,
+    banreason TEXT NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+CREATE INDEX idx_character_banned_guid ON character_banned (guid, active);
+
+CREATE TABLE account_data (
+    account_id BIGINT NOT NULL,
+    type SMALLINT NOT NULL,
+    time BIGINT NOT NULL DEFAULT 0,
+    data BYTEA NOT NULL,
+    PRIMARY KEY (account_id, type)
+);
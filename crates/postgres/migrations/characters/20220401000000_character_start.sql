@@ -0,0 +1,47 @@
+-- What a character starts with: their facing, items, spells,
+-- skills and action bar.
+
+ALTER TABLE characters ADD COLUMN orientation REAL NOT NULL DEFAULT 0;
+
+CREATE TABLE item_instance (
+    guid BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY,
+    item_entry BIGINT NOT NULL,
+    owner_guid BIGINT NOT NULL,
+    count BIGINT NOT NULL DEFAULT 1,
+    durability INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_item_instance_owner ON item_instance (owner_guid);
+
+CREATE TABLE character_inventory (
+    guid BIGINT NOT NULL,
+    bag BIGINT NOT NULL DEFAULT 0,
+    slot SMALLINT NOT NULL,
+    item BIGINT PRIMARY KEY,
+    UNIQUE (guid, bag, slot)
+);
+
+CREATE TABLE character_spell (
+    guid BIGINT NOT NULL,
+    spell BIGINT NOT NULL,
+    active SMALLINT NOT NULL DEFAULT 1,
+    disabled SMALLINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, spell)
+);
+
+CREATE TABLE character_skills (
+    guid BIGINT NOT NULL,
+    skill INTEGER NOT NULL,
+    value INTEGER NOT NULL,
+    max INTEGER NOT NULL,
+    PRIMARY KEY (guid, skill)
+);
+
+CREATE TABLE character_action (
+    guid BIGINT NOT NULL,
+    spec SMALLINT NOT NULL DEFAULT 0,
+    button SMALLINT NOT NULL,
+    action BIGINT NOT NULL,
+    type SMALLINT NOT NULL,
+    PRIMARY KEY (guid, spec, button)
+);
@@ -0,0 +1,24 @@
+-- What the character screen shows besides the character itself:
+-- their flags, guild and pet.
+
+ALTER TABLE characters ADD COLUMN player_flags BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE characters ADD COLUMN at_login INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE guild_member (
+    guildid BIGINT NOT NULL,
+    guid BIGINT PRIMARY KEY,
+    rank SMALLINT NOT NULL DEFAULT 0
+);
+
+-- the pet a character has out is in slot 0
+CREATE TABLE character_pet (
+    id BIGINT PRIMARY KEY,
+    entry BIGINT NOT NULL,
+    owner BIGINT NOT NULL,
+    modelid BIGINT NOT NULL DEFAULT 0,
+    level SMALLINT NOT NULL DEFAULT 1,
+    name TEXT NOT NULL DEFAULT '',
+    slot SMALLINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_character_pet_owner ON character_pet (owner, slot);
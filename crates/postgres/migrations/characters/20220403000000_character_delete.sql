@@ -0,0 +1,11 @@
+-- Deleted characters can be kept around to be restored. They are
+-- taken off their account and lose their name, so only the names
+-- of characters that are not deleted need to be unique.
+
+ALTER TABLE characters ADD COLUMN delete_infos_account BIGINT;
+ALTER TABLE characters ADD COLUMN delete_infos_name TEXT;
+ALTER TABLE characters ADD COLUMN delete_date BIGINT;
+
+DROP INDEX idx_characters_name;
+CREATE UNIQUE INDEX idx_characters_name ON characters (upper(name)) WHERE delete_date IS NULL;
+CREATE INDEX idx_characters_delete_date ON characters (delete_date);
@@ -0,0 +1,10 @@
+-- The data the client stores for each character, such as their
+-- macros and chat layout, next to the account wide data.
+
+CREATE TABLE character_account_data (
+    guid BIGINT NOT NULL,
+    type SMALLINT NOT NULL,
+    time BIGINT NOT NULL DEFAULT 0,
+    data BYTEA NOT NULL,
+    PRIMARY KEY (guid, type)
+);
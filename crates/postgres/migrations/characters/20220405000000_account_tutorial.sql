@@ -0,0 +1,14 @@
+-- The tutorials each account has seen, one bit per tutorial.
+
+CREATE TABLE account_tutorial (
+    account_id BIGINT NOT NULL,
+    tut0 BIGINT NOT NULL DEFAULT 0,
+    tut1 BIGINT NOT NULL DEFAULT 0,
+    tut2 BIGINT NOT NULL DEFAULT 0,
+    tut3 BIGINT NOT NULL DEFAULT 0,
+    tut4 BIGINT NOT NULL DEFAULT 0,
+    tut5 BIGINT NOT NULL DEFAULT 0,
+    tut6 BIGINT NOT NULL DEFAULT 0,
+    tut7 BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (account_id)
+);
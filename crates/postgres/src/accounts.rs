@@ -0,0 +1,828 @@
+use std::{collections::HashMap, fmt, net::AddrParseError, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::{
+        Account, AccountFetchError, AccountId, AccountOpError, AccountService, Ban, BanStatus,
+        ConnectToken, LoginFailure, ReconnectToken, MAX_EXPANSION,
+    },
+    audit::{LoginAttempt, LoginKind},
+    characters::CharacterService,
+    realms::RealmId,
+    types::Locale,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::{query, query_as, FromRow, PgPool};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, instrument, warn};
+use wow_srp::{Salt, Verifier, WowSRPServer};
+
+/// An account as it is stored in the database. The ban
+/// status is stored separately, in the `account_banned` table.
+#[derive(FromRow)]
+struct AccountRow {
+    id: i64,
+    username: String,
+    email: String,
+    salt: Vec<u8>,
+    verifier: Vec<u8>,
+    session_key: Option<Vec<u8>>,
+    joindate: i64,
+    last_login: Option<i64>,
+    online: i16,
+    locked: i16,
+    expansion: i16,
+}
+
+impl AccountRow {
+    fn with_ban(self, ban_status: Option<BanStatus>) -> Result<Account, AccountOpError> {
+        Ok(Account {
+            id: AccountId(self.id as u32),
+            username: self.username,
+            email: self.email,
+            ban_status,
+            salt: Salt(
+                self.salt
+                    .try_into()
+                    .map_err(|_| AccountOpError::PersistError("invalid salt".to_string()))?,
+            ),
+            verifier: Verifier(
+                self.verifier
+                    .try_into()
+                    .map_err(|_| AccountOpError::PersistError("invalid verifier".to_string()))?,
+            ),
+            session_key: self.session_key.and_then(|k| k.try_into().ok()),
+            joindate: timestamp(self.joindate)?,
+            last_login: self.last_login.map(timestamp).transpose()?,
+            online: self.online as u8,
+            locked: self.locked as u8,
+            expansion: self.expansion as u8,
+        })
+    }
+}
+
+/// A unix timestamp read from the database as a date.
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, AccountOpError> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| AccountOpError::PersistError(format!("invalid timestamp {secs}")))
+}
+
+#[derive(Clone)]
+pub struct PostgresAccountService {
+    pool: PgPool,
+    /// The character services of every realm, used to clean
+    /// up characters when an account is deleted.
+    characters: Vec<Arc<dyn CharacterService + Send + Sync>>,
+    /// Logins that have been started but not completed, keyed
+    /// by the public key of the server for that login.
+    pending: Arc<Mutex<HashMap<[u8; 32], AccountId>>>,
+}
+
+impl fmt::Debug for PostgresAccountService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PostgresAccountService")
+            .field("pool", &self.pool)
+            .field("characters", &self.characters.len())
+            .finish()
+    }
+}
+
+impl PostgresAccountService {
+    pub fn new(pool: PgPool) -> Self {
+        debug!("Starting accounts service");
+        Self {
+            pool,
+            characters: vec![],
+            pending: Default::default(),
+        }
+    }
+
+    /// Set the character services of the realms that should be
+    /// cleaned up when an account is deleted.
+    pub fn with_characters(self, characters: Vec<Arc<dyn CharacterService + Send + Sync>>) -> Self {
+        Self { characters, ..self }
+    }
+
+    /// Check that an account with the given id exists.
+    async fn exists(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let exists = query("SELECT id FROM account WHERE id = $1")
+            .bind(i64::from(id.0))
+            .fetch_optional(&self.pool)
+            .await
+            .map(|r| r.is_some())
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if exists {
+            Ok(())
+        } else {
+            Err(AccountOpError::InvalidAccount(id))
+        }
+    }
+
+    /// Get the ban currently in place on an account, if any. If there
+    /// are several, a permanent ban wins over the longest temporary one.
+    async fn active_ban(&self, id: AccountId) -> Result<Option<BanStatus>, sqlx::Error> {
+        let ban: Option<(i64, i64)> = query_as(
+            "SELECT bandate, unbandate FROM account_banned WHERE id = $1 AND active AND (unbandate > $2 OR unbandate = bandate) ORDER BY unbandate = bandate DESC, unbandate DESC LIMIT 1",
+        )
+        .bind(i64::from(id.0))
+        .bind(Utc::now().timestamp())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(ban.and_then(|(bandate, unbandate)| BanStatus::from_timestamps(bandate, unbandate)))
+    }
+}
+
+#[async_trait]
+impl AccountService for PostgresAccountService {
+    async fn list_account(&self) -> Result<Vec<Account>, AccountFetchError> {
+        let accounts: Vec<AccountRow> = query_as(
+            "SELECT id, username, email, salt, verifier, session_key, joindate, last_login, online, locked, expansion FROM account ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountFetchError::IO(e.to_string()))?;
+
+        // permanent bans sort last so that they take precedence
+        let bans = query_as::<_, (i64, i64, i64)>(
+            "SELECT id, bandate, unbandate FROM account_banned WHERE active AND (unbandate > $1 OR unbandate = bandate) ORDER BY unbandate = bandate, unbandate",
+        )
+        .bind(Utc::now().timestamp())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountFetchError::IO(e.to_string()))?
+        .into_iter()
+        .filter_map(|(id, bandate, unbandate)| {
+            Some((id, BanStatus::from_timestamps(bandate, unbandate)?))
+        })
+        .collect::<HashMap<_, _>>();
+
+        accounts
+            .into_iter()
+            .map(|a| {
+                let ban = bans.get(&a.id).copied();
+                a.with_ban(ban)
+                    .map_err(|e| AccountFetchError::IO(e.to_string()))
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn create_account(
+        &self,
+        username: &str,
+        password: &str,
+        email: &str,
+    ) -> Result<AccountId, AccountOpError> {
+        if username.len() > 16 {
+            return Err(AccountOpError::UsernameTooLong);
+        } else if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        // convert to uppercase
+        let username = username.to_ascii_uppercase();
+        let password = password.to_ascii_uppercase();
+
+        let (verifier, salt) = WowSRPServer::register(&username, &password);
+
+        let (id,): (i64,) = query_as(
+            "INSERT INTO account (username, salt, verifier, email, joindate) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+        )
+        .bind(&username)
+        .bind(&salt.0[..])
+        .bind(&verifier.0[..])
+        .bind(email)
+        .bind(Utc::now().timestamp())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        Ok(AccountId(id as u32))
+    }
+
+    /// Deletes the account and everything attached to it. Characters
+    /// are deleted on every realm before the account itself, so if any
+    /// realm fails the account is left in place and can be retried.
+    #[instrument(skip(self))]
+    async fn delete_account(&self, id: AccountId) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM account_access WHERE account_id = $1",
+            "DELETE FROM account_banned WHERE id = $1",
+            "DELETE FROM account_muted WHERE account_id = $1",
+            "DELETE FROM realm_characters WHERE account_id = $1",
+            "DELETE FROM account WHERE id = $1",
+        ] {
+            query(statement)
+                .bind(i64::from(id.0))
+                .execute(&mut tx)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        }
+
+        for characters in &self.characters {
+            let deleted = characters
+                .delete_by_account(id)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+            debug!("deleted {deleted} characters for account {id}");
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("deleted account {id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get(&self, id: AccountId) -> Result<Account, AccountOpError> {
+        let account: AccountRow = query_as(
+            "SELECT id, username, email, salt, verifier, session_key, joindate, last_login, online, locked, expansion FROM account WHERE id = $1",
+        )
+        .bind(i64::from(id.0))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+        .ok_or(AccountOpError::InvalidAccount(id))?;
+
+        let ban = self
+            .active_ban(id)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        account.with_ban(ban)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_by_username(&self, username: &str) -> Result<Option<Account>, AccountOpError> {
+        let account: AccountRow = match query_as(
+            "SELECT id, username, email, salt, verifier, session_key, joindate, last_login, online, locked, expansion FROM account WHERE upper(username) = upper($1)",
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+        {
+            Some(a) => a,
+            None => return Ok(None),
+        };
+
+        let ban = self
+            .active_ban(AccountId(account.id as u32))
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        account.with_ban(ban).map(Some)
+    }
+
+    async fn initiate_login(&self, username: &str) -> Result<ConnectToken, LoginFailure> {
+        let account = self
+            .get_by_username(username)
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?
+            .ok_or(LoginFailure::UnknownAccount)?;
+
+        match account.ban_status {
+            Some(BanStatus::Permanent) => {
+                warn!("permanently banned user {username} tried to login");
+                Err(LoginFailure::Banned)
+            }
+            Some(BanStatus::Temporary { until }) => {
+                warn!("banned user {username} tried to login, suspended until {until}");
+                Err(LoginFailure::Suspended { until })
+            }
+            None => {
+                let token = ConnectToken::new(&account.username, account.salt, account.verifier);
+                self.pending
+                    .lock()
+                    .await
+                    .insert(*token.get_b_pub(), account.id);
+                Ok(token)
+            }
+        }
+    }
+
+    async fn initiate_relogin(&self, username: &str) -> Result<ReconnectToken, LoginFailure> {
+        let account = self
+            .get_by_username(username)
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?
+            .ok_or(LoginFailure::UnknownAccount)?;
+
+        let session_key = account.session_key.ok_or(LoginFailure::UnknownAccount)?;
+
+        Ok(ReconnectToken::new(account, session_key))
+    }
+
+    async fn complete_login(
+        &self,
+        token: &ConnectToken,
+        public_key: &[u8; 32],
+        client_proof: &[u8; 20],
+    ) -> Result<[u8; 20], LoginFailure> {
+        let id = self
+            .pending
+            .lock()
+            .await
+            .remove(token.get_b_pub())
+            .ok_or(LoginFailure::DatabaseError)?;
+
+        let (server_proof, session_key) = token.accept(public_key, client_proof)?;
+
+        // todo(arlyon) set the ip, locale and os from the client
+        query(
+            "UPDATE account SET session_key = $1, last_ip = $2, last_login = $3, locale = $4, os = $5 WHERE id = $6",
+        )
+        .bind(&session_key[..])
+        .bind("0.0.0.0")
+        .bind(Utc::now().timestamp())
+        .bind(i16::from(u8::from(Locale::enUS)))
+        .bind("Win")
+        .bind(i64::from(id.0))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("error updating session: {e}");
+            LoginFailure::DatabaseError
+        })?;
+
+        info!("logged in {id}");
+
+        Ok(server_proof)
+    }
+
+    async fn complete_relogin(
+        &self,
+        token: &ReconnectToken,
+        proof_data: &[u8; 16],
+        client_proof: &[u8; 20],
+    ) -> Result<[u8; 20], LoginFailure> {
+        token
+            .accept(proof_data, client_proof)
+            .map(|_| client_proof.to_owned())
+    }
+
+    /// Set a ban for a given account.
+    ///
+    /// duration: the duration of the ban. If `None`, the ban is permanent.
+    ///
+    /// note: permabans are represented by a unbandate of the same value as the bandate.
+    async fn set_ban(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        let bandate = Utc::now();
+        let unbandate = match duration {
+            Some(d) => chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| bandate.checked_add_signed(d))
+                .ok_or_else(|| {
+                    AccountOpError::PersistError(format!("ban duration {d:?} is too long"))
+                })?,
+            None => bandate,
+        };
+
+        query(
+            "INSERT INTO account_banned (id, bandate, unbandate, bannedby, banreason) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(i64::from(id.0))
+        .bind(bandate.timestamp())
+        .bind(unbandate.timestamp())
+        .bind(author)
+        .bind(reason.unwrap_or(""))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("banned {id} for {duration:?}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unban(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let done = query("UPDATE account_banned SET active = FALSE WHERE id = $1 AND active")
+            .bind(i64::from(id.0))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() == 0 {
+            return Err(AccountOpError::NotBanned(id));
+        }
+
+        info!("unbanned {id}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn bans(&self, id: AccountId) -> Result<Vec<Ban>, AccountOpError> {
+        self.exists(id).await?;
+        let bans: Vec<(i64, i64, String, String, bool)> = query_as(
+            "SELECT bandate, unbandate, bannedby, banreason, active FROM account_banned WHERE id = $1 ORDER BY bandate DESC",
+        )
+        .bind(i64::from(id.0))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        bans.into_iter()
+            .map(|(bandate, unbandate, author, reason, active)| {
+                Ok(Ban {
+                    banned_at: timestamp(bandate)?,
+                    expires: (unbandate != bandate)
+                        .then(|| timestamp(unbandate))
+                        .transpose()?,
+                    author,
+                    reason,
+                    active,
+                })
+            })
+            .collect()
+    }
+
+    async fn expire_bans(&self) -> Result<u64, AccountOpError> {
+        let done = query(
+            "UPDATE account_banned SET active = FALSE WHERE active AND unbandate <> bandate AND unbandate <= $1",
+        )
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() > 0 {
+            info!("expired {} bans", done.rows_affected());
+        }
+
+        Ok(done.rows_affected())
+    }
+
+    /// Mute an account. The mute is recorded in `account_muted`
+    /// so that there is a history of who muted the account and why.
+    #[instrument(skip(self))]
+    async fn set_mute(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+
+        let now = Utc::now();
+        let until = chrono::Duration::from_std(duration)
+            .ok()
+            .and_then(|d| now.checked_add_signed(d))
+            .unwrap_or(chrono::MAX_DATETIME);
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        query("UPDATE account SET mutetime = $1 WHERE id = $2")
+            .bind(until.timestamp())
+            .bind(i64::from(id.0))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        query(
+            "INSERT INTO account_muted (account_id, mutedate, mutetime, mutedby, mutereason) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(i64::from(id.0))
+        .bind(now.timestamp())
+        .bind((duration.as_secs() / 60) as i64)
+        .bind(author)
+        .bind(reason.unwrap_or(""))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("muted {id} until {until}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unmute(&self, id: AccountId) -> Result<(), AccountOpError> {
+        if self.muted_until(id).await?.is_none() {
+            return Err(AccountOpError::NotMuted(id));
+        }
+
+        query("UPDATE account SET mutetime = 0 WHERE id = $1")
+            .bind(i64::from(id.0))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("unmuted {id}");
+
+        Ok(())
+    }
+
+    async fn muted_until(&self, id: AccountId) -> Result<Option<DateTime<Utc>>, AccountOpError> {
+        let (mutetime,): (i64,) = query_as("SELECT mutetime FROM account WHERE id = $1")
+            .bind(i64::from(id.0))
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+            .ok_or(AccountOpError::InvalidAccount(id))?;
+
+        let until = timestamp(mutetime)?;
+        Ok((until > Utc::now()).then_some(until))
+    }
+
+    async fn record_login(&self, attempt: &LoginAttempt) -> Result<(), AccountOpError> {
+        query(
+            "INSERT INTO account_login_log (kind, username, account_id, ip, build, os, country, result, time) VALUES ($1, $2, (SELECT id FROM account WHERE upper(username) = upper($2)), $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(attempt.kind as i16)
+        .bind(&attempt.username)
+        .bind(attempt.ip.to_string())
+        .bind(i32::from(attempt.build))
+        .bind(&attempt.os)
+        .bind(&attempt.country)
+        .bind(i16::from(attempt.result))
+        .bind(attempt.time.timestamp())
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))
+    }
+
+    async fn login_history(
+        &self,
+        id: Option<AccountId>,
+        limit: u32,
+    ) -> Result<Vec<LoginAttempt>, AccountOpError> {
+        let rows: Vec<(i16, String, Option<i64>, String, i32, String, String, i16, i64)> = query_as(
+            "SELECT kind, username, account_id, ip, build, os, country, result, time FROM account_login_log WHERE $1::bigint IS NULL OR account_id = $1 ORDER BY time DESC, id DESC LIMIT $2",
+        )
+        .bind(id.map(|i| i64::from(i.0)))
+        .bind(i64::from(limit))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(
+                |(kind, username, account, ip, build, os, country, result, time)| {
+                    Ok(LoginAttempt {
+                        kind: LoginKind::from_u8(kind as u8).ok_or_else(|| {
+                            AccountOpError::PersistError(format!("unknown login kind {kind}"))
+                        })?,
+                        username,
+                        account: account.map(|a| AccountId(a as u32)),
+                        ip: ip.parse().map_err(|e: AddrParseError| {
+                            AccountOpError::PersistError(e.to_string())
+                        })?,
+                        build: build as u16,
+                        os,
+                        country,
+                        result: result as u8,
+                        time: timestamp(time)?,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    async fn prune_login_history(&self, before: DateTime<Utc>) -> Result<u64, AccountOpError> {
+        let done = query("DELETE FROM account_login_log WHERE time < $1")
+            .bind(before.timestamp())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() > 0 {
+            info!("pruned {} login attempts", done.rows_affected());
+        }
+
+        Ok(done.rows_affected())
+    }
+
+    #[instrument(skip(self, password))]
+    async fn set_password(&self, id: AccountId, password: &str) -> Result<(), AccountOpError> {
+        if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        let account = self.get(id).await?;
+        let (verifier, salt) =
+            WowSRPServer::register(&account.username, &password.to_ascii_uppercase());
+
+        // clearing the session key forces the client to log in again
+        query("UPDATE account SET salt = $1, verifier = $2, session_key = NULL WHERE id = $3")
+            .bind(&salt.0[..])
+            .bind(&verifier.0[..])
+            .bind(i64::from(id.0))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("changed password for {id}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_email(&self, id: AccountId, email: &str) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        query("UPDATE account SET email = $1 WHERE id = $2")
+            .bind(email)
+            .bind(i64::from(id.0))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Set the GM level for an account.
+    ///
+    /// note: a realm id of -1 grants the level on all realms.
+    #[instrument(skip(self))]
+    async fn set_gm_level(
+        &self,
+        id: AccountId,
+        level: u8,
+        realm: Option<RealmId>,
+    ) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        let realm = realm.map(|r| i64::from(r.0)).unwrap_or(-1);
+
+        let statement = if level == 0 {
+            // removing the level from all realms removes it from each realm too
+            query("DELETE FROM account_access WHERE account_id = $1 AND (realm_id = $2 OR $2 = -1)")
+                .bind(i64::from(id.0))
+                .bind(realm)
+        } else {
+            query(
+                "INSERT INTO account_access (account_id, realm_id, security_level) VALUES ($1, $2, $3) ON CONFLICT (account_id, realm_id) DO UPDATE SET security_level = excluded.security_level",
+            )
+            .bind(i64::from(id.0))
+            .bind(realm)
+            .bind(i16::from(level))
+        };
+        statement
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("set gm level for {id} on realm {realm} to {level}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_expansion(&self, id: AccountId, expansion: u8) -> Result<(), AccountOpError> {
+        if expansion > MAX_EXPANSION {
+            return Err(AccountOpError::InvalidExpansion(expansion));
+        }
+
+        self.exists(id).await?;
+        query("UPDATE account SET expansion = $1 WHERE id = $2")
+            .bind(i16::from(expansion))
+            .bind(i64::from(id.0))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_locked(&self, id: AccountId, locked: bool) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        query("UPDATE account SET locked = $1 WHERE id = $2")
+            .bind(i16::from(locked))
+            .bind(i64::from(id.0))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::Duration;
+
+    use azerust_game::accounts::{AccountId, AccountOpError, AccountService, BanStatus};
+    use wow_srp::WowSRPClient;
+
+    use super::PostgresAccountService;
+    use crate::{test_pool, unique_name, AUTH_MIGRATOR};
+
+    async fn accounts() -> PostgresAccountService {
+        PostgresAccountService::new(test_pool("auth", &AUTH_MIGRATOR).await)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn create_and_log_in() {
+        let accounts = accounts().await;
+        let username = unique_name("thrall");
+        let id = accounts
+            .create_account(&username, "lokthar", "thrall@example.com")
+            .await
+            .unwrap();
+
+        let account = accounts.get_by_username(&username).await.unwrap().unwrap();
+        assert_eq!(account.id, id);
+        assert_eq!(account.username, username.to_ascii_uppercase());
+        assert_eq!(account.session_key, None);
+
+        let token = accounts.initiate_login(&account.username).await.unwrap();
+        let client = WowSRPClient::new(&account.username, "LOKTHAR");
+        let (proof, session_key) = client
+            .calculate_proof(token.get_b_pub(), token.get_salt())
+            .unwrap();
+        let server_proof = accounts
+            .complete_login(&token, client.get_a_pub(), &proof)
+            .await
+            .unwrap();
+
+        assert_eq!(server_proof, client.get_server_proof(&proof, &session_key));
+        let account = accounts.get(id).await.unwrap();
+        assert_eq!(account.session_key, Some(session_key));
+        assert!(account.last_login.is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn bans_and_mutes() {
+        let accounts = accounts().await;
+        let id = accounts
+            .create_account(&unique_name("thrall"), "lokthar", "thrall@example.com")
+            .await
+            .unwrap();
+
+        accounts
+            .set_ban(id, "jaina", Some(Duration::from_secs(60)), None)
+            .await
+            .unwrap();
+        accounts
+            .set_ban(id, "jaina", None, Some("rude"))
+            .await
+            .unwrap();
+        assert_eq!(
+            accounts.get(id).await.unwrap().ban_status,
+            Some(BanStatus::Permanent)
+        );
+        assert_eq!(accounts.bans(id).await.unwrap().len(), 2);
+
+        accounts.unban(id).await.unwrap();
+        assert_eq!(accounts.get(id).await.unwrap().ban_status, None);
+        assert!(matches!(
+            accounts.unban(id).await,
+            Err(AccountOpError::NotBanned(_))
+        ));
+
+        accounts
+            .set_mute(id, "jaina", Duration::from_secs(60), None)
+            .await
+            .unwrap();
+        assert!(accounts.muted_until(id).await.unwrap().is_some());
+        accounts.unmute(id).await.unwrap();
+        assert!(accounts.muted_until(id).await.unwrap().is_none());
+
+        accounts.delete_account(id).await.unwrap();
+        assert!(matches!(
+            accounts.delete_account(id).await,
+            Err(AccountOpError::InvalidAccount(_))
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn delete_missing_account() {
+        let accounts = accounts().await;
+        assert!(matches!(
+            accounts.delete_account(AccountId(u32::MAX)).await,
+            Err(AccountOpError::InvalidAccount(_))
+        ));
+    }
+}
@@ -0,0 +1,1025 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::{AccountId, BanStatus},
+    characters::{
+        AccountData, AccountDataCache, AccountDataType, AtLoginFlags, Character, CharacterCreate,
+        CharacterId, CharacterPet, CharacterService, CharacterServiceError, DeletedCharacter,
+        DualDataCache, EquippedItem, EQUIPMENT_SLOTS,
+    },
+    EntityType, WowId,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use enumflags2::BitFlags;
+use sqlx::{query, query_as, FromRow, PgPool};
+use tracing::{debug, info, instrument};
+
+/// A character as it is stored in the database, where
+/// the guid is the low part of the character's id.
+#[derive(FromRow)]
+struct CharacterRow {
+    guid: i64,
+    account: i64,
+    name: String,
+    level: i16,
+    race: i16,
+    class: i16,
+    gender: i16,
+    skin_color: i16,
+    face: i16,
+    hair_style: i16,
+    hair_color: i16,
+    facial_style: i16,
+    zone: i32,
+    map: i32,
+    position_x: f32,
+    position_y: f32,
+    position_z: f32,
+    player_flags: i64,
+    at_login: i32,
+    guild: i64,
+    pet_entry: Option<i64>,
+    pet_display: Option<i64>,
+    pet_level: Option<i16>,
+}
+
+impl From<CharacterRow> for Character {
+    fn from(r: CharacterRow) -> Self {
+        Self {
+            id: WowId::new(EntityType::Player, r.guid as u32, 0),
+            account: AccountId(r.account as u32),
+            name: r.name,
+            level: r.level as u8,
+            race: r.race as u8,
+            class: r.class as u8,
+            gender: r.gender as u8,
+            skin_color: r.skin_color as u8,
+            face: r.face as u8,
+            hair_style: r.hair_style as u8,
+            hair_color: r.hair_color as u8,
+            facial_style: r.facial_style as u8,
+            zone: r.zone as u16,
+            map: r.map as u16,
+            position_x: r.position_x,
+            position_y: r.position_y,
+            position_z: r.position_z,
+            player_flags: BitFlags::from_bits_truncate(r.player_flags as u32),
+            at_login: BitFlags::from_bits_truncate(r.at_login as u16),
+            guild: r.guild as u32,
+            pet: r.pet_entry.map(|entry| CharacterPet {
+                entry: entry as u32,
+                display: r.pet_display.unwrap_or(0) as u32,
+                level: r.pet_level.unwrap_or(1) as u8,
+            }),
+        }
+    }
+}
+
+/// The guid a character is stored under.
+fn guid(id: CharacterId) -> i64 {
+    i64::from(WowId::from(id).low())
+}
+
+/// A unix timestamp read from the database as a date.
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, CharacterServiceError> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| CharacterServiceError::PersistError(format!("invalid timestamp {secs}")))
+}
+
+#[derive(Clone, Debug)]
+pub struct PostgresCharacterService {
+    pool: PgPool,
+}
+
+impl PostgresCharacterService {
+    pub fn new(pool: PgPool) -> Self {
+        debug!("Starting character service");
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CharacterService for PostgresCharacterService {
+    async fn get(&self, id: CharacterId) -> Result<Character, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hair_style, c.hair_color, c.facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.player_flags, c.at_login, COALESCE(g.guildid, 0) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.guid = $1",
+        )
+        .bind(guid(id))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+        .map(Character::from)
+        .ok_or(CharacterServiceError::NoSuchCharacter(id))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_by_account(&self, id: AccountId) -> Result<Vec<Character>, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hair_style, c.hair_color, c.facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.player_flags, c.at_login, COALESCE(g.guildid, 0) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.account = $1 ORDER BY c.guid",
+        )
+        .bind(i64::from(id.0))
+        .fetch_all(&self.pool)
+        .await
+        .map(|rows| rows.into_iter().map(Character::from).collect())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Character>, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hair_style, c.hair_color, c.facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.player_flags, c.at_login, COALESCE(g.guildid, 0) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE upper(c.name) = upper($1)",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|row| row.map(Character::from))
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn count_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        query_as::<_, (i64,)>("SELECT count(*) FROM characters WHERE account = $1")
+            .bind(i64::from(id.0))
+            .fetch_one(&self.pool)
+            .await
+            .map(|(c,)| c as usize)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn name_available(&self, name: &str) -> Result<bool, CharacterServiceError> {
+        query_as::<_, (i64,)>("SELECT count(*) FROM characters WHERE upper(name) = upper($1)")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .map(|(c,)| c == 0)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn equipment(&self, id: CharacterId) -> Result<Vec<EquippedItem>, CharacterServiceError> {
+        // enchantments are not stored yet, so nothing is enchanted
+        query_as::<_, (i16, i64)>(
+            "SELECT i.slot, ii.item_entry FROM character_inventory i JOIN item_instance ii ON ii.guid = i.item WHERE i.guid = $1 AND i.bag = 0 AND i.slot < $2 ORDER BY i.slot",
+        )
+        .bind(guid(id))
+        .bind(i16::from(EQUIPMENT_SLOTS))
+        .fetch_all(&self.pool)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|(slot, entry)| EquippedItem {
+                    slot: slot as u8,
+                    entry: entry as u32,
+                    enchant: 0,
+                })
+                .collect()
+        })
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn max_guid(&self, kind: EntityType) -> Result<u32, CharacterServiceError> {
+        let statement = match kind {
+            EntityType::Player => "SELECT COALESCE(MAX(guid), 0) FROM characters",
+            EntityType::ItemOrContainer => "SELECT COALESCE(MAX(guid), 0) FROM item_instance",
+            _ => return Ok(0),
+        };
+        query_as::<_, (i64,)>(statement)
+            .fetch_one(&self.pool)
+            .await
+            .map(|(max,)| max as u32)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn create_character(
+        &self,
+        account: AccountId,
+        create: CharacterCreate,
+    ) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        let guid = i64::from(create.id.low());
+        query(
+            "INSERT INTO characters (guid, account, name, race, class, gender, skin, face, hair_style, hair_color, facial_style, zone, map, position_x, position_y, position_z, orientation, at_login) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)",
+        )
+        .bind(guid)
+        .bind(i64::from(account.0))
+        .bind(&create.name)
+        .bind(i16::from(create.race))
+        .bind(i16::from(create.class))
+        .bind(i16::from(create.gender))
+        .bind(i16::from(create.skin_color))
+        .bind(i16::from(create.face))
+        .bind(i16::from(create.hair_style))
+        .bind(i16::from(create.hair_color))
+        .bind(i16::from(create.facial_style))
+        .bind(i32::from(create.zone))
+        .bind(i32::from(create.map))
+        .bind(create.position_x)
+        .bind(create.position_y)
+        .bind(create.position_z)
+        .bind(create.orientation)
+        .bind(AtLoginFlags::FirstLogin as i32)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for item in create.items {
+            let item_guid = i64::from(item.id.low());
+            query(
+                "INSERT INTO item_instance (guid, item_entry, owner_guid, count, durability) VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(item_guid)
+            .bind(i64::from(item.entry))
+            .bind(guid)
+            .bind(i64::from(item.count))
+            .bind(i32::from(item.durability))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+            query("INSERT INTO character_inventory (guid, bag, slot, item) VALUES ($1, 0, $2, $3)")
+                .bind(guid)
+                .bind(i16::from(item.slot))
+                .bind(item_guid)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for spell in create.spells {
+            query(
+                "INSERT INTO character_spell (guid, spell) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+            )
+            .bind(guid)
+            .bind(i64::from(spell))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for skill in create.skills {
+            query("INSERT INTO character_skills (guid, skill, value, max) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING")
+                .bind(guid)
+                .bind(i32::from(skill.skill))
+                .bind(i32::from(skill.value))
+                .bind(i32::from(skill.max))
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for button in create.action_buttons {
+            query(
+                "INSERT INTO character_action (guid, button, action, type) VALUES ($1, $2, $3, $4)
+    ON CONFLICT (guid, spec, button) DO UPDATE SET action = excluded.action, type = excluded.type",
+            )
+            .bind(guid)
+            .bind(i16::from(button.button))
+            .bind(i64::from(button.action))
+            .bind(i16::from(button.action_type))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid = $1",
+            "DELETE FROM character_inventory WHERE guid = $1",
+            "DELETE FROM character_spell WHERE guid = $1",
+            "DELETE FROM character_skills WHERE guid = $1",
+            "DELETE FROM character_action WHERE guid = $1",
+            "DELETE FROM character_pet WHERE owner = $1",
+            "DELETE FROM guild_member WHERE guid = $1",
+            "DELETE FROM character_banned WHERE guid = $1",
+            "DELETE FROM character_account_data WHERE guid = $1",
+            "DELETE FROM characters WHERE guid = $1",
+        ] {
+            query(statement)
+                .bind(guid(id))
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_inventory WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_spell WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_skills WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_action WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_pet WHERE owner IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM guild_member WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_banned WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM character_account_data WHERE guid IN (SELECT guid FROM characters WHERE $1 IN (account, delete_infos_account))",
+            "DELETE FROM account_data WHERE account_id = $1",
+            "DELETE FROM account_tutorial WHERE account_id = $1",
+        ] {
+            query(statement)
+                .bind(i64::from(id.0))
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        let deleted = query("DELETE FROM characters WHERE $1 IN (account, delete_infos_account)")
+            .bind(i64::from(id.0))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+            .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(deleted as usize)
+    }
+
+    #[instrument(skip(self))]
+    async fn soft_delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        let done = query(
+            "UPDATE characters SET delete_infos_account = account, delete_infos_name = name, delete_date = $1, account = 0, name = '' WHERE guid = $2 AND delete_date IS NULL",
+        )
+        .bind(Utc::now().timestamp())
+        .bind(guid(id))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+
+        query("DELETE FROM guild_member WHERE guid = $1")
+            .bind(guid(id))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn deleted_characters(&self) -> Result<Vec<DeletedCharacter>, CharacterServiceError> {
+        let rows = query_as::<_, (i64, i64, String, i16, i16, i16, i64)>(
+            "SELECT guid, delete_infos_account, delete_infos_name, level, race, class, delete_date FROM characters WHERE delete_date IS NOT NULL ORDER BY delete_date, guid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|(guid, account, name, level, race, class, deleted)| {
+                Ok(DeletedCharacter {
+                    id: WowId::new(EntityType::Player, guid as u32, 0),
+                    account: AccountId(account as u32),
+                    name,
+                    level: level as u8,
+                    race: race as u8,
+                    class: class as u8,
+                    deleted: timestamp(deleted)?,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn restore_character(
+        &self,
+        id: CharacterId,
+        name: &str,
+    ) -> Result<(), CharacterServiceError> {
+        let done = query(
+            "UPDATE characters SET account = delete_infos_account, name = $1, delete_infos_account = NULL, delete_infos_name = NULL, delete_date = NULL WHERE guid = $2 AND delete_date IS NOT NULL",
+        )
+        .bind(name)
+        .bind(guid(id))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<usize, CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_inventory WHERE guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_spell WHERE guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_skills WHERE guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_action WHERE guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_pet WHERE owner IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_banned WHERE guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+            "DELETE FROM character_account_data WHERE guid IN (SELECT guid FROM characters WHERE delete_date < $1)",
+        ] {
+            query(statement)
+                .bind(before.timestamp())
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        let deleted = query("DELETE FROM characters WHERE delete_date < $1")
+            .bind(before.timestamp())
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+            .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        info!("purged {deleted} deleted characters");
+        Ok(deleted as usize)
+    }
+
+    async fn save_character(&self, character: &Character) -> Result<(), CharacterServiceError> {
+        query(
+            "UPDATE characters SET level = $1, zone = $2, map = $3, position_x = $4, position_y = $5, position_z = $6 WHERE guid = $7",
+        )
+        .bind(i16::from(character.level))
+        .bind(i32::from(character.zone))
+        .bind(i32::from(character.map))
+        .bind(character.position_x)
+        .bind(character.position_y)
+        .bind(character.position_z)
+        .bind(i64::from(character.id.low()))
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn set_at_login(
+        &self,
+        id: CharacterId,
+        flags: BitFlags<AtLoginFlags>,
+    ) -> Result<(), CharacterServiceError> {
+        let done = query("UPDATE characters SET at_login = $1 WHERE guid = $2")
+            .bind(i32::from(flags.bits()))
+            .bind(guid(id))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        Ok(())
+    }
+
+    async fn customize_character(
+        &self,
+        character: &Character,
+    ) -> Result<(), CharacterServiceError> {
+        query(
+            "UPDATE characters SET name = $1, race = $2, gender = $3, skin = $4, face = $5, hair_style = $6, hair_color = $7, facial_style = $8, at_login = $9 WHERE guid = $10",
+        )
+        .bind(&character.name)
+        .bind(i16::from(character.race))
+        .bind(i16::from(character.gender))
+        .bind(i16::from(character.skin_color))
+        .bind(i16::from(character.face))
+        .bind(i16::from(character.hair_style))
+        .bind(i16::from(character.hair_color))
+        .bind(i16::from(character.facial_style))
+        .bind(i32::from(character.at_login.bits()))
+        .bind(i64::from(character.id.low()))
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    /// Ban a character from logging in.
+    ///
+    /// note: permabans are represented by a unbandate of the same value as the bandate.
+    #[instrument(skip(self))]
+    async fn ban_character(
+        &self,
+        id: CharacterId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), CharacterServiceError> {
+        let bandate = Utc::now();
+        let unbandate = match duration {
+            Some(d) => chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| bandate.checked_add_signed(d))
+                .ok_or_else(|| {
+                    CharacterServiceError::PersistError(format!("ban duration {d:?} is too long"))
+                })?,
+            None => bandate,
+        };
+
+        query(
+            "INSERT INTO character_banned (guid, bandate, unbandate, bannedby, banreason) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(guid(id))
+        .bind(bandate.timestamp())
+        .bind(unbandate.timestamp())
+        .bind(author)
+        .bind(reason.unwrap_or(""))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        info!("banned character {id:?} for {duration:?}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unban_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let done = query("UPDATE character_banned SET active = FALSE WHERE guid = $1 AND active")
+            .bind(guid(id))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NotBanned(id));
+        }
+
+        info!("unbanned character {id:?}");
+
+        Ok(())
+    }
+
+    async fn character_ban(
+        &self,
+        id: CharacterId,
+    ) -> Result<Option<BanStatus>, CharacterServiceError> {
+        let ban: Option<(i64, i64)> = query_as(
+            "SELECT bandate, unbandate FROM character_banned WHERE guid = $1 AND active AND (unbandate > $2 OR unbandate = bandate) ORDER BY unbandate = bandate DESC, unbandate DESC LIMIT 1",
+        )
+        .bind(guid(id))
+        .bind(Utc::now().timestamp())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(ban.and_then(|(bandate, unbandate)| BanStatus::from_timestamps(bandate, unbandate)))
+    }
+
+    async fn account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+    ) -> Result<AccountData, CharacterServiceError> {
+        let mut rows = query_as::<_, (i16, i64, Vec<u8>)>(
+            "SELECT type, time, data FROM account_data WHERE account_id = $1 UNION ALL SELECT type, time, data FROM character_account_data WHERE guid = $2",
+        )
+        .bind(i64::from(id.0))
+        .bind(character.map(guid))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+        .into_iter()
+        .map(|(kind, time, data)| {
+            (
+                kind,
+                AccountDataCache {
+                    time: time as u32,
+                    data,
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+        Ok(AccountData {
+            config: DualDataCache {
+                global: rows.remove(&0),
+                per_char: rows.remove(&1),
+            },
+            bindings: DualDataCache {
+                global: rows.remove(&2),
+                per_char: rows.remove(&3),
+            },
+            macros: DualDataCache {
+                global: rows.remove(&4),
+                per_char: rows.remove(&5),
+            },
+            per_char_layout: rows.remove(&6),
+            per_char_chat: rows.remove(&7),
+        })
+    }
+
+    async fn set_account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    ) -> Result<(), CharacterServiceError> {
+        let statement = if kind.is_global() {
+            query("INSERT INTO account_data (account_id, type, time, data) VALUES ($1, $2, $3, $4) ON CONFLICT (account_id, type) DO UPDATE SET time = excluded.time, data = excluded.data")
+                .bind(i64::from(id.0))
+        } else {
+            let character = character.ok_or_else(|| {
+                CharacterServiceError::PersistError(format!("{kind:?} is kept per character"))
+            })?;
+            query("INSERT INTO character_account_data (guid, type, time, data) VALUES ($1, $2, $3, $4) ON CONFLICT (guid, type) DO UPDATE SET time = excluded.time, data = excluded.data")
+                .bind(guid(character))
+        };
+
+        statement
+            .bind(i16::from(u8::from(kind)))
+            .bind(i64::from(data.time))
+            .bind(data.data)
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn tutorials(&self, id: AccountId) -> Result<[u32; 8], CharacterServiceError> {
+        let tutorials = query_as::<_, (i64, i64, i64, i64, i64, i64, i64, i64)>(
+            "SELECT tut0, tut1, tut2, tut3, tut4, tut5, tut6, tut7 FROM account_tutorial WHERE account_id = $1",
+        )
+        .bind(i64::from(id.0))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        let mut out = [0u32; 8];
+        if let Some((t0, t1, t2, t3, t4, t5, t6, t7)) = tutorials {
+            for (out, t) in out.iter_mut().zip([t0, t1, t2, t3, t4, t5, t6, t7]) {
+                *out = u32::try_from(t)
+                    .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+            }
+        }
+        Ok(out)
+    }
+
+    async fn set_tutorials(
+        &self,
+        id: AccountId,
+        tutorials: [u32; 8],
+    ) -> Result<(), CharacterServiceError> {
+        tutorials
+            .iter()
+            .fold(
+                query("INSERT INTO account_tutorial (account_id, tut0, tut1, tut2, tut3, tut4, tut5, tut6, tut7) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (account_id) DO UPDATE SET tut0 = excluded.tut0, tut1 = excluded.tut1, tut2 = excluded.tut2, tut3 = excluded.tut3, tut4 = excluded.tut4, tut5 = excluded.tut5, tut6 = excluded.tut6, tut7 = excluded.tut7")
+                    .bind(i64::from(id.0)),
+                |statement, t| statement.bind(i64::from(*t)),
+            )
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::Duration;
+
+    use azerust_game::{
+        accounts::{AccountId, BanStatus},
+        characters::{
+            AccountDataCache, AccountDataType, ActionButton, AtLoginFlags, CharacterCreate,
+            CharacterId, CharacterPet, CharacterService, CharacterSkill, EquippedItem,
+            StartingItem,
+        },
+        EntityType, WowId,
+    };
+    use chrono::Utc;
+    use sqlx::{query, query_as};
+
+    use super::PostgresCharacterService;
+    use crate::{test_pool, unique_name, CHARACTERS_MIGRATOR};
+
+    fn create(id: u32, name: &str) -> CharacterCreate {
+        CharacterCreate {
+            id: WowId::new(EntityType::Player, id, 0),
+            name: name.to_string(),
+            race: 2,
+            class: 7,
+            gender: 0,
+            skin_color: 0,
+            face: 0,
+            hair_style: 0,
+            hair_color: 0,
+            facial_style: 0,
+            zone: 14,
+            map: 1,
+            position_x: -618.518,
+            position_y: -4251.67,
+            position_z: 38.718,
+            orientation: 0.0,
+            items: vec![],
+            spells: vec![],
+            skills: vec![],
+            action_buttons: vec![],
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn create_get_and_delete() {
+        let characters =
+            PostgresCharacterService::new(test_pool("characters", &CHARACTERS_MIGRATOR).await);
+        let name = unique_name("Thrall");
+        // accounts in the character database are only referenced by id
+        let account = AccountId(name.trim_start_matches("Thrall").parse().unwrap());
+
+        characters
+            .create_character(account, create(account.0, &name))
+            .await
+            .unwrap();
+        assert!(characters
+            .create_character(account, create(account.0 + 1, &name.to_lowercase()))
+            .await
+            .is_err());
+        assert!(!characters
+            .name_available(&name.to_uppercase())
+            .await
+            .unwrap());
+        assert_eq!(characters.count_by_account(account).await.unwrap(), 1);
+
+        let thrall = characters.get_by_name(&name).await.unwrap().unwrap();
+        let id = CharacterId::try_from(thrall.id).unwrap();
+        assert_eq!(characters.get(id).await.unwrap().name, name);
+
+        characters
+            .ban_character(id, "jaina", Some(Duration::from_secs(60)), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            characters.character_ban(id).await.unwrap(),
+            Some(BanStatus::Temporary { .. })
+        ));
+
+        assert_eq!(characters.delete_by_account(account).await.unwrap(), 1);
+        assert!(characters.get(id).await.is_err());
+        assert_eq!(characters.character_ban(id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn create_with_starting_data() {
+        let pool = test_pool("characters", &CHARACTERS_MIGRATOR).await;
+        let characters = PostgresCharacterService::new(pool.clone());
+        let name = unique_name("Garrosh");
+        let account = AccountId(name.trim_start_matches("Garrosh").parse().unwrap());
+
+        let item = |low, entry, slot| StartingItem {
+            id: WowId::new(EntityType::ItemOrContainer, low, 0),
+            entry,
+            count: 1,
+            slot,
+            durability: 0,
+        };
+        characters
+            .create_character(
+                account,
+                CharacterCreate {
+                    items: vec![
+                        item(account.0, 6125, 3),
+                        item(account.0 + 1, 6126, 6),
+                        item(account.0 + 2, 12282, 15),
+                    ],
+                    spells: vec![78, 2457],
+                    skills: vec![CharacterSkill {
+                        skill: 26,
+                        value: 1,
+                        max: 5,
+                    }],
+                    action_buttons: vec![ActionButton {
+                        button: 0,
+                        action: 6603,
+                        action_type: 0,
+                    }],
+                    ..create(account.0, &name)
+                },
+            )
+            .await
+            .unwrap();
+
+        let garrosh = characters.get_by_name(&name).await.unwrap().unwrap();
+        let id = CharacterId::try_from(garrosh.id).unwrap();
+        let guid = i64::from(garrosh.id.low());
+        let count = |table: &str| {
+            let statement = format!("SELECT count(*) FROM {table} WHERE guid = $1");
+            let pool = pool.clone();
+            async move {
+                query_as::<_, (i64,)>(&statement)
+                    .bind(guid)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+                    .0
+            }
+        };
+        assert_eq!(count("character_inventory").await, 3);
+        assert_eq!(count("character_spell").await, 2);
+        assert_eq!(count("character_skills").await, 1);
+        assert_eq!(count("character_action").await, 1);
+        let equipped = |slot, entry| EquippedItem {
+            slot,
+            entry,
+            enchant: 0,
+        };
+        assert_eq!(
+            characters.equipment(id).await.unwrap(),
+            vec![equipped(3, 6125), equipped(6, 6126), equipped(15, 12282)]
+        );
+        assert!(
+            characters
+                .max_guid(EntityType::ItemOrContainer)
+                .await
+                .unwrap()
+                >= account.0 + 2
+        );
+
+        characters.delete_character(id).await.unwrap();
+        assert_eq!(count("character_inventory").await, 0);
+        assert_eq!(count("character_spell").await, 0);
+        assert_eq!(count("character_skills").await, 0);
+        assert_eq!(count("character_action").await, 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn get_with_guild_and_pet() {
+        let pool = test_pool("characters", &CHARACTERS_MIGRATOR).await;
+        let characters = PostgresCharacterService::new(pool.clone());
+        let name = unique_name("Rexxar");
+        let account = AccountId(name.trim_start_matches("Rexxar").parse().unwrap());
+        characters
+            .create_character(account, create(account.0, &name))
+            .await
+            .unwrap();
+
+        let rexxar = characters.get_by_name(&name).await.unwrap().unwrap();
+        assert_eq!(rexxar.at_login, AtLoginFlags::FirstLogin);
+        assert_eq!(rexxar.guild, 0);
+        assert_eq!(rexxar.pet, None);
+
+        let guid = i64::from(account.0);
+        for statement in [
+            "INSERT INTO guild_member (guildid, guid) VALUES (7, $1)",
+            "INSERT INTO character_pet (id, entry, owner, modelid, level) VALUES ($1, 3475, $1, 2709, 10)",
+        ] {
+            query(statement).bind(guid).execute(&pool).await.unwrap();
+        }
+
+        let rexxar = characters.get_by_name(&name).await.unwrap().unwrap();
+        assert_eq!(rexxar.guild, 7);
+        assert_eq!(
+            rexxar.pet,
+            Some(CharacterPet {
+                entry: 3475,
+                display: 2709,
+                level: 10
+            })
+        );
+
+        characters
+            .delete_character(CharacterId::try_from(rexxar.id).unwrap())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn soft_delete_restore_and_purge() {
+        let characters =
+            PostgresCharacterService::new(test_pool("characters", &CHARACTERS_MIGRATOR).await);
+        let name = unique_name("Cairne");
+        let account = AccountId(name.trim_start_matches("Cairne").parse().unwrap());
+        characters
+            .create_character(account, create(account.0, &name))
+            .await
+            .unwrap();
+        let id = CharacterId::try_from(WowId::new(EntityType::Player, account.0, 0)).unwrap();
+
+        characters.soft_delete_character(id).await.unwrap();
+        assert!(characters.soft_delete_character(id).await.is_err());
+        assert!(characters.get_by_account(account).await.unwrap().is_empty());
+        assert!(characters.name_available(&name).await.unwrap());
+
+        let deleted = characters.deleted_characters().await.unwrap();
+        let cairne = deleted
+            .iter()
+            .find(|c| c.id == WowId::new(EntityType::Player, account.0, 0))
+            .unwrap();
+        assert_eq!(cairne.account, account);
+        assert_eq!(cairne.name, name);
+
+        characters.restore_character(id, &name).await.unwrap();
+        assert_eq!(characters.get(id).await.unwrap().account, account);
+
+        characters.soft_delete_character(id).await.unwrap();
+        characters
+            .purge_deleted(Utc::now() + chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert!(characters.get(id).await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn account_data_per_character() {
+        let characters =
+            PostgresCharacterService::new(test_pool("characters", &CHARACTERS_MIGRATOR).await);
+        let name = unique_name("Data");
+        let account = AccountId(name.trim_start_matches("Data").parse().unwrap());
+        let id = CharacterId::try_from(WowId::new(EntityType::Player, account.0, 0)).unwrap();
+        let cache = |time, data: &[u8]| AccountDataCache {
+            time,
+            data: data.to_vec(),
+        };
+
+        for (character, kind, data) in [
+            (None, AccountDataType::GlobalMacros, cache(1, b"a")),
+            (None, AccountDataType::GlobalMacros, cache(2, b"b")),
+            (Some(id), AccountDataType::CharacterChat, cache(3, b"c")),
+        ] {
+            characters
+                .set_account_data(account, character, kind, data)
+                .await
+                .unwrap();
+        }
+
+        let data = characters.account_data(account, Some(id)).await.unwrap();
+        let macros = data.get(AccountDataType::GlobalMacros).unwrap();
+        assert_eq!((macros.time, macros.data.as_slice()), (2, &b"b"[..]));
+        let chat = data.get(AccountDataType::CharacterChat).unwrap();
+        assert_eq!((chat.time, chat.data.as_slice()), (3, &b"c"[..]));
+
+        let data = characters.account_data(account, None).await.unwrap();
+        assert!(data.get(AccountDataType::CharacterChat).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn tutorials_are_stored() {
+        let characters =
+            PostgresCharacterService::new(test_pool("characters", &CHARACTERS_MIGRATOR).await);
+        let name = unique_name("Tutorial");
+        let account = AccountId(name.trim_start_matches("Tutorial").parse().unwrap());
+
+        assert_eq!(characters.tutorials(account).await.unwrap(), [0; 8]);
+        for tutorials in [
+            [1, 2, 3, 4, 5, 6, 7, u32::MAX],
+            [8, 7, 6, 5, 4, 3, 2, u32::MAX],
+        ] {
+            characters.set_tutorials(account, tutorials).await.unwrap();
+        }
+        assert_eq!(
+            characters.tutorials(account).await.unwrap(),
+            [8, 7, 6, 5, 4, 3, 2, u32::MAX]
+        );
+
+        characters.delete_by_account(account).await.unwrap();
+        assert_eq!(characters.tutorials(account).await.unwrap(), [0; 8]);
+    }
+}
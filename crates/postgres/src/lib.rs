@@ -0,0 +1,71 @@
+//! postgres
+//!
+//! Account, realm and character services backed by PostgreSQL. Like
+//! the MySQL backend, the auth and character tables live in separate
+//! databases with their own migrations.
+
+use sqlx::{migrate::Migrator, PgPool};
+
+pub mod accounts;
+pub mod characters;
+pub mod realms;
+
+pub use accounts::PostgresAccountService;
+pub use characters::PostgresCharacterService;
+pub use realms::PostgresRealmList;
+
+/// The migrations for the auth database.
+pub static AUTH_MIGRATOR: Migrator = sqlx::migrate!("migrations/auth");
+
+/// The migrations for a character database.
+pub static CHARACTERS_MIGRATOR: Migrator = sqlx::migrate!("migrations/characters");
+
+/// Connect to the database at the given url. The schema is left
+/// as it is, see [`AUTH_MIGRATOR`] and [`CHARACTERS_MIGRATOR`].
+pub async fn connect(url: &str) -> Result<PgPool, sqlx::Error> {
+    PgPool::connect(url).await
+}
+
+/// Connect to the database used by the tests. These need a live
+/// server, so they are ignored unless run with `--ignored`. Each
+/// set of migrations gets its own schema in that database, as
+/// they would otherwise share a migrations table.
+#[cfg(test)]
+async fn test_pool(schema: &'static str, migrator: &Migrator) -> PgPool {
+    use sqlx::{postgres::PgPoolOptions, Executor};
+
+    let url = std::env::var("POSTGRES_URL").expect("POSTGRES_URL should be set");
+    let pool = connect(&url).await.expect("could not connect to postgres");
+    // another test may be creating it at the same time
+    let _ = pool
+        .execute(format!("CREATE SCHEMA IF NOT EXISTS {schema}").as_str())
+        .await;
+
+    let pool = PgPoolOptions::new()
+        .after_connect(move |conn| {
+            Box::pin(async move {
+                conn.execute(format!("SET search_path TO {schema}").as_str())
+                    .await
+                    .map(|_| ())
+            })
+        })
+        .connect(&url)
+        .await
+        .expect("could not connect to postgres");
+    migrator
+        .run(&pool)
+        .await
+        .expect("could not migrate postgres");
+    pool
+}
+
+/// A name that no other test run will have used, as the
+/// tests share a database rather than starting from scratch.
+#[cfg(test)]
+fn unique_name(prefix: &str) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    format!("{prefix}{nanos}")
+}
@@ -0,0 +1,337 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::AccountId,
+    realms::{Realm, RealmFlags, RealmId, RealmList, RealmListError, RealmRegistration, RealmType},
+};
+use enumflags2::BitFlags;
+use sqlx::{query, query_as, FromRow, PgPool};
+use tracing::{debug, error, trace};
+
+/// A realm as it is stored in the database.
+#[derive(FromRow)]
+struct RealmRow {
+    id: i64,
+    name: String,
+    realm_type: i16,
+    build: i64,
+    external_address: String,
+    local_address: String,
+    local_subnet_mask: String,
+    port: i32,
+    flags: i16,
+    timezone: i16,
+    population: f32,
+}
+
+impl TryFrom<RealmRow> for Realm {
+    type Error = String;
+
+    fn try_from(r: RealmRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: RealmId(r.id as u32),
+            name: r.name,
+            realm_type: match r.realm_type {
+                0 => RealmType::Normal,
+                1 => RealmType::PVP,
+                6 => RealmType::RP,
+                8 => RealmType::RPPvP,
+                other => return Err(format!("unknown realm type {other}")),
+            },
+            build: r.build as u32,
+            external_address: r.external_address,
+            local_address: r.local_address,
+            local_subnet_mask: r.local_subnet_mask,
+            port: r.port as u16,
+            flags: BitFlags::from_bits_truncate(r.flags as u8),
+            timezone: r.timezone as u8,
+            population: r.population,
+        })
+    }
+}
+
+/// A realmlist stored in Postgres.
+#[derive(Clone, Debug)]
+pub struct PostgresRealmList {
+    pool: PgPool,
+}
+
+impl PostgresRealmList {
+    pub fn new(pool: PgPool) -> Self {
+        debug!("Starting realmlist service");
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RealmList for PostgresRealmList {
+    async fn realms(&self) -> Vec<Realm> {
+        match query_as::<_, RealmRow>(
+            "SELECT id, name, icon AS realm_type, gamebuild AS build, address AS external_address, local_address, local_subnet_mask, port, flag AS flags, timezone, population FROM realmlist WHERE flag <> 3 ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        {
+            Ok(realms) => realms
+                .into_iter()
+                .filter_map(|r| {
+                    Realm::try_from(r)
+                        .map_err(|e| error!("skipping realm: {e}"))
+                        .ok()
+                })
+                .collect(),
+            Err(e) => {
+                error!("could not load realms: {e}");
+                vec![]
+            }
+        }
+    }
+
+    async fn reload(&self) {}
+
+    async fn register(&self, realm: &RealmRegistration) -> Result<(), RealmListError> {
+        debug!("Registering realm {:?} ({})", realm.id, realm.name);
+        query(
+            "INSERT INTO realmlist (id, name, address, local_address, local_subnet_mask, port, icon, flag, timezone, gamebuild)
+    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+    ON CONFLICT (id) DO UPDATE SET
+        name = excluded.name, address = excluded.address, local_address = excluded.local_address,
+        local_subnet_mask = excluded.local_subnet_mask, port = excluded.port, icon = excluded.icon,
+        timezone = excluded.timezone, gamebuild = excluded.gamebuild
+    ",
+        )
+        .bind(i64::from(realm.id.0))
+        .bind(&realm.name)
+        .bind(&realm.external_address)
+        .bind(&realm.local_address)
+        .bind(&realm.local_subnet_mask)
+        .bind(i32::from(realm.port))
+        .bind(i16::from(u8::from(realm.realm_type)))
+        .bind(RealmFlags::Offline as i16)
+        .bind(i16::from(realm.timezone))
+        .bind(i64::from(realm.build))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn update_status(&self, status: Vec<(RealmId, bool)>) -> Result<(), RealmListError> {
+        let offline = RealmFlags::Offline as i16;
+        for (id, online) in status {
+            query("UPDATE realmlist SET flag = (flag & ~$1) | $2 WHERE id = $3")
+                .bind(offline)
+                .bind(if online { 0 } else { offline })
+                .bind(i64::from(id.0))
+                .execute(&self.pool)
+                .await
+                .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_uptime(
+        &self,
+        id: RealmId,
+        start: SystemTime,
+        population: u32,
+    ) -> Result<(), RealmListError> {
+        let start_u32: u32 = start
+            .duration_since(UNIX_EPOCH)
+            .expect("no time can be smaller than UNIX_EPOCH")
+            .as_secs()
+            .try_into()
+            .map_err(|_| RealmListError::StartTimeTooLarge)?;
+
+        let uptime = SystemTime::now()
+            .duration_since(start)
+            .map_err(|_| RealmListError::StartTimeInFuture)?;
+
+        let uptime_u32: u32 = uptime
+            .as_secs()
+            .try_into()
+            .expect("server won't be up for 40 years");
+
+        trace!("setting uptime for realm {:?} to {:?}", id, uptime);
+        query(
+            "INSERT INTO uptime (realm_id, starttime, uptime, maxplayers, revision)
+    VALUES ($1, $2, $3, $4, 'azerust-0.1.0')
+    ON CONFLICT (realm_id, starttime) DO UPDATE SET
+        uptime = excluded.uptime, maxplayers = excluded.maxplayers
+    ",
+        )
+        .bind(i64::from(id.0))
+        .bind(i64::from(start_u32))
+        .bind(i64::from(uptime_u32))
+        .bind(i64::from(population))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn request_shutdown(
+        &self,
+        id: RealmId,
+        at: Option<SystemTime>,
+    ) -> Result<(), RealmListError> {
+        let at: u32 = match at {
+            Some(at) => at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .try_into()
+                .map_err(|_| RealmListError::ShutdownTimeTooLarge)?,
+            None => {
+                query("DELETE FROM realm_shutdown WHERE realm_id = $1")
+                    .bind(i64::from(id.0))
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+                return Ok(());
+            }
+        };
+
+        query("INSERT INTO realm_shutdown (realm_id, shutdown_at) VALUES ($1, $2) ON CONFLICT (realm_id) DO UPDATE SET shutdown_at = excluded.shutdown_at")
+            .bind(i64::from(id.0))
+            .bind(i64::from(at))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn requested_shutdown(&self, id: RealmId) -> Result<Option<SystemTime>, RealmListError> {
+        let at =
+            query_as::<_, (i64,)>("SELECT shutdown_at FROM realm_shutdown WHERE realm_id = $1")
+                .bind(i64::from(id.0))
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(at.map(|(at,)| UNIX_EPOCH + Duration::from_secs(at.try_into().unwrap_or_default())))
+    }
+
+    async fn set_character_count(
+        &self,
+        id: RealmId,
+        account: AccountId,
+        count: u8,
+    ) -> Result<(), RealmListError> {
+        query("INSERT INTO realm_characters (realm_id, account_id, num_chars) VALUES ($1, $2, $3) ON CONFLICT (realm_id, account_id) DO UPDATE SET num_chars = excluded.num_chars")
+            .bind(i64::from(id.0))
+            .bind(i64::from(account.0))
+            .bind(i16::from(count))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn character_count(&self, account: AccountId) -> Result<usize, RealmListError> {
+        let (count,) = query_as::<_, (i64,)>(
+            "SELECT COALESCE(SUM(num_chars), 0) FROM realm_characters WHERE account_id = $1",
+        )
+        .bind(i64::from(account.0))
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(count.try_into().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use azerust_game::{
+        accounts::AccountId,
+        realms::{Realm, RealmId, RealmList, RealmRegistration, RealmType},
+    };
+
+    use super::PostgresRealmList;
+    use crate::{test_pool, unique_name, AUTH_MIGRATOR};
+
+    fn registration(id: RealmId, name: &str) -> RealmRegistration {
+        RealmRegistration {
+            id,
+            name: name.to_string(),
+            realm_type: RealmType::PVP,
+            build: 12340,
+            external_address: "127.0.0.1".to_string(),
+            local_address: "127.0.0.1".to_string(),
+            local_subnet_mask: "255.255.255.0".to_string(),
+            port: 8085,
+            timezone: 1,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn register_and_update_status() {
+        let realms = PostgresRealmList::new(test_pool("auth", &AUTH_MIGRATOR).await);
+        let name = unique_name("Azeroth");
+        let id = RealmId(name.trim_start_matches("Azeroth").parse().unwrap());
+        let find = |realms: Vec<Realm>| realms.into_iter().find(|r| r.id == id).unwrap();
+
+        realms.register(&registration(id, &name)).await.unwrap();
+        let realm = find(realms.realms().await);
+        assert_eq!(realm.name, name);
+        assert!(!realm.is_online());
+
+        realms.update_status(vec![(id, true)]).await.unwrap();
+        let renamed = unique_name("Outland");
+        realms.register(&registration(id, &renamed)).await.unwrap();
+        let realm = find(realms.realms().await);
+        assert_eq!(realm.name, renamed);
+        assert!(realm.is_online());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn request_shutdown() {
+        let realms = PostgresRealmList::new(test_pool("auth", &AUTH_MIGRATOR).await);
+        let name = unique_name("Shutdown");
+        let id = RealmId(name.trim_start_matches("Shutdown").parse().unwrap());
+        let at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        assert_eq!(realms.requested_shutdown(id).await.unwrap(), None);
+        realms.request_shutdown(id, Some(at)).await.unwrap();
+        assert_eq!(realms.requested_shutdown(id).await.unwrap(), Some(at));
+        realms.request_shutdown(id, None).await.unwrap();
+        assert_eq!(realms.requested_shutdown(id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a postgres database at POSTGRES_URL"]
+    pub async fn character_count() {
+        let realms = PostgresRealmList::new(test_pool("auth", &AUTH_MIGRATOR).await);
+        let name = unique_name("Characters");
+        let account = AccountId(name.trim_start_matches("Characters").parse().unwrap());
+
+        assert_eq!(realms.character_count(account).await.unwrap(), 0);
+        realms
+            .set_character_count(RealmId(1), account, 3)
+            .await
+            .unwrap();
+        realms
+            .set_character_count(RealmId(2), account, 4)
+            .await
+            .unwrap();
+        assert_eq!(realms.character_count(account).await.unwrap(), 7);
+        realms
+            .set_character_count(RealmId(1), account, 1)
+            .await
+            .unwrap();
+        assert_eq!(realms.character_count(account).await.unwrap(), 5);
+    }
+}
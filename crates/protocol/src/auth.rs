@@ -57,7 +57,9 @@ pub enum ReturnCode {
 impl From<LoginFailure> for ReturnCode {
     fn from(f: LoginFailure) -> Self {
         match f {
-            LoginFailure::Suspended => ReturnCode::Suspended,
+            // the challenge response has no room for the expiry, so the
+            // client is only told that the account is suspended
+            LoginFailure::Suspended { .. } => ReturnCode::Suspended,
             LoginFailure::Banned => ReturnCode::Banned,
             LoginFailure::UnknownAccount => ReturnCode::UnknownAccount,
             LoginFailure::IncorrectPassword => ReturnCode::IncorrectPassword,
@@ -1,11 +1,11 @@
 use azerust_game::{
-    characters::{AccountData, Character},
+    characters::{AccountData, AccountDataCache, AccountDataType, Character},
     realms::RealmId,
     WowId,
 };
 use num_enum::IntoPrimitive;
-use serde::Serialize;
-use world::ResponseCode;
+use serde::{Deserialize, Serialize};
+use world::{ChatType, ResponseCode, ServerMessageType};
 
 #[cfg(feature = "auth")]
 pub mod auth;
@@ -43,8 +43,8 @@ pub struct AuthSession {
 #[derive(Debug, Clone)]
 pub struct Addon {
     pub name: String,
-    #[allow(dead_code)]
-    signature: bool,
+    /// Whether the client wants the addon's signature checked
+    pub signature: bool,
     pub crc: u32,
     #[allow(dead_code)]
     crc2: u32,
@@ -61,6 +61,18 @@ impl Addon {
     }
 }
 
+/// An addon that clients are told to disable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BannedAddon {
+    pub name: String,
+    /// The version that is banned, or empty for every version
+    #[serde(default)]
+    pub version: String,
+    /// When the addon was banned, as a unix timestamp
+    #[serde(default)]
+    pub timestamp: u32,
+}
+
 #[derive(Debug)]
 pub enum ClientPacket {
     AuthSession(AuthSession),
@@ -70,6 +82,20 @@ pub enum ClientPacket {
         latency: u32,
     },
     ReadyForAccountDataTimes,
+    RequestAccountData(AccountDataType),
+    UpdateAccountData {
+        kind: AccountDataType,
+        /// When the client last changed the data
+        time: u32,
+        /// The decompressed data, which is empty if the client cleared it
+        data: Vec<u8>,
+    },
+    /// Mark a tutorial as seen
+    TutorialFlag(u32),
+    /// Mark every tutorial as seen
+    TutorialClear,
+    /// Show every tutorial again
+    TutorialReset,
     CharEnum,
     RealmSplit {
         realm: u32,
@@ -87,6 +113,40 @@ pub enum ClientPacket {
     },
     PlayerLogin(WowId),
     CharacterDelete(WowId),
+    CharacterRename {
+        id: WowId,
+        name: String,
+    },
+    CharacterCustomize {
+        id: WowId,
+        name: String,
+        gender: u8,
+        skin_color: u8,
+        face: u8,
+        hair_style: u8,
+        hair_color: u8,
+        facial_style: u8,
+    },
+    CharacterRaceChange {
+        id: WowId,
+        name: String,
+        race: u8,
+        gender: u8,
+        skin_color: u8,
+        face: u8,
+        hair_style: u8,
+        hair_color: u8,
+        facial_style: u8,
+        /// Whether the client asked to change faction rather than race
+        faction: bool,
+    },
+    MessageChat {
+        kind: ChatType,
+        language: u32,
+        /// The whisper target or channel name, for messages that have one.
+        target: Option<String>,
+        message: String,
+    },
 }
 
 #[derive(Debug, Serialize, Clone, Copy)]
@@ -96,16 +156,55 @@ pub struct Item {
     pub aura: u32,
 }
 
+/// A character as it is listed on the character screen.
+#[derive(Debug)]
+pub struct CharacterListing {
+    pub character: Character,
+    /// Whether the character is banned
+    pub locked: bool,
+    /// The creature family of the character's pet, or 0
+    pub pet_family: u8,
+    pub items: [Item; 23],
+}
+
 #[derive(Debug)]
 pub enum ServerPacket {
     AuthResponse,
-    AddonInfo(Vec<Addon>),
+    AddonInfo {
+        addons: Vec<Addon>,
+        banned: Vec<BannedAddon>,
+    },
     ClientCacheVersion(u32),
-    TutorialData,
+    TutorialData([u32; 8]),
     Pong(u32),
-    CharEnum(Vec<(Character, [Item; 23])>),
-    AccountDataTimes(Box<AccountData>),
+    CharEnum(Vec<CharacterListing>),
+    AccountDataTimes {
+        /// The types of data to send the times of
+        mask: u32,
+        data: Box<AccountData>,
+    },
+    UpdateAccountData {
+        /// The character the data is for, if it is per character
+        id: Option<WowId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    },
+    UpdateAccountDataComplete(AccountDataType),
     RealmSplit { realm: u32 },
     CharacterCreate(ResponseCode),
     CharacterDelete(ResponseCode),
+    CharacterRename(Result<(WowId, String), ResponseCode>),
+    CharacterCustomize(Result<Box<Character>, ResponseCode>),
+    CharacterRaceChange(Result<Box<Character>, ResponseCode>),
+    ServerMessage(ServerMessageType, String),
+    CharacterLoginFailed(ResponseCode),
+    MessageChat {
+        kind: ChatType,
+        language: u32,
+        sender: WowId,
+        receiver: WowId,
+        message: String,
+    },
+    ChatPlayerNotFound(String),
+    Notification(String),
 }
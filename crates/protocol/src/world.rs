@@ -70,7 +70,7 @@ pub enum OpCode {
     // SmsgNewWorld = 0x03E,
     // SmsgTransferPending = 0x03F,
     // SmsgTransferAborted = 0x040,
-    // SmsgCharacterLoginFailed = 0x041,
+    SmsgCharacterLoginFailed = 0x041,
     // SmsgLoginSettimespeed = 0x042,
     // SmsgGametimeUpdate = 0x043,
     // CmsgGametimeSet = 0x044,
@@ -154,8 +154,8 @@ pub enum OpCode {
     // SmsgGuildEvent = 0x092,
     // SmsgGuildCommandResult = 0x093,
     // UmsgUpdateGuild = 0x094,
-    // CmsgMessagechat = 0x095,
-    // SmsgMessagechat = 0x096,
+    CmsgMessagechat = 0x095,
+    SmsgMessagechat = 0x096,
     // CmsgJoinChannel = 0x097,
     // CmsgLeaveChannel = 0x098,
     // SmsgChannelNotify = 0x099,
@@ -259,9 +259,9 @@ pub enum OpCode {
     // CmsgNextCinematicCamera = 0x0FB,
     // CmsgCompleteCinematic = 0x0FC,
     SmsgTutorialFlags = 0x0FD,
-    // CmsgTutorialFlag = 0x0FE,
-    // CmsgTutorialClear = 0x0FF,
-    // CmsgTutorialReset = 0x100,
+    CmsgTutorialFlag = 0x0FE,
+    CmsgTutorialClear = 0x0FF,
+    CmsgTutorialReset = 0x100,
     // CmsgStandstatechange = 0x101,
     // CmsgEmote = 0x102,
     // SmsgEmote = 0x103,
@@ -464,7 +464,7 @@ pub enum OpCode {
     // SmsgFishNotHooked = 0x1C8,
     // SmsgFishEscaped = 0x1C9,
     // CmsgBug = 0x1CA,
-    // SmsgNotification = 0x1CB,
+    SmsgNotification = 0x1CB,
     CmsgPlayedTime = 0x1CC,
     SmsgPlayedTime = 0x1CD,
     CmsgQueryTime = 0x1CE,
@@ -662,7 +662,7 @@ pub enum OpCode {
     // CmsgGroupRaidConvert = 0x28E,
     // CmsgGroupAssistantLeader = 0x28F,
     // CmsgBuybackItem = 0x290,
-    // SmsgServerMessage = 0x291,
+    SmsgServerMessage = 0x291,
     // CmsgSetSavedInstanceExtend = 0x292,
     // SmsgLfgOfferContinue = 0x293,
     // CmsgTestDropRate = 0x294,
@@ -686,7 +686,7 @@ pub enum OpCode {
     // SmsgSpellFailedOther = 0x2A6,
     // SmsgGameobjectResetState = 0x2A7,
     // CmsgRepairItem = 0x2A8,
-    // SmsgChatPlayerNotFound = 0x2A9,
+    SmsgChatPlayerNotFound = 0x2A9,
     // MsgTalentWipeConfirm = 0x2AA,
     // SmsgSummonRequest = 0x2AB,
     // CmsgSummonResponse = 0x2AC,
@@ -716,8 +716,8 @@ pub enum OpCode {
     // CmsgItemNameQuery = 0x2C4,
     // SmsgItemNameQueryResponse = 0x2C5,
     // SmsgPetActionFeedback = 0x2C6,
-    // CmsgCharRename = 0x2C7,
-    // SmsgCharRename = 0x2C8,
+    CmsgCharRename = 0x2C7,
+    SmsgCharRename = 0x2C8,
     // CmsgMoveSplineDone = 0x2C9,
     // CmsgMoveFallReset = 0x2CA,
     // SmsgInstanceSaveCreated = 0x2CB,
@@ -1128,7 +1128,7 @@ pub enum OpCode {
     // SmsgCalendarEventInviteNotes = 0x460,
     // SmsgCalendarEventInviteNotesAlert = 0x461,
     // CmsgUpdateMissileTrajectory = 0x462,
-    // SmsgUpdateAccountDataComplete = 0x463,
+    SmsgUpdateAccountDataComplete = 0x463,
     // SmsgTriggerMovie = 0x464,
     // CmsgCompleteMovie = 0x465,
     // CmsgSetGlyphSlot = 0x466,
@@ -1144,8 +1144,8 @@ pub enum OpCode {
     // CmsgSetCriteriaCheat = 0x470,
     // SmsgCalendarRaidLockoutUpdated = 0x471,
     // CmsgUnitanimtierCheat = 0x472,
-    // CmsgCharCustomize = 0x473,
-    // SmsgCharCustomize = 0x474,
+    CmsgCharCustomize = 0x473,
+    SmsgCharCustomize = 0x474,
     // SmsgPetRenameable = 0x475,
     // CmsgRequestVehicleExit = 0x476,
     // CmsgRequestVehiclePrevSeat = 0x477,
@@ -1246,8 +1246,8 @@ pub enum OpCode {
     // SmsgEquipmentSetUseResult = 0x4D6,
     // CmsgForceAnim = 0x4D7,
     // SmsgForceAnim = 0x4D8,
-    // CmsgCharFactionChange = 0x4D9,
-    // SmsgCharFactionChange = 0x4DA,
+    CmsgCharFactionChange = 0x4D9,
+    SmsgCharFactionChange = 0x4DA,
     // CmsgPvpQueueStatsRequest = 0x4DB,
     // SmsgPvpQueueStats = 0x4DC,
     // CmsgSetPaidServiceCheat = 0x4DD,
@@ -1277,7 +1277,7 @@ pub enum OpCode {
     // SmsgServerinfo = 0x4F5,
     CmsgWorldStateUiTimerUpdate = 0x4F6,
     SmsgWorldStateUiTimerUpdate = 0x4F7,
-    // CmsgCharRaceChange = 0x4F8,
+    CmsgCharRaceChange = 0x4F8,
     // MsgViewPhaseShift = 0x4F9,
     // SmsgTalentsInvoluntarilyReset = 0x4FA, // uint8
     // CmsgDebugServerGeo = 0x4FB,
@@ -1319,11 +1319,49 @@ pub enum OpCode {
     // NumMsgTypes = 0x51F,
 }
 
+/// The kinds of message that can be sent with `SMSG_SERVER_MESSAGE`.
+/// The client formats the message text according to its kind.
+#[repr(u32)]
+#[derive(Serialize, IntoPrimitive, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(into = "u32")]
+pub enum ServerMessageType {
+    ShutdownTime = 1,
+    RestartTime = 2,
+    String = 3,
+    ShutdownCancelled = 4,
+    RestartCancelled = 5,
+}
+
+/// The kinds of chat message. Only the kinds that players can
+/// send are listed here.
+#[repr(u8)]
+#[derive(Serialize, IntoPrimitive, TryFromPrimitive, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(into = "u8")]
+pub enum ChatType {
+    Say = 0x01,
+    Party = 0x02,
+    Raid = 0x03,
+    Guild = 0x04,
+    Officer = 0x05,
+    Yell = 0x06,
+    Whisper = 0x07,
+    WhisperInform = 0x09,
+    Emote = 0x0A,
+    Channel = 0x11,
+    Afk = 0x17,
+    Dnd = 0x18,
+    RaidLeader = 0x27,
+    RaidWarning = 0x28,
+    Battleground = 0x2C,
+    BattlegroundLeader = 0x2D,
+    PartyLeader = 0x33,
+}
+
 #[repr(u32)]
 #[derive(Serialize, IntoPrimitive, Debug, PartialEq, Eq, Clone, Copy)]
 #[serde(into = "u32")]
 pub enum ResponseCode {
-    // ResponseSuccess = 0,
+    ResponseSuccess = 0,
     // ResponseFailure = 1,
     // ResponseCancelled = 2,
     // ResponseDisconnected = 3,
@@ -1375,22 +1413,22 @@ pub enum ResponseCode {
     // CharCreateFailed = 49,
     CharCreateNameInUse = 50,
     // CharCreateDisabled = 51,
-    // CharCreatePvpTeamsViolation = 52,
-    // CharCreateServerLimit = 53,
-    // CharCreateAccountLimit = 54,
+    CharCreatePvpTeamsViolation = 52,
+    CharCreateServerLimit = 53,
+    CharCreateAccountLimit = 54,
     // CharCreateServerQueue = 55,
     // CharCreateOnlyExisting = 56,
-    // CharCreateExpansion = 57,
-    // CharCreateExpansionClass = 58,
-    // CharCreateLevelRequirement = 59,
-    // CharCreateUniqueClassLimit = 60,
+    CharCreateExpansion = 57,
+    CharCreateExpansionClass = 58,
+    CharCreateLevelRequirement = 59,
+    CharCreateUniqueClassLimit = 60,
     // CharCreateCharacterInGuild = 61,
-    // CharCreateRestrictedRaceclass = 62,
+    CharCreateRestrictedRaceclass = 62,
     // CharCreateCharacterChooseRace = 63,
     // CharCreateCharacterArenaLeader = 64,
     // CharCreateCharacterDeleteMail = 65,
-    // CharCreateCharacterSwapFaction = 66,
-    // CharCreateCharacterRaceOnly = 67,
+    CharCreateCharacterSwapFaction = 66,
+    CharCreateCharacterRaceOnly = 67,
     // CharCreateCharacterGoldLimit = 68,
     // CharCreateForceLogin = 69,
     // CharDeleteInProgress = 70,
@@ -1406,22 +1444,22 @@ pub enum ResponseCode {
     // CharLoginNoInstances = 80,
     // CharLoginFailed = 81,
     // CharLoginDisabled = 82,
-    // CharLoginNoCharacter = 83,
+    CharLoginNoCharacter = 83,
     // CharLoginLockedForTransfer = 84,
-    // CharLoginLockedByBilling = 85,
+    CharLoginLockedByBilling = 85,
     // CharLoginLockedByMobileAh = 86,
     // CharNameSuccess = 87,
     CharNameFailure = 88,
     CharNameNoName = 89,
     CharNameTooShort = 90,
     CharNameTooLong = 91,
-    // CharNameInvalidCharacter = 92,
-    // CharNameMixedLanguages = 93,
-    // CharNameProfane = 94,
-    // CharNameReserved = 95,
+    CharNameInvalidCharacter = 92,
+    CharNameMixedLanguages = 93,
+    CharNameProfane = 94,
+    CharNameReserved = 95,
     // CharNameInvalidApostrophe = 96,
     // CharNameMultipleApostrophes = 97,
-    // CharNameThreeConsecutive = 98,
+    CharNameThreeConsecutive = 98,
     // CharNameInvalidSpace = 99,
     // CharNameConsecutiveSpaces = 100,
     // CharNameRussianConsecutiveSilentCharacters = 101,
@@ -0,0 +1,90 @@
+-- Accounts, bans and the realmlist.
+
+CREATE TABLE account (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    salt BLOB NOT NULL,
+    verifier BLOB NOT NULL,
+    session_key BLOB,
+    email TEXT NOT NULL DEFAULT '',
+    joindate INTEGER NOT NULL,
+    last_ip TEXT NOT NULL DEFAULT '127.0.0.1',
+    last_login INTEGER,
+    online INTEGER NOT NULL DEFAULT 0,
+    locked INTEGER NOT NULL DEFAULT 0,
+    expansion INTEGER NOT NULL DEFAULT 2,
+    -- when the account's mute ends, as a unix timestamp
+    mutetime INTEGER NOT NULL DEFAULT 0,
+    locale INTEGER NOT NULL DEFAULT 0,
+    os TEXT NOT NULL DEFAULT ''
+);
+
+-- gm levels, where a realm id of -1 applies to every realm
+CREATE TABLE account_access (
+    account_id INTEGER NOT NULL,
+    security_level INTEGER NOT NULL,
+    realm_id INTEGER NOT NULL DEFAULT -1,
+    PRIMARY KEY (account_id, realm_id)
+);
+
+-- permanent bans have an unbandate equal to their bandate
+CREATE TABLE account_banned (
+    id INTEGER NOT NULL,
+    bandate INTEGER NOT NULL,
+    unbandate INTEGER NOT NULL,
+    bannedby TEXT NOT NULL,
+    banreason TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX idx_account_banned_id ON account_banned (id, active);
+
+CREATE TABLE account_muted (
+    account_id INTEGER NOT NULL,
+    mutedate INTEGER NOT NULL,
+    -- the length of the mute in minutes
+    mutetime INTEGER NOT NULL,
+    mutedby TEXT NOT NULL,
+    mutereason TEXT NOT NULL
+);
+
+CREATE TABLE account_login_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 0 = connect, 1 = reconnect
+    kind INTEGER NOT NULL,
+    username TEXT NOT NULL,
+    account_id INTEGER,
+    ip TEXT NOT NULL,
+    build INTEGER NOT NULL,
+    os TEXT NOT NULL,
+    country TEXT NOT NULL,
+    -- the auth return code, 0 = success
+    result INTEGER NOT NULL,
+    time INTEGER NOT NULL
+);
+
+CREATE INDEX idx_account_login_log_account_time ON account_login_log (account_id, time);
+CREATE INDEX idx_account_login_log_time ON account_login_log (time);
+
+CREATE TABLE realmlist (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    address TEXT NOT NULL DEFAULT '127.0.0.1',
+    local_address TEXT NOT NULL DEFAULT '127.0.0.1',
+    local_subnet_mask TEXT NOT NULL DEFAULT '255.255.255.0',
+    port INTEGER NOT NULL DEFAULT 8085,
+    icon INTEGER NOT NULL DEFAULT 0,
+    flag INTEGER NOT NULL DEFAULT 2,
+    timezone INTEGER NOT NULL DEFAULT 0,
+    gamebuild INTEGER NOT NULL DEFAULT 12340,
+    population REAL NOT NULL DEFAULT 0
+);
+
+CREATE TABLE uptime (
+    realm_id INTEGER NOT NULL,
+    starttime INTEGER NOT NULL,
+    uptime INTEGER NOT NULL DEFAULT 0,
+    maxplayers INTEGER NOT NULL DEFAULT 0,
+    revision TEXT NOT NULL,
+    PRIMARY KEY (realm_id, starttime)
+);
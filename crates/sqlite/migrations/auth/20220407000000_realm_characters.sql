@@ -0,0 +1,9 @@
+-- The number of characters each account has on each realm, kept
+-- by the world servers so that they can limit characters per account.
+
+CREATE TABLE realm_characters (
+    realm_id INTEGER NOT NULL,
+    account_id INTEGER NOT NULL,
+    num_chars INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (realm_id, account_id)
+);
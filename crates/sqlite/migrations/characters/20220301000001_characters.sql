@@ -0,0 +1,43 @@
+-- Characters and the data the client stores per account.
+
+CREATE TABLE characters (
+    guid INTEGER PRIMARY KEY AUTOINCREMENT,
+    account INTEGER NOT NULL,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    level INTEGER NOT NULL DEFAULT 1,
+    race INTEGER NOT NULL,
+    class INTEGER NOT NULL,
+    gender INTEGER NOT NULL,
+    skin INTEGER NOT NULL,
+    face INTEGER NOT NULL,
+    hair_style INTEGER NOT NULL,
+    hair_color INTEGER NOT NULL,
+    facial_style INTEGER NOT NULL,
+    zone INTEGER NOT NULL,
+    map INTEGER NOT NULL,
+    position_x REAL NOT NULL,
+    position_y REAL NOT NULL,
+    position_z REAL NOT NULL
+);
+
+CREATE INDEX idx_characters_account ON characters (account);
+
+-- permanent bans have an unbandate equal to their bandate
+CREATE TABLE character_banned (
+    guid INTEGER NOT NULL,
+    bandate INTEGER NOT NULL,
+    unbandate INTEGER NOT NULL,
+    bannedby TEXT NOT NULL,
+    banreason TEXT NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1
+);
+
+CREATE INDEX idx_character_banned_guid ON character_banned (guid, active);
+
+CREATE TABLE account_data (
+    account_id INTEGER NOT NULL,
+    type INTEGER NOT NULL,
+    time INTEGER NOT NULL DEFAULT 0,
+    data BLOB NOT NULL,
+    PRIMARY KEY (account_id, type)
+);
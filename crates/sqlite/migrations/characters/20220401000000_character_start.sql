@@ -0,0 +1,47 @@
+-- What a character starts with: their facing, items, spells,
+-- skills and action bar.
+
+ALTER TABLE characters ADD COLUMN orientation REAL NOT NULL DEFAULT 0;
+
+CREATE TABLE item_instance (
+    guid INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_entry INTEGER NOT NULL,
+    owner_guid INTEGER NOT NULL,
+    count INTEGER NOT NULL DEFAULT 1,
+    durability INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_item_instance_owner ON item_instance (owner_guid);
+
+CREATE TABLE character_inventory (
+    guid INTEGER NOT NULL,
+    bag INTEGER NOT NULL DEFAULT 0,
+    slot INTEGER NOT NULL,
+    item INTEGER PRIMARY KEY,
+    UNIQUE (guid, bag, slot)
+);
+
+CREATE TABLE character_spell (
+    guid INTEGER NOT NULL,
+    spell INTEGER NOT NULL,
+    active INTEGER NOT NULL DEFAULT 1,
+    disabled INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (guid, spell)
+);
+
+CREATE TABLE character_skills (
+    guid INTEGER NOT NULL,
+    skill INTEGER NOT NULL,
+    value INTEGER NOT NULL,
+    max INTEGER NOT NULL,
+    PRIMARY KEY (guid, skill)
+);
+
+CREATE TABLE character_action (
+    guid INTEGER NOT NULL,
+    spec INTEGER NOT NULL DEFAULT 0,
+    button INTEGER NOT NULL,
+    action INTEGER NOT NULL,
+    type INTEGER NOT NULL,
+    PRIMARY KEY (guid, spec, button)
+);
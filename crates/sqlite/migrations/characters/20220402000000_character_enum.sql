@@ -0,0 +1,24 @@
+-- What the character screen shows besides the character itself:
+-- their flags, guild and pet.
+
+ALTER TABLE characters ADD COLUMN player_flags INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE characters ADD COLUMN at_login INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE guild_member (
+    guildid INTEGER NOT NULL,
+    guid INTEGER PRIMARY KEY,
+    rank INTEGER NOT NULL DEFAULT 0
+);
+
+-- the pet a character has out is in slot 0
+CREATE TABLE character_pet (
+    id INTEGER PRIMARY KEY,
+    entry INTEGER NOT NULL,
+    owner INTEGER NOT NULL,
+    modelid INTEGER NOT NULL DEFAULT 0,
+    level INTEGER NOT NULL DEFAULT 1,
+    name TEXT NOT NULL DEFAULT '',
+    slot INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_character_pet_owner ON character_pet (owner, slot);
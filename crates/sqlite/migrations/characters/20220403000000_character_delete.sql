@@ -0,0 +1,39 @@
+-- Deleted characters can be kept around to be restored. They are
+-- taken off their account and lose their name, which sqlite can
+-- only stop being unique by rebuilding the table.
+
+CREATE TABLE characters_new (
+    guid INTEGER PRIMARY KEY AUTOINCREMENT,
+    account INTEGER NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    level INTEGER NOT NULL DEFAULT 1,
+    race INTEGER NOT NULL,
+    class INTEGER NOT NULL,
+    gender INTEGER NOT NULL,
+    skin INTEGER NOT NULL,
+    face INTEGER NOT NULL,
+    hair_style INTEGER NOT NULL,
+    hair_color INTEGER NOT NULL,
+    facial_style INTEGER NOT NULL,
+    zone INTEGER NOT NULL,
+    map INTEGER NOT NULL,
+    position_x REAL NOT NULL,
+    position_y REAL NOT NULL,
+    position_z REAL NOT NULL,
+    orientation REAL NOT NULL DEFAULT 0,
+    player_flags INTEGER NOT NULL DEFAULT 0,
+    at_login INTEGER NOT NULL DEFAULT 0,
+    delete_infos_account INTEGER,
+    delete_infos_name TEXT,
+    delete_date INTEGER
+);
+
+INSERT INTO characters_new (guid, account, name, level, race, class, gender, skin, face, hair_style, hair_color, facial_style, zone, map, position_x, position_y, position_z, orientation, player_flags, at_login)
+SELECT guid, account, name, level, race, class, gender, skin, face, hair_style, hair_color, facial_style, zone, map, position_x, position_y, position_z, orientation, player_flags, at_login FROM characters;
+
+DROP TABLE characters;
+ALTER TABLE characters_new RENAME TO characters;
+
+CREATE INDEX idx_characters_account ON characters (account);
+CREATE UNIQUE INDEX idx_characters_name ON characters (name) WHERE delete_date IS NULL;
+CREATE INDEX idx_characters_delete_date ON characters (delete_date);
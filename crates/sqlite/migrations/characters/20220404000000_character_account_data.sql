@@ -0,0 +1,10 @@
+-- The data the client stores for each character, such as their
+-- macros and chat layout, next to the account wide data.
+
+CREATE TABLE character_account_data (
+    guid INTEGER NOT NULL,
+    type INTEGER NOT NULL,
+    time INTEGER NOT NULL DEFAULT 0,
+    data BLOB NOT NULL,
+    PRIMARY KEY (guid, type)
+);
@@ -0,0 +1,14 @@
+-- The tutorials each account has seen, one bit per tutorial.
+
+CREATE TABLE account_tutorial (
+    account_id INTEGER NOT NULL,
+    tut0 INTEGER NOT NULL DEFAULT 0,
+    tut1 INTEGER NOT NULL DEFAULT 0,
+    tut2 INTEGER NOT NULL DEFAULT 0,
+    tut3 INTEGER NOT NULL DEFAULT 0,
+    tut4 INTEGER NOT NULL DEFAULT 0,
+    tut5 INTEGER NOT NULL DEFAULT 0,
+    tut6 INTEGER NOT NULL DEFAULT 0,
+    tut7 INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (account_id)
+);
@@ -0,0 +1,868 @@
+use std::{collections::HashMap, fmt, net::AddrParseError, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::{
+        Account, AccountFetchError, AccountId, AccountOpError, AccountService, Ban, BanStatus,
+        ConnectToken, LoginFailure, ReconnectToken, MAX_EXPANSION,
+    },
+    audit::{LoginAttempt, LoginKind},
+    characters::CharacterService,
+    realms::RealmId,
+    types::Locale,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use sqlx::{query, query_as, FromRow, SqlitePool};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, instrument, warn};
+use wow_srp::{Salt, Verifier, WowSRPServer};
+
+/// An account as it is stored in the database. The ban
+/// status is stored separately, in the `account_banned` table.
+#[derive(FromRow)]
+struct AccountRow {
+    id: u32,
+    username: String,
+    email: String,
+    salt: Vec<u8>,
+    verifier: Vec<u8>,
+    session_key: Option<Vec<u8>>,
+    joindate: i64,
+    last_login: Option<i64>,
+    online: u8,
+    locked: u8,
+    expansion: u8,
+}
+
+impl AccountRow {
+    fn with_ban(self, ban_status: Option<BanStatus>) -> Result<Account, AccountOpError> {
+        Ok(Account {
+            id: AccountId(self.id),
+            username: self.username,
+            email: self.email,
+            ban_status,
+            salt: Salt(
+                self.salt
+                    .try_into()
+                    .map_err(|_| AccountOpError::PersistError("invalid salt".to_string()))?,
+            ),
+            verifier: Verifier(
+                self.verifier
+                    .try_into()
+                    .map_err(|_| AccountOpError::PersistError("invalid verifier".to_string()))?,
+            ),
+            session_key: self.session_key.and_then(|k| k.try_into().ok()),
+            joindate: timestamp(self.joindate)?,
+            last_login: self.last_login.map(timestamp).transpose()?,
+            online: self.online,
+            locked: self.locked,
+            expansion: self.expansion,
+        })
+    }
+}
+
+/// A unix timestamp read from the database as a date.
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, AccountOpError> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| AccountOpError::PersistError(format!("invalid timestamp {secs}")))
+}
+
+#[derive(Clone)]
+pub struct SqliteAccountService {
+    pool: SqlitePool,
+    /// The character services of every realm, used to clean
+    /// up characters when an account is deleted.
+    characters: Vec<Arc<dyn CharacterService + Send + Sync>>,
+    /// Logins that have been started but not completed, keyed
+    /// by the public key of the server for that login.
+    pending: Arc<Mutex<HashMap<[u8; 32], AccountId>>>,
+}
+
+impl fmt::Debug for SqliteAccountService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SqliteAccountService")
+            .field("pool", &self.pool)
+            .field("characters", &self.characters.len())
+            .finish()
+    }
+}
+
+impl SqliteAccountService {
+    pub fn new(pool: SqlitePool) -> Self {
+        debug!("Starting accounts service");
+        Self {
+            pool,
+            characters: vec![],
+            pending: Default::default(),
+        }
+    }
+
+    /// Set the character services of the realms that should be
+    /// cleaned up when an account is deleted.
+    pub fn with_characters(self, characters: Vec<Arc<dyn CharacterService + Send + Sync>>) -> Self {
+        Self { characters, ..self }
+    }
+
+    /// Check that an account with the given id exists.
+    async fn exists(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let exists = query("SELECT id FROM account WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map(|r| r.is_some())
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if exists {
+            Ok(())
+        } else {
+            Err(AccountOpError::InvalidAccount(id))
+        }
+    }
+
+    /// Get the ban currently in place on an account, if any. If there
+    /// are several, a permanent ban wins over the longest temporary one.
+    async fn active_ban(&self, id: AccountId) -> Result<Option<BanStatus>, sqlx::Error> {
+        let ban: Option<(i64, i64)> = query_as(
+            "SELECT bandate, unbandate FROM account_banned WHERE id = ? AND active = 1 AND (unbandate > ? OR unbandate = bandate) ORDER BY unbandate = bandate DESC, unbandate DESC LIMIT 1",
+        )
+        .bind(id)
+        .bind(Utc::now().timestamp())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(ban.and_then(|(bandate, unbandate)| BanStatus::from_timestamps(bandate, unbandate)))
+    }
+}
+
+#[async_trait]
+impl AccountService for SqliteAccountService {
+    async fn list_account(&self) -> Result<Vec<Account>, AccountFetchError> {
+        let accounts: Vec<AccountRow> = query_as(
+            "SELECT id, username, email, salt, verifier, session_key, joindate, last_login, online, locked, expansion FROM account ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountFetchError::IO(e.to_string()))?;
+
+        // permanent bans sort last so that they take precedence
+        let bans = query_as::<_, (u32, i64, i64)>(
+            "SELECT id, bandate, unbandate FROM account_banned WHERE active = 1 AND (unbandate > ? OR unbandate = bandate) ORDER BY unbandate = bandate, unbandate",
+        )
+        .bind(Utc::now().timestamp())
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountFetchError::IO(e.to_string()))?
+        .into_iter()
+        .filter_map(|(id, bandate, unbandate)| {
+            Some((id, BanStatus::from_timestamps(bandate, unbandate)?))
+        })
+        .collect::<HashMap<_, _>>();
+
+        accounts
+            .into_iter()
+            .map(|a| {
+                let ban = bans.get(&a.id).copied();
+                a.with_ban(ban)
+                    .map_err(|e| AccountFetchError::IO(e.to_string()))
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn create_account(
+        &self,
+        username: &str,
+        password: &str,
+        email: &str,
+    ) -> Result<AccountId, AccountOpError> {
+        if username.len() > 16 {
+            return Err(AccountOpError::UsernameTooLong);
+        } else if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        // convert to uppercase
+        let username = username.to_ascii_uppercase();
+        let password = password.to_ascii_uppercase();
+
+        let (verifier, salt) = WowSRPServer::register(&username, &password);
+
+        let done = query(
+            "INSERT INTO account (username, salt, verifier, email, joindate) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&username)
+        .bind(&salt.0[..])
+        .bind(&verifier.0[..])
+        .bind(email)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        Ok(AccountId(done.last_insert_rowid() as u32))
+    }
+
+    /// Deletes the account and everything attached to it. Characters
+    /// are deleted on every realm before the account itself, so if any
+    /// realm fails the account is left in place and can be retried.
+    ///
+    /// note: the characters may live in the same file, so they are deleted
+    /// before the transaction starts rather than while it holds the lock.
+    #[instrument(skip(self))]
+    async fn delete_account(&self, id: AccountId) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+
+        for characters in &self.characters {
+            let deleted = characters
+                .delete_by_account(id)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+            debug!("deleted {deleted} characters for account {id}");
+        }
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM account_access WHERE account_id = ?",
+            "DELETE FROM account_banned WHERE id = ?",
+            "DELETE FROM account_muted WHERE account_id = ?",
+            "DELETE FROM realm_characters WHERE account_id = ?",
+            "DELETE FROM account WHERE id = ?",
+        ] {
+            query(statement)
+                .bind(id)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("deleted account {id}");
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get(&self, id: AccountId) -> Result<Account, AccountOpError> {
+        let account: AccountRow = query_as(
+            "SELECT id, username, email, salt, verifier, session_key, joindate, last_login, online, locked, expansion FROM account WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+        .ok_or(AccountOpError::InvalidAccount(id))?;
+
+        let ban = self
+            .active_ban(id)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        account.with_ban(ban)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_by_username(&self, username: &str) -> Result<Option<Account>, AccountOpError> {
+        let account: AccountRow = match query_as(
+            "SELECT id, username, email, salt, verifier, session_key, joindate, last_login, online, locked, expansion FROM account WHERE username = ?",
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+        {
+            Some(a) => a,
+            None => return Ok(None),
+        };
+
+        let ban = self
+            .active_ban(AccountId(account.id))
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        account.with_ban(ban).map(Some)
+    }
+
+    async fn initiate_login(&self, username: &str) -> Result<ConnectToken, LoginFailure> {
+        let account = self
+            .get_by_username(username)
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?
+            .ok_or(LoginFailure::UnknownAccount)?;
+
+        match account.ban_status {
+            Some(BanStatus::Permanent) => {
+                warn!("permanently banned user {username} tried to login");
+                Err(LoginFailure::Banned)
+            }
+            Some(BanStatus::Temporary { until }) => {
+                warn!("banned user {username} tried to login, suspended until {until}");
+                Err(LoginFailure::Suspended { until })
+            }
+            None => {
+                let token = ConnectToken::new(&account.username, account.salt, account.verifier);
+                self.pending
+                    .lock()
+                    .await
+                    .insert(*token.get_b_pub(), account.id);
+                Ok(token)
+            }
+        }
+    }
+
+    async fn initiate_relogin(&self, username: &str) -> Result<ReconnectToken, LoginFailure> {
+        let account = self
+            .get_by_username(username)
+            .await
+            .map_err(|_| LoginFailure::DatabaseError)?
+            .ok_or(LoginFailure::UnknownAccount)?;
+
+        let session_key = account.session_key.ok_or(LoginFailure::UnknownAccount)?;
+
+        Ok(ReconnectToken::new(account, session_key))
+    }
+
+    async fn complete_login(
+        &self,
+        token: &ConnectToken,
+        public_key: &[u8; 32],
+        client_proof: &[u8; 20],
+    ) -> Result<[u8; 20], LoginFailure> {
+        let id = self
+            .pending
+            .lock()
+            .await
+            .remove(token.get_b_pub())
+            .ok_or(LoginFailure::DatabaseError)?;
+
+        let (server_proof, session_key) = token.accept(public_key, client_proof)?;
+
+        // todo(arlyon) set the ip, locale and os from the client
+        query(
+            "UPDATE account SET session_key = ?, last_ip = ?, last_login = ?, locale = ?, os = ? WHERE id = ?",
+        )
+        .bind(&session_key[..])
+        .bind("0.0.0.0")
+        .bind(Utc::now().timestamp())
+        .bind(u8::from(Locale::enUS))
+        .bind("Win")
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| {
+            error!("error updating session: {e}");
+            LoginFailure::DatabaseError
+        })?;
+
+        info!("logged in {id}");
+
+        Ok(server_proof)
+    }
+
+    async fn complete_relogin(
+        &self,
+        token: &ReconnectToken,
+        proof_data: &[u8; 16],
+        client_proof: &[u8; 20],
+    ) -> Result<[u8; 20], LoginFailure> {
+        token
+            .accept(proof_data, client_proof)
+            .map(|_| client_proof.to_owned())
+    }
+
+    /// Set a ban for a given account.
+    ///
+    /// duration: the duration of the ban. If `None`, the ban is permanent.
+    ///
+    /// note: permabans are represented by a unbandate of the same value as the bandate.
+    async fn set_ban(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        let bandate = Utc::now();
+        let unbandate = match duration {
+            Some(d) => chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| bandate.checked_add_signed(d))
+                .ok_or_else(|| {
+                    AccountOpError::PersistError(format!("ban duration {d:?} is too long"))
+                })?,
+            None => bandate,
+        };
+
+        query(
+            "INSERT INTO account_banned (id, bandate, unbandate, bannedby, banreason) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(bandate.timestamp())
+        .bind(unbandate.timestamp())
+        .bind(author)
+        .bind(reason.unwrap_or(""))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("banned {id} for {duration:?}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unban(&self, id: AccountId) -> Result<(), AccountOpError> {
+        let done = query("UPDATE account_banned SET active = 0 WHERE id = ? AND active = 1")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() == 0 {
+            return Err(AccountOpError::NotBanned(id));
+        }
+
+        info!("unbanned {id}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn bans(&self, id: AccountId) -> Result<Vec<Ban>, AccountOpError> {
+        self.exists(id).await?;
+        let bans: Vec<(i64, i64, String, String, bool)> = query_as(
+            "SELECT bandate, unbandate, bannedby, banreason, active FROM account_banned WHERE id = ? ORDER BY bandate DESC, rowid DESC",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        bans.into_iter()
+            .map(|(bandate, unbandate, author, reason, active)| {
+                Ok(Ban {
+                    banned_at: timestamp(bandate)?,
+                    expires: (unbandate != bandate)
+                        .then(|| timestamp(unbandate))
+                        .transpose()?,
+                    author,
+                    reason,
+                    active,
+                })
+            })
+            .collect()
+    }
+
+    async fn expire_bans(&self) -> Result<u64, AccountOpError> {
+        let done = query(
+            "UPDATE account_banned SET active = 0 WHERE active = 1 AND unbandate <> bandate AND unbandate <= ?",
+        )
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() > 0 {
+            info!("expired {} bans", done.rows_affected());
+        }
+
+        Ok(done.rows_affected())
+    }
+
+    /// Mute an account. The mute is recorded in `account_muted`
+    /// so that there is a history of who muted the account and why.
+    #[instrument(skip(self))]
+    async fn set_mute(
+        &self,
+        id: AccountId,
+        author: &str,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+
+        let now = Utc::now();
+        let until = chrono::Duration::from_std(duration)
+            .ok()
+            .and_then(|d| now.checked_add_signed(d))
+            .unwrap_or(chrono::MAX_DATETIME);
+
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        query("UPDATE account SET mutetime = ? WHERE id = ?")
+            .bind(until.timestamp())
+            .bind(id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        query(
+            "INSERT INTO account_muted (account_id, mutedate, mutetime, mutedby, mutereason) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(now.timestamp())
+        .bind((duration.as_secs() / 60) as i64)
+        .bind(author)
+        .bind(reason.unwrap_or(""))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("muted {id} until {until}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unmute(&self, id: AccountId) -> Result<(), AccountOpError> {
+        if self.muted_until(id).await?.is_none() {
+            return Err(AccountOpError::NotMuted(id));
+        }
+
+        query("UPDATE account SET mutetime = 0 WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("unmuted {id}");
+
+        Ok(())
+    }
+
+    async fn muted_until(&self, id: AccountId) -> Result<Option<DateTime<Utc>>, AccountOpError> {
+        let (mutetime,): (i64,) = query_as("SELECT mutetime FROM account WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?
+            .ok_or(AccountOpError::InvalidAccount(id))?;
+
+        let until = timestamp(mutetime)?;
+        Ok((until > Utc::now()).then_some(until))
+    }
+
+    async fn record_login(&self, attempt: &LoginAttempt) -> Result<(), AccountOpError> {
+        query(
+            "INSERT INTO account_login_log (kind, username, account_id, ip, build, os, country, result, time) VALUES (?, ?, (SELECT id FROM account WHERE username = ?), ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(attempt.kind as u8)
+        .bind(&attempt.username)
+        .bind(&attempt.username)
+        .bind(attempt.ip.to_string())
+        .bind(attempt.build)
+        .bind(&attempt.os)
+        .bind(&attempt.country)
+        .bind(attempt.result)
+        .bind(attempt.time.timestamp())
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))
+    }
+
+    async fn login_history(
+        &self,
+        id: Option<AccountId>,
+        limit: u32,
+    ) -> Result<Vec<LoginAttempt>, AccountOpError> {
+        let rows: Vec<(u8, String, Option<u32>, String, u16, String, String, u8, i64)> = query_as(
+            "SELECT kind, username, account_id, ip, build, os, country, result, time FROM account_login_log WHERE ? IS NULL OR account_id = ? ORDER BY time DESC, id DESC LIMIT ?",
+        )
+        .bind(id)
+        .bind(id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(
+                |(kind, username, account, ip, build, os, country, result, time)| {
+                    Ok(LoginAttempt {
+                        kind: LoginKind::from_u8(kind).ok_or_else(|| {
+                            AccountOpError::PersistError(format!("unknown login kind {kind}"))
+                        })?,
+                        username,
+                        account: account.map(AccountId),
+                        ip: ip.parse().map_err(|e: AddrParseError| {
+                            AccountOpError::PersistError(e.to_string())
+                        })?,
+                        build,
+                        os,
+                        country,
+                        result,
+                        time: timestamp(time)?,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    async fn prune_login_history(&self, before: DateTime<Utc>) -> Result<u64, AccountOpError> {
+        let done = query("DELETE FROM account_login_log WHERE time < ?")
+            .bind(before.timestamp())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() > 0 {
+            info!("pruned {} login attempts", done.rows_affected());
+        }
+
+        Ok(done.rows_affected())
+    }
+
+    #[instrument(skip(self, password))]
+    async fn set_password(&self, id: AccountId, password: &str) -> Result<(), AccountOpError> {
+        if password.len() > 16 {
+            return Err(AccountOpError::PasswordTooLong);
+        }
+
+        let account = self.get(id).await?;
+        let (verifier, salt) =
+            WowSRPServer::register(&account.username, &password.to_ascii_uppercase());
+
+        // clearing the session key forces the client to log in again
+        query("UPDATE account SET salt = ?, verifier = ?, session_key = NULL WHERE id = ?")
+            .bind(&salt.0[..])
+            .bind(&verifier.0[..])
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("changed password for {id}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_email(&self, id: AccountId, email: &str) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        query("UPDATE account SET email = ? WHERE id = ?")
+            .bind(email)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Set the GM level for an account.
+    ///
+    /// note: a realm id of -1 grants the level on all realms.
+    #[instrument(skip(self))]
+    async fn set_gm_level(
+        &self,
+        id: AccountId,
+        level: u8,
+        realm: Option<RealmId>,
+    ) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        let realm = realm.map(|r| r.0 as i64).unwrap_or(-1);
+
+        let statement = if level == 0 {
+            // removing the level from all realms removes it from each realm too
+            query("DELETE FROM account_access WHERE account_id = ?1 AND (realm_id = ?2 OR ?2 = -1)")
+                .bind(id)
+                .bind(realm)
+        } else {
+            query(
+                "INSERT INTO account_access (account_id, realm_id, security_level) VALUES (?, ?, ?) ON CONFLICT (account_id, realm_id) DO UPDATE SET security_level = excluded.security_level",
+            )
+            .bind(id)
+            .bind(realm)
+            .bind(level)
+        };
+        statement
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+
+        info!("set gm level for {id} on realm {realm} to {level}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_expansion(&self, id: AccountId, expansion: u8) -> Result<(), AccountOpError> {
+        if expansion > MAX_EXPANSION {
+            return Err(AccountOpError::InvalidExpansion(expansion));
+        }
+
+        self.exists(id).await?;
+        query("UPDATE account SET expansion = ? WHERE id = ?")
+            .bind(expansion)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn set_locked(&self, id: AccountId, locked: bool) -> Result<(), AccountOpError> {
+        self.exists(id).await?;
+        query("UPDATE account SET locked = ? WHERE id = ?")
+            .bind(locked)
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| AccountOpError::PersistError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::Duration;
+
+    use azerust_game::{
+        accounts::{AccountId, AccountOpError, AccountService, BanStatus},
+        realms::RealmId,
+    };
+    use sqlx::query_scalar;
+    use wow_srp::WowSRPClient;
+
+    use super::SqliteAccountService;
+    use crate::{test_pool, AUTH_MIGRATOR};
+
+    async fn accounts() -> SqliteAccountService {
+        SqliteAccountService::new(test_pool(&AUTH_MIGRATOR).await)
+    }
+
+    #[tokio::test]
+    pub async fn create_and_log_in() {
+        let accounts = accounts().await;
+        let id = accounts
+            .create_account("thrall", "lokthar", "thrall@example.com")
+            .await
+            .unwrap();
+
+        let account = accounts.get_by_username("Thrall").await.unwrap().unwrap();
+        assert_eq!(account.id, id);
+        assert_eq!(account.username, "THRALL");
+        assert_eq!(account.session_key, None);
+
+        let token = accounts.initiate_login("THRALL").await.unwrap();
+        let client = WowSRPClient::new("THRALL", "LOKTHAR");
+        let (proof, session_key) = client
+            .calculate_proof(token.get_b_pub(), token.get_salt())
+            .unwrap();
+        let server_proof = accounts
+            .complete_login(&token, client.get_a_pub(), &proof)
+            .await
+            .unwrap();
+
+        assert_eq!(server_proof, client.get_server_proof(&proof, &session_key));
+        let account = accounts.get(id).await.unwrap();
+        assert_eq!(account.session_key, Some(session_key));
+        assert!(account.last_login.is_some());
+    }
+
+    #[tokio::test]
+    pub async fn bans_and_mutes() {
+        let accounts = accounts().await;
+        let id = accounts
+            .create_account("thrall", "lokthar", "thrall@example.com")
+            .await
+            .unwrap();
+
+        accounts
+            .set_ban(id, "jaina", Some(Duration::from_secs(60)), None)
+            .await
+            .unwrap();
+        accounts
+            .set_ban(id, "jaina", None, Some("rude"))
+            .await
+            .unwrap();
+        assert_eq!(
+            accounts.get(id).await.unwrap().ban_status,
+            Some(BanStatus::Permanent)
+        );
+        assert_eq!(accounts.bans(id).await.unwrap().len(), 2);
+        // a ban that would end past the last representable date
+        let forever = Duration::from_secs(100_000_000 * 24 * 60 * 60);
+        assert!(accounts
+            .set_ban(id, "jaina", Some(forever), None)
+            .await
+            .is_err());
+
+        accounts.unban(id).await.unwrap();
+        assert_eq!(accounts.get(id).await.unwrap().ban_status, None);
+        assert!(matches!(
+            accounts.unban(id).await,
+            Err(AccountOpError::NotBanned(_))
+        ));
+
+        accounts
+            .set_mute(id, "jaina", Duration::from_secs(60), None)
+            .await
+            .unwrap();
+        assert!(accounts.muted_until(id).await.unwrap().is_some());
+        accounts.unmute(id).await.unwrap();
+        assert!(accounts.muted_until(id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    pub async fn delete_missing_account() {
+        let accounts = accounts().await;
+        assert!(matches!(
+            accounts.delete_account(AccountId(1)).await,
+            Err(AccountOpError::InvalidAccount(_))
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn remove_gm_level_from_all_realms() {
+        let accounts = accounts().await;
+        let id = accounts
+            .create_account("jaina", "proudmoore", "jaina@example.com")
+            .await
+            .unwrap();
+        let pool = accounts.pool.clone();
+        let levels = || {
+            query_scalar::<_, i64>("SELECT COUNT(*) FROM account_access WHERE account_id = ?")
+                .bind(id)
+                .fetch_one(&pool)
+        };
+
+        accounts.set_gm_level(id, 3, None).await.unwrap();
+        accounts
+            .set_gm_level(id, 2, Some(RealmId(1)))
+            .await
+            .unwrap();
+        assert_eq!(levels().await.unwrap(), 2);
+
+        accounts
+            .set_gm_level(id, 0, Some(RealmId(1)))
+            .await
+            .unwrap();
+        assert_eq!(levels().await.unwrap(), 1);
+
+        accounts
+            .set_gm_level(id, 2, Some(RealmId(1)))
+            .await
+            .unwrap();
+        accounts.set_gm_level(id, 0, None).await.unwrap();
+        assert_eq!(levels().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,1071 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::{AccountId, BanStatus},
+    characters::{
+        AccountData, AccountDataCache, AccountDataType, AtLoginFlags, Character, CharacterCreate,
+        CharacterId, CharacterPet, CharacterService, CharacterServiceError, DeletedCharacter,
+        DualDataCache, EquippedItem, EQUIPMENT_SLOTS,
+    },
+    EntityType, WowId,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use enumflags2::BitFlags;
+use sqlx::{query, query_as, FromRow, SqlitePool};
+use tracing::{debug, info, instrument};
+
+/// A character as it is stored in the database, where
+/// the guid is the low part of the character's id.
+#[derive(FromRow)]
+struct CharacterRow {
+    guid: u32,
+    account: AccountId,
+    name: String,
+    level: u8,
+    race: u8,
+    class: u8,
+    gender: u8,
+    skin_color: u8,
+    face: u8,
+    hair_style: u8,
+    hair_color: u8,
+    facial_style: u8,
+    zone: u16,
+    map: u16,
+    position_x: f32,
+    position_y: f32,
+    position_z: f32,
+    player_flags: u32,
+    at_login: u16,
+    guild: u32,
+    pet_entry: Option<u32>,
+    pet_display: Option<u32>,
+    pet_level: Option<u8>,
+}
+
+impl From<CharacterRow> for Character {
+    fn from(r: CharacterRow) -> Self {
+        Self {
+            id: WowId::new(EntityType::Player, r.guid, 0),
+            account: r.account,
+            name: r.name,
+            level: r.level,
+            race: r.race,
+            class: r.class,
+            gender: r.gender,
+            skin_color: r.skin_color,
+            face: r.face,
+            hair_style: r.hair_style,
+            hair_color: r.hair_color,
+            facial_style: r.facial_style,
+            zone: r.zone,
+            map: r.map,
+            position_x: r.position_x,
+            position_y: r.position_y,
+            position_z: r.position_z,
+            player_flags: BitFlags::from_bits_truncate(r.player_flags),
+            at_login: BitFlags::from_bits_truncate(r.at_login),
+            guild: r.guild,
+            pet: r.pet_entry.map(|entry| CharacterPet {
+                entry,
+                display: r.pet_display.unwrap_or(0),
+                level: r.pet_level.unwrap_or(1),
+            }),
+        }
+    }
+}
+
+/// The guid a character is stored under.
+fn guid(id: CharacterId) -> u32 {
+    WowId::from(id).low()
+}
+
+/// A unix timestamp read from the database as a date.
+fn timestamp(secs: i64) -> Result<DateTime<Utc>, CharacterServiceError> {
+    Utc.timestamp_opt(secs, 0)
+        .single()
+        .ok_or_else(|| CharacterServiceError::PersistError(format!("invalid timestamp {secs}")))
+}
+
+#[derive(Clone, Debug)]
+pub struct SqliteCharacterService {
+    pool: SqlitePool,
+}
+
+impl SqliteCharacterService {
+    pub fn new(pool: SqlitePool) -> Self {
+        debug!("Starting character service");
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl CharacterService for SqliteCharacterService {
+    async fn get(&self, id: CharacterId) -> Result<Character, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hair_style, c.hair_color, c.facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.player_flags, c.at_login, COALESCE(g.guildid, 0) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.guid = ?",
+        )
+        .bind(guid(id))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+        .map(Character::from)
+        .ok_or(CharacterServiceError::NoSuchCharacter(id))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_by_account(&self, id: AccountId) -> Result<Vec<Character>, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hair_style, c.hair_color, c.facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.player_flags, c.at_login, COALESCE(g.guildid, 0) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.account = ? ORDER BY c.guid",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map(|rows| rows.into_iter().map(Character::from).collect())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn get_by_name(&self, name: &str) -> Result<Option<Character>, CharacterServiceError> {
+        query_as::<_, CharacterRow>(
+            "SELECT c.guid, c.account, c.name, c.level, c.race, c.class, c.gender, c.skin AS skin_color, c.face, c.hair_style, c.hair_color, c.facial_style, c.zone, c.map, c.position_x, c.position_y, c.position_z, c.player_flags, c.at_login, COALESCE(g.guildid, 0) AS guild, p.entry AS pet_entry, p.modelid AS pet_display, p.level AS pet_level FROM characters c LEFT JOIN guild_member g ON g.guid = c.guid LEFT JOIN character_pet p ON p.owner = c.guid AND p.slot = 0 WHERE c.name = ?",
+        )
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|row| row.map(Character::from))
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn count_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        query_as::<_, (i64,)>("SELECT count(*) FROM characters WHERE account = ?")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .map(|(c,)| c as usize)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn name_available(&self, name: &str) -> Result<bool, CharacterServiceError> {
+        query_as::<_, (i64,)>("SELECT count(*) FROM characters WHERE name = ?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await
+            .map(|(c,)| c == 0)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn equipment(&self, id: CharacterId) -> Result<Vec<EquippedItem>, CharacterServiceError> {
+        // enchantments are not stored yet, so nothing is enchanted
+        query_as::<_, (u8, u32)>(
+            "SELECT i.slot, ii.item_entry FROM character_inventory i JOIN item_instance ii ON ii.guid = i.item WHERE i.guid = ? AND i.bag = 0 AND i.slot < ? ORDER BY i.slot",
+        )
+        .bind(guid(id))
+        .bind(EQUIPMENT_SLOTS)
+        .fetch_all(&self.pool)
+        .await
+        .map(|rows| {
+            rows.into_iter()
+                .map(|(slot, entry)| EquippedItem {
+                    slot,
+                    entry,
+                    enchant: 0,
+                })
+                .collect()
+        })
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn max_guid(&self, kind: EntityType) -> Result<u32, CharacterServiceError> {
+        let statement = match kind {
+            EntityType::Player => "SELECT COALESCE(MAX(guid), 0) FROM characters",
+            EntityType::ItemOrContainer => "SELECT COALESCE(MAX(guid), 0) FROM item_instance",
+            _ => return Ok(0),
+        };
+        query_as::<_, (u32,)>(statement)
+            .fetch_one(&self.pool)
+            .await
+            .map(|(max,)| max)
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn create_character(
+        &self,
+        account: AccountId,
+        create: CharacterCreate,
+    ) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        let guid = create.id.low();
+        query(
+            "INSERT INTO characters (guid, account, name, race, class, gender, skin, face, hair_style, hair_color, facial_style, zone, map, position_x, position_y, position_z, orientation, at_login) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(guid)
+        .bind(account)
+        .bind(&create.name)
+        .bind(create.race)
+        .bind(create.class)
+        .bind(create.gender)
+        .bind(create.skin_color)
+        .bind(create.face)
+        .bind(create.hair_style)
+        .bind(create.hair_color)
+        .bind(create.facial_style)
+        .bind(create.zone)
+        .bind(create.map)
+        .bind(create.position_x)
+        .bind(create.position_y)
+        .bind(create.position_z)
+        .bind(create.orientation)
+        .bind(AtLoginFlags::FirstLogin as u16)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for item in create.items {
+            query(
+                "INSERT INTO item_instance (guid, item_entry, owner_guid, count, durability) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(item.id.low())
+            .bind(item.entry)
+            .bind(guid)
+            .bind(item.count)
+            .bind(item.durability)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+            query("INSERT INTO character_inventory (guid, bag, slot, item) VALUES (?, 0, ?, ?)")
+                .bind(guid)
+                .bind(item.slot)
+                .bind(item.id.low())
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for spell in create.spells {
+            query("INSERT OR IGNORE INTO character_spell (guid, spell) VALUES (?, ?)")
+                .bind(guid)
+                .bind(spell)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for skill in create.skills {
+            query("INSERT OR IGNORE INTO character_skills (guid, skill, value, max) VALUES (?, ?, ?, ?)")
+                .bind(guid)
+                .bind(skill.skill)
+                .bind(skill.value)
+                .bind(skill.max)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        for button in create.action_buttons {
+            query("INSERT OR REPLACE INTO character_action (guid, button, action, type) VALUES (?, ?, ?, ?)")
+                .bind(guid)
+                .bind(button.button)
+                .bind(button.action)
+                .bind(button.action_type)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid = ?",
+            "DELETE FROM character_inventory WHERE guid = ?",
+            "DELETE FROM character_spell WHERE guid = ?",
+            "DELETE FROM character_skills WHERE guid = ?",
+            "DELETE FROM character_action WHERE guid = ?",
+            "DELETE FROM character_pet WHERE owner = ?",
+            "DELETE FROM guild_member WHERE guid = ?",
+            "DELETE FROM character_banned WHERE guid = ?",
+            "DELETE FROM character_account_data WHERE guid = ?",
+            "DELETE FROM characters WHERE guid = ?",
+        ] {
+            query(statement)
+                .bind(guid(id))
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_by_account(&self, id: AccountId) -> Result<usize, CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_inventory WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_spell WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_skills WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_action WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_pet WHERE owner IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM guild_member WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_banned WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM character_account_data WHERE guid IN (SELECT guid FROM characters WHERE ? IN (account, delete_infos_account))",
+            "DELETE FROM account_data WHERE account_id = ?",
+            "DELETE FROM account_tutorial WHERE account_id = ?",
+        ] {
+            query(statement)
+                .bind(id)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        let deleted = query("DELETE FROM characters WHERE ? IN (account, delete_infos_account)")
+            .bind(id)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+            .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(deleted as usize)
+    }
+
+    #[instrument(skip(self))]
+    async fn soft_delete_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        let done = query(
+            "UPDATE characters SET delete_infos_account = account, delete_infos_name = name, delete_date = ?, account = 0, name = '' WHERE guid = ? AND delete_date IS NULL",
+        )
+        .bind(Utc::now().timestamp())
+        .bind(guid(id))
+        .execute(&mut tx)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+
+        query("DELETE FROM guild_member WHERE guid = ?")
+            .bind(guid(id))
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn deleted_characters(&self) -> Result<Vec<DeletedCharacter>, CharacterServiceError> {
+        let rows = query_as::<_, (u32, AccountId, String, u8, u8, u8, i64)>(
+            "SELECT guid, delete_infos_account, delete_infos_name, level, race, class, delete_date FROM characters WHERE delete_date IS NOT NULL ORDER BY delete_date, guid",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|(guid, account, name, level, race, class, deleted)| {
+                Ok(DeletedCharacter {
+                    id: WowId::new(EntityType::Player, guid, 0),
+                    account,
+                    name,
+                    level,
+                    race,
+                    class,
+                    deleted: timestamp(deleted)?,
+                })
+            })
+            .collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn restore_character(
+        &self,
+        id: CharacterId,
+        name: &str,
+    ) -> Result<(), CharacterServiceError> {
+        let done = query(
+            "UPDATE characters SET account = delete_infos_account, name = ?, delete_infos_account = NULL, delete_infos_name = NULL, delete_date = NULL WHERE guid = ? AND delete_date IS NOT NULL",
+        )
+        .bind(name)
+        .bind(guid(id))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<usize, CharacterServiceError> {
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        for statement in [
+            "DELETE FROM item_instance WHERE owner_guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_inventory WHERE guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_spell WHERE guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_skills WHERE guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_action WHERE guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_pet WHERE owner IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_banned WHERE guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+            "DELETE FROM character_account_data WHERE guid IN (SELECT guid FROM characters WHERE delete_date < ?)",
+        ] {
+            query(statement)
+                .bind(before.timestamp())
+                .execute(&mut tx)
+                .await
+                .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        }
+
+        let deleted = query("DELETE FROM characters WHERE delete_date < ?")
+            .bind(before.timestamp())
+            .execute(&mut tx)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+            .rows_affected();
+
+        tx.commit()
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        info!("purged {deleted} deleted characters");
+        Ok(deleted as usize)
+    }
+
+    async fn save_character(&self, character: &Character) -> Result<(), CharacterServiceError> {
+        query(
+            "UPDATE characters SET level = ?, zone = ?, map = ?, position_x = ?, position_y = ?, position_z = ? WHERE guid = ?",
+        )
+        .bind(character.level)
+        .bind(character.zone)
+        .bind(character.map)
+        .bind(character.position_x)
+        .bind(character.position_y)
+        .bind(character.position_z)
+        .bind(character.id.low())
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn set_at_login(
+        &self,
+        id: CharacterId,
+        flags: BitFlags<AtLoginFlags>,
+    ) -> Result<(), CharacterServiceError> {
+        let done = query("UPDATE characters SET at_login = ? WHERE guid = ?")
+            .bind(flags.bits())
+            .bind(guid(id))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NoSuchCharacter(id));
+        }
+        Ok(())
+    }
+
+    async fn customize_character(
+        &self,
+        character: &Character,
+    ) -> Result<(), CharacterServiceError> {
+        query(
+            "UPDATE characters SET name = ?, race = ?, gender = ?, skin = ?, face = ?, hair_style = ?, hair_color = ?, facial_style = ?, at_login = ? WHERE guid = ?",
+        )
+        .bind(&character.name)
+        .bind(character.race)
+        .bind(character.gender)
+        .bind(character.skin_color)
+        .bind(character.face)
+        .bind(character.hair_style)
+        .bind(character.hair_color)
+        .bind(character.facial_style)
+        .bind(character.at_login.bits())
+        .bind(character.id.low())
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    /// Ban a character from logging in.
+    ///
+    /// note: permabans are represented by a unbandate of the same value as the bandate.
+    #[instrument(skip(self))]
+    async fn ban_character(
+        &self,
+        id: CharacterId,
+        author: &str,
+        duration: Option<Duration>,
+        reason: Option<&str>,
+    ) -> Result<(), CharacterServiceError> {
+        let bandate = Utc::now();
+        let unbandate = match duration {
+            Some(d) => chrono::Duration::from_std(d)
+                .ok()
+                .and_then(|d| bandate.checked_add_signed(d))
+                .ok_or_else(|| {
+                    CharacterServiceError::PersistError(format!("ban duration {d:?} is too long"))
+                })?,
+            None => bandate,
+        };
+
+        query(
+            "INSERT INTO character_banned (guid, bandate, unbandate, bannedby, banreason, active) VALUES (?, ?, ?, ?, ?, 1)",
+        )
+        .bind(guid(id))
+        .bind(bandate.timestamp())
+        .bind(unbandate.timestamp())
+        .bind(author)
+        .bind(reason.unwrap_or(""))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        info!("banned character {id:?} for {duration:?}");
+
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn unban_character(&self, id: CharacterId) -> Result<(), CharacterServiceError> {
+        let done = query("UPDATE character_banned SET active = 0 WHERE guid = ? AND active = 1")
+            .bind(guid(id))
+            .execute(&self.pool)
+            .await
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        if done.rows_affected() == 0 {
+            return Err(CharacterServiceError::NotBanned(id));
+        }
+
+        info!("unbanned character {id:?}");
+
+        Ok(())
+    }
+
+    async fn character_ban(
+        &self,
+        id: CharacterId,
+    ) -> Result<Option<BanStatus>, CharacterServiceError> {
+        let ban: Option<(i64, i64)> = query_as(
+            "SELECT bandate, unbandate FROM character_banned WHERE guid = ? AND active = 1 AND (unbandate > ? OR unbandate = bandate) ORDER BY unbandate = bandate DESC, unbandate DESC LIMIT 1",
+        )
+        .bind(guid(id))
+        .bind(Utc::now().timestamp())
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(ban.and_then(|(bandate, unbandate)| BanStatus::from_timestamps(bandate, unbandate)))
+    }
+
+    async fn account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+    ) -> Result<AccountData, CharacterServiceError> {
+        let mut rows = query_as::<_, (u8, u32, Vec<u8>)>(
+            "SELECT type, time, data FROM account_data WHERE account_id = ? UNION ALL SELECT type, time, data FROM character_account_data WHERE guid = ?",
+        )
+        .bind(id)
+        .bind(character.map(guid))
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?
+        .into_iter()
+        .map(|(kind, time, data)| (kind, AccountDataCache { time, data }))
+        .collect::<HashMap<_, _>>();
+
+        Ok(AccountData {
+            config: DualDataCache {
+                global: rows.remove(&0),
+                per_char: rows.remove(&1),
+            },
+            bindings: DualDataCache {
+                global: rows.remove(&2),
+                per_char: rows.remove(&3),
+            },
+            macros: DualDataCache {
+                global: rows.remove(&4),
+                per_char: rows.remove(&5),
+            },
+            per_char_layout: rows.remove(&6),
+            per_char_chat: rows.remove(&7),
+        })
+    }
+
+    async fn set_account_data(
+        &self,
+        id: AccountId,
+        character: Option<CharacterId>,
+        kind: AccountDataType,
+        data: AccountDataCache,
+    ) -> Result<(), CharacterServiceError> {
+        let statement = if kind.is_global() {
+            query("INSERT INTO account_data (account_id, type, time, data) VALUES (?, ?, ?, ?) ON CONFLICT (account_id, type) DO UPDATE SET time = excluded.time, data = excluded.data")
+                .bind(id)
+        } else {
+            let character = character.ok_or_else(|| {
+                CharacterServiceError::PersistError(format!("{kind:?} is kept per character"))
+            })?;
+            query("INSERT INTO character_account_data (guid, type, time, data) VALUES (?, ?, ?, ?) ON CONFLICT (guid, type) DO UPDATE SET time = excluded.time, data = excluded.data")
+                .bind(guid(character))
+        };
+
+        statement
+            .bind(u8::from(kind))
+            .bind(data.time)
+            .bind(data.data)
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+
+    async fn tutorials(&self, id: AccountId) -> Result<[u32; 8], CharacterServiceError> {
+        let tutorials = query_as::<_, (u32, u32, u32, u32, u32, u32, u32, u32)>(
+            "SELECT tut0, tut1, tut2, tut3, tut4, tut5, tut6, tut7 FROM account_tutorial WHERE account_id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| CharacterServiceError::PersistError(e.to_string()))?;
+
+        Ok(tutorials
+            .map(|(t0, t1, t2, t3, t4, t5, t6, t7)| [t0, t1, t2, t3, t4, t5, t6, t7])
+            .unwrap_or_default())
+    }
+
+    async fn set_tutorials(
+        &self,
+        id: AccountId,
+        tutorials: [u32; 8],
+    ) -> Result<(), CharacterServiceError> {
+        tutorials
+            .iter()
+            .fold(
+                query("INSERT INTO account_tutorial (account_id, tut0, tut1, tut2, tut3, tut4, tut5, tut6, tut7) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT (account_id) DO UPDATE SET tut0 = excluded.tut0, tut1 = excluded.tut1, tut2 = excluded.tut2, tut3 = excluded.tut3, tut4 = excluded.tut4, tut5 = excluded.tut5, tut6 = excluded.tut6, tut7 = excluded.tut7")
+                    .bind(id),
+                |statement, t| statement.bind(*t),
+            )
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|e| CharacterServiceError::PersistError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::Duration;
+
+    use azerust_game::{
+        accounts::{AccountId, BanStatus},
+        characters::{
+            AccountDataCache, AccountDataType, ActionButton, AtLoginFlags, CharacterCreate,
+            CharacterId, CharacterPet, CharacterService, CharacterSkill, EquippedItem,
+            StartingItem,
+        },
+        EntityType, WowId,
+    };
+    use chrono::Utc;
+    use sqlx::{query, query_as};
+
+    use super::SqliteCharacterService;
+    use crate::{test_pool, CHARACTERS_MIGRATOR};
+
+    fn create(id: u32, name: &str) -> CharacterCreate {
+        CharacterCreate {
+            id: WowId::new(EntityType::Player, id, 0),
+            name: name.to_string(),
+            race: 2,
+            class: 7,
+            gender: 0,
+            skin_color: 0,
+            face: 0,
+            hair_style: 0,
+            hair_color: 0,
+            facial_style: 0,
+            zone: 14,
+            map: 1,
+            position_x: -618.518,
+            position_y: -4251.67,
+            position_z: 38.718,
+            orientation: 0.0,
+            items: vec![],
+            spells: vec![],
+            skills: vec![],
+            action_buttons: vec![],
+        }
+    }
+
+    #[tokio::test]
+    pub async fn create_get_and_delete() {
+        let characters = SqliteCharacterService::new(test_pool(&CHARACTERS_MIGRATOR).await);
+        let account = AccountId(1);
+
+        characters
+            .create_character(account, create(1, "Thrall"))
+            .await
+            .unwrap();
+        assert!(characters
+            .create_character(account, create(2, "thrall"))
+            .await
+            .is_err());
+        assert!(!characters.name_available("THRALL").await.unwrap());
+        assert_eq!(characters.count_by_account(account).await.unwrap(), 1);
+
+        let thrall = characters.get_by_name("Thrall").await.unwrap().unwrap();
+        let id = CharacterId::try_from(thrall.id).unwrap();
+        assert_eq!(characters.get(id).await.unwrap().name, "Thrall");
+        assert_eq!(characters.max_guid(EntityType::Player).await.unwrap(), 1);
+
+        characters
+            .ban_character(id, "jaina", Some(Duration::from_secs(60)), None)
+            .await
+            .unwrap();
+        assert!(matches!(
+            characters.character_ban(id).await.unwrap(),
+            Some(BanStatus::Temporary { .. })
+        ));
+        // a ban that would end past the last representable date
+        let forever = Duration::from_secs(100_000_000 * 24 * 60 * 60);
+        assert!(characters
+            .ban_character(id, "jaina", Some(forever), None)
+            .await
+            .is_err());
+
+        assert_eq!(characters.delete_by_account(account).await.unwrap(), 1);
+        assert!(characters.get(id).await.is_err());
+        assert_eq!(characters.character_ban(id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    pub async fn create_with_starting_data() {
+        let pool = test_pool(&CHARACTERS_MIGRATOR).await;
+        let characters = SqliteCharacterService::new(pool.clone());
+        let name = "Garrosh".to_string();
+        let account = AccountId(2);
+
+        let item = |low, entry, slot| StartingItem {
+            id: WowId::new(EntityType::ItemOrContainer, low, 0),
+            entry,
+            count: 1,
+            slot,
+            durability: 0,
+        };
+        characters
+            .create_character(
+                account,
+                CharacterCreate {
+                    items: vec![item(1, 6125, 3), item(2, 6126, 6), item(3, 12282, 15)],
+                    spells: vec![78, 2457],
+                    skills: vec![CharacterSkill {
+                        skill: 26,
+                        value: 1,
+                        max: 5,
+                    }],
+                    action_buttons: vec![ActionButton {
+                        button: 0,
+                        action: 6603,
+                        action_type: 0,
+                    }],
+                    ..create(1, &name)
+                },
+            )
+            .await
+            .unwrap();
+
+        let garrosh = characters.get_by_name(&name).await.unwrap().unwrap();
+        let id = CharacterId::try_from(garrosh.id).unwrap();
+        let guid = i64::from(garrosh.id.low());
+        let count = |table: &str| {
+            let statement = format!("SELECT count(*) FROM {table} WHERE guid = ?");
+            let pool = pool.clone();
+            async move {
+                query_as::<_, (i64,)>(&statement)
+                    .bind(guid)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap()
+                    .0
+            }
+        };
+        assert_eq!(count("character_inventory").await, 3);
+        assert_eq!(count("character_spell").await, 2);
+        assert_eq!(count("character_skills").await, 1);
+        assert_eq!(count("character_action").await, 1);
+        let equipped = |slot, entry| EquippedItem {
+            slot,
+            entry,
+            enchant: 0,
+        };
+        assert_eq!(
+            characters.equipment(id).await.unwrap(),
+            vec![equipped(3, 6125), equipped(6, 6126), equipped(15, 12282)]
+        );
+        assert_eq!(
+            characters
+                .max_guid(EntityType::ItemOrContainer)
+                .await
+                .unwrap(),
+            3
+        );
+
+        characters.delete_character(id).await.unwrap();
+        assert_eq!(count("character_inventory").await, 0);
+        assert_eq!(count("character_spell").await, 0);
+        assert_eq!(count("character_skills").await, 0);
+        assert_eq!(count("character_action").await, 0);
+    }
+
+    #[tokio::test]
+    pub async fn get_with_guild_and_pet() {
+        let pool = test_pool(&CHARACTERS_MIGRATOR).await;
+        let characters = SqliteCharacterService::new(pool.clone());
+        characters
+            .create_character(AccountId(3), create(1, "Rexxar"))
+            .await
+            .unwrap();
+
+        let rexxar = characters.get_by_name("Rexxar").await.unwrap().unwrap();
+        assert_eq!(rexxar.at_login, AtLoginFlags::FirstLogin);
+        assert_eq!(rexxar.guild, 0);
+        assert_eq!(rexxar.pet, None);
+
+        for statement in [
+            "INSERT INTO guild_member (guildid, guid) VALUES (7, 1)",
+            "INSERT INTO character_pet (id, entry, owner, modelid, level) VALUES (1, 3475, 1, 2709, 10)",
+        ] {
+            query(statement).execute(&pool).await.unwrap();
+        }
+
+        let rexxar = characters.get_by_name("Rexxar").await.unwrap().unwrap();
+        assert_eq!(rexxar.guild, 7);
+        assert_eq!(
+            rexxar.pet,
+            Some(CharacterPet {
+                entry: 3475,
+                display: 2709,
+                level: 10
+            })
+        );
+    }
+
+    #[tokio::test]
+    pub async fn rename_and_customize() {
+        let characters = SqliteCharacterService::new(test_pool(&CHARACTERS_MIGRATOR).await);
+        characters
+            .create_character(AccountId(4), create(1, "Cairne"))
+            .await
+            .unwrap();
+        let id = CharacterId::try_from(WowId::new(EntityType::Player, 1, 0)).unwrap();
+
+        characters
+            .set_at_login(id, AtLoginFlags::Customize.into())
+            .await
+            .unwrap();
+        let mut cairne = characters.get(id).await.unwrap();
+        assert_eq!(cairne.at_login, AtLoginFlags::Customize);
+
+        cairne.name = "Baine".to_string();
+        cairne.face = 3;
+        cairne.at_login.remove(AtLoginFlags::Customize);
+        characters.customize_character(&cairne).await.unwrap();
+
+        let baine = characters.get(id).await.unwrap();
+        assert_eq!(baine.name, "Baine");
+        assert_eq!(baine.face, 3);
+        assert!(baine.at_login.is_empty());
+        assert!(characters.name_available("Cairne").await.unwrap());
+    }
+
+    #[tokio::test]
+    pub async fn save_keeps_at_login() {
+        let characters = SqliteCharacterService::new(test_pool(&CHARACTERS_MIGRATOR).await);
+        characters
+            .create_character(AccountId(4), create(1, "Cairne"))
+            .await
+            .unwrap();
+        let id = CharacterId::try_from(WowId::new(EntityType::Player, 1, 0)).unwrap();
+        let mut cairne = characters.get(id).await.unwrap();
+
+        // flagged while the character is in the world
+        characters
+            .set_at_login(id, AtLoginFlags::Rename.into())
+            .await
+            .unwrap();
+        cairne.level = 2;
+        characters.save_character(&cairne).await.unwrap();
+
+        let cairne = characters.get(id).await.unwrap();
+        assert_eq!(cairne.level, 2);
+        assert_eq!(cairne.at_login, AtLoginFlags::Rename);
+    }
+
+    #[tokio::test]
+    pub async fn soft_delete_restore_and_purge() {
+        let characters = SqliteCharacterService::new(test_pool(&CHARACTERS_MIGRATOR).await);
+        let account = AccountId(5);
+        characters
+            .create_character(account, create(1, "Cairne"))
+            .await
+            .unwrap();
+        let id = CharacterId::try_from(WowId::new(EntityType::Player, 1, 0)).unwrap();
+
+        characters.soft_delete_character(id).await.unwrap();
+        assert!(characters.soft_delete_character(id).await.is_err());
+        assert!(characters.get_by_account(account).await.unwrap().is_empty());
+        assert!(characters.name_available("Cairne").await.unwrap());
+        characters
+            .create_character(account, create(2, "Cairne"))
+            .await
+            .unwrap();
+
+        let deleted = characters.deleted_characters().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, WowId::new(EntityType::Player, 1, 0));
+        assert_eq!(deleted[0].account, account);
+        assert_eq!(deleted[0].name, "Cairne");
+
+        assert!(characters.restore_character(id, "Cairne").await.is_err());
+        characters.restore_character(id, "Baine").await.unwrap();
+        assert_eq!(characters.get(id).await.unwrap().account, account);
+        assert!(characters.deleted_characters().await.unwrap().is_empty());
+
+        characters.soft_delete_character(id).await.unwrap();
+        assert_eq!(
+            characters
+                .purge_deleted(Utc::now() - chrono::Duration::days(1))
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            characters
+                .purge_deleted(Utc::now() + chrono::Duration::days(1))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(characters.get(id).await.is_err());
+        assert_eq!(characters.count_by_account(account).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    pub async fn account_data_per_character() {
+        let characters = SqliteCharacterService::new(test_pool(&CHARACTERS_MIGRATOR).await);
+        let account = AccountId(6);
+        let id = CharacterId::try_from(WowId::new(EntityType::Player, 1, 0)).unwrap();
+        let other = CharacterId::try_from(WowId::new(EntityType::Player, 2, 0)).unwrap();
+        let cache = |time, data: &[u8]| AccountDataCache {
+            time,
+            data: data.to_vec(),
+        };
+
+        characters
+            .set_account_data(account, None, AccountDataType::GlobalMacros, cache(1, b"a"))
+            .await
+            .unwrap();
+        characters
+            .set_account_data(account, None, AccountDataType::GlobalMacros, cache(2, b"b"))
+            .await
+            .unwrap();
+        characters
+            .set_account_data(
+                account,
+                Some(id),
+                AccountDataType::CharacterChat,
+                cache(3, b"c"),
+            )
+            .await
+            .unwrap();
+        assert!(characters
+            .set_account_data(
+                account,
+                None,
+                AccountDataType::CharacterChat,
+                cache(4, b"d")
+            )
+            .await
+            .is_err());
+
+        let data = characters.account_data(account, Some(id)).await.unwrap();
+        let macros = data.get(AccountDataType::GlobalMacros).unwrap();
+        assert_eq!((macros.time, macros.data.as_slice()), (2, &b"b"[..]));
+        let chat = data.get(AccountDataType::CharacterChat).unwrap();
+        assert_eq!((chat.time, chat.data.as_slice()), (3, &b"c"[..]));
+
+        let data = characters.account_data(account, Some(other)).await.unwrap();
+        assert!(data.get(AccountDataType::GlobalMacros).is_some());
+        assert!(data.get(AccountDataType::CharacterChat).is_none());
+        let data = characters.account_data(account, None).await.unwrap();
+        assert!(data.get(AccountDataType::CharacterChat).is_none());
+    }
+
+    #[tokio::test]
+    pub async fn tutorials_are_stored() {
+        let characters = SqliteCharacterService::new(test_pool(&CHARACTERS_MIGRATOR).await);
+        let account = AccountId(7);
+
+        assert_eq!(characters.tutorials(account).await.unwrap(), [0; 8]);
+        characters
+            .set_tutorials(account, [1, 2, 3, 4, 5, 6, 7, u32::MAX])
+            .await
+            .unwrap();
+        characters
+            .set_tutorials(account, [8, 7, 6, 5, 4, 3, 2, u32::MAX])
+            .await
+            .unwrap();
+        assert_eq!(
+            characters.tutorials(account).await.unwrap(),
+            [8, 7, 6, 5, 4, 3, 2, u32::MAX]
+        );
+
+        characters.delete_by_account(account).await.unwrap();
+        assert_eq!(characters.tutorials(account).await.unwrap(), [0; 8]);
+    }
+}
@@ -0,0 +1,61 @@
+//! sqlite
+//!
+//! Account, realm and character services backed by SQLite, for small
+//! realms and local development. Like the MySQL backend, the auth and
+//! character tables live in separate databases with their own migrations.
+
+use std::str::FromStr;
+
+use sqlx::{
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+
+pub mod accounts;
+pub mod characters;
+pub mod realms;
+
+pub use accounts::SqliteAccountService;
+pub use characters::SqliteCharacterService;
+pub use realms::SqliteRealmList;
+
+/// The migrations for the auth database.
+pub static AUTH_MIGRATOR: Migrator = sqlx::migrate!("migrations/auth");
+
+/// The migrations for a character database.
+pub static CHARACTERS_MIGRATOR: Migrator = sqlx::migrate!("migrations/characters");
+
+/// Open the database at the given url, creating it if it does not exist
+/// yet. The schema is left as it is, see [`AUTH_MIGRATOR`] and
+/// [`CHARACTERS_MIGRATOR`].
+pub async fn connect(url: &str) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+
+    // an in-memory database is dropped along with its last connection
+    let pool = if url.contains(":memory:") {
+        SqlitePoolOptions::new()
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        SqlitePoolOptions::new()
+    }
+    .connect_with(options)
+    .await?;
+
+    Ok(pool)
+}
+
+/// An empty in-memory database with the given migrations applied.
+#[cfg(test)]
+async fn test_pool(migrator: &Migrator) -> SqlitePool {
+    let pool = connect("sqlite::memory:")
+        .await
+        .expect("could not open database");
+    migrator
+        .run(&pool)
+        .await
+        .expect("could not migrate database");
+    pool
+}
@@ -0,0 +1,318 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use azerust_game::{
+    accounts::AccountId,
+    realms::{Realm, RealmFlags, RealmId, RealmList, RealmListError, RealmRegistration, RealmType},
+};
+use enumflags2::BitFlags;
+use sqlx::{query, query_as, FromRow, SqlitePool};
+use tracing::{debug, error, trace};
+
+/// A realm as it is stored in the database.
+#[derive(FromRow)]
+struct RealmRow {
+    id: RealmId,
+    name: String,
+    realm_type: RealmType,
+    build: u32,
+    external_address: String,
+    local_address: String,
+    local_subnet_mask: String,
+    port: u16,
+    flags: u8,
+    timezone: u8,
+    population: f32,
+}
+
+impl From<RealmRow> for Realm {
+    fn from(r: RealmRow) -> Self {
+        Self {
+            id: r.id,
+            name: r.name,
+            realm_type: r.realm_type,
+            build: r.build,
+            external_address: r.external_address,
+            local_address: r.local_address,
+            local_subnet_mask: r.local_subnet_mask,
+            port: r.port,
+            flags: BitFlags::from_bits_truncate(r.flags),
+            timezone: r.timezone,
+            population: r.population,
+        }
+    }
+}
+
+/// A realmlist stored in SQLite. Reads are cheap, so unlike the
+/// MySQL realmlist nothing is cached between requests.
+#[derive(Clone, Debug)]
+pub struct SqliteRealmList {
+    pool: SqlitePool,
+}
+
+impl SqliteRealmList {
+    pub fn new(pool: SqlitePool) -> Self {
+        debug!("Starting realmlist service");
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl RealmList for SqliteRealmList {
+    async fn realms(&self) -> Vec<Realm> {
+        query_as::<_, RealmRow>(
+            "SELECT id, name, icon AS realm_type, gamebuild AS build, address AS external_address, local_address, local_subnet_mask, port, flag AS flags, timezone, population FROM realmlist WHERE flag <> 3 ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map(|realms| realms.into_iter().map(Realm::from).collect())
+        .unwrap_or_else(|e| {
+            error!("could not load realms: {e}");
+            vec![]
+        })
+    }
+
+    async fn reload(&self) {}
+
+    async fn register(&self, realm: &RealmRegistration) -> Result<(), RealmListError> {
+        debug!("Registering realm {:?} ({})", realm.id, realm.name);
+        query(
+            "INSERT INTO realmlist (id, name, address, local_address, local_subnet_mask, port, icon, flag, timezone, gamebuild)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT (id) DO UPDATE SET
+        name = excluded.name, address = excluded.address, local_address = excluded.local_address,
+        local_subnet_mask = excluded.local_subnet_mask, port = excluded.port, icon = excluded.icon,
+        timezone = excluded.timezone, gamebuild = excluded.gamebuild
+    ",
+        )
+        .bind(realm.id)
+        .bind(&realm.name)
+        .bind(&realm.external_address)
+        .bind(&realm.local_address)
+        .bind(&realm.local_subnet_mask)
+        .bind(realm.port)
+        .bind(u8::from(realm.realm_type))
+        .bind(RealmFlags::Offline as u8)
+        .bind(realm.timezone)
+        .bind(realm.build)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn update_status(&self, status: Vec<(RealmId, bool)>) -> Result<(), RealmListError> {
+        let offline = RealmFlags::Offline as u8;
+        for (id, online) in status {
+            query("UPDATE realmlist SET flag = (flag & ~?) | ? WHERE id = ?")
+                .bind(offline)
+                .bind(if online { 0 } else { offline })
+                .bind(id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    async fn set_uptime(
+        &self,
+        id: RealmId,
+        start: SystemTime,
+        population: u32,
+    ) -> Result<(), RealmListError> {
+        let start_u32: u32 = start
+            .duration_since(UNIX_EPOCH)
+            .expect("no time can be smaller than UNIX_EPOCH")
+            .as_secs()
+            .try_into()
+            .map_err(|_| RealmListError::StartTimeTooLarge)?;
+
+        let uptime = SystemTime::now()
+            .duration_since(start)
+            .map_err(|_| RealmListError::StartTimeInFuture)?;
+
+        let uptime_u32: u32 = uptime
+            .as_secs()
+            .try_into()
+            .expect("server won't be up for 40 years");
+
+        trace!("setting uptime for realm {:?} to {:?}", id, uptime);
+        query(
+            "INSERT INTO uptime (realm_id, starttime, uptime, maxplayers, revision)
+    VALUES (?, ?, ?, ?, 'azerust-0.1.0')
+    ON CONFLICT (realm_id, starttime) DO UPDATE SET
+        uptime = excluded.uptime, maxplayers = excluded.maxplayers
+    ",
+        )
+        .bind(id)
+        .bind(start_u32)
+        .bind(uptime_u32)
+        .bind(population)
+        .execute(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn request_shutdown(
+        &self,
+        id: RealmId,
+        at: Option<SystemTime>,
+    ) -> Result<(), RealmListError> {
+        let at: u32 = match at {
+            Some(at) => at
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .try_into()
+                .map_err(|_| RealmListError::ShutdownTimeTooLarge)?,
+            None => {
+                query("DELETE FROM realm_shutdown WHERE realm_id = ?")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+                return Ok(());
+            }
+        };
+
+        query("INSERT INTO realm_shutdown (realm_id, shutdown_at) VALUES (?, ?) ON CONFLICT (realm_id) DO UPDATE SET shutdown_at = excluded.shutdown_at")
+            .bind(id)
+            .bind(at)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn requested_shutdown(&self, id: RealmId) -> Result<Option<SystemTime>, RealmListError> {
+        let at = query_as::<_, (u32,)>("SELECT shutdown_at FROM realm_shutdown WHERE realm_id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(at.map(|(at,)| UNIX_EPOCH + Duration::from_secs(at.into())))
+    }
+
+    async fn set_character_count(
+        &self,
+        id: RealmId,
+        account: AccountId,
+        count: u8,
+    ) -> Result<(), RealmListError> {
+        query("INSERT INTO realm_characters (realm_id, account_id, num_chars) VALUES (?, ?, ?) ON CONFLICT (realm_id, account_id) DO UPDATE SET num_chars = excluded.num_chars")
+            .bind(id)
+            .bind(account)
+            .bind(count)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn character_count(&self, account: AccountId) -> Result<usize, RealmListError> {
+        let (count,) = query_as::<_, (i64,)>(
+            "SELECT COALESCE(SUM(num_chars), 0) FROM realm_characters WHERE account_id = ?",
+        )
+        .bind(account)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| RealmListError::PersistError(e.to_string()))?;
+
+        Ok(count.try_into().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use azerust_game::{
+        accounts::AccountId,
+        realms::{RealmId, RealmList, RealmRegistration, RealmType},
+    };
+
+    use super::SqliteRealmList;
+    use crate::{test_pool, AUTH_MIGRATOR};
+
+    fn registration(name: &str) -> RealmRegistration {
+        RealmRegistration {
+            id: RealmId(1),
+            name: name.to_string(),
+            realm_type: RealmType::PVP,
+            build: 12340,
+            external_address: "127.0.0.1".to_string(),
+            local_address: "127.0.0.1".to_string(),
+            local_subnet_mask: "255.255.255.0".to_string(),
+            port: 8085,
+            timezone: 1,
+        }
+    }
+
+    #[tokio::test]
+    pub async fn register_and_update_status() {
+        let realms = SqliteRealmList::new(test_pool(&AUTH_MIGRATOR).await);
+
+        realms.register(&registration("Azeroth")).await.unwrap();
+        let listed = realms.realms().await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Azeroth");
+        assert!(!listed[0].is_online());
+
+        realms
+            .update_status(vec![(RealmId(1), true)])
+            .await
+            .unwrap();
+        realms.register(&registration("Outland")).await.unwrap();
+        let listed = realms.realms().await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Outland");
+        assert!(listed[0].is_online());
+    }
+
+    #[tokio::test]
+    pub async fn request_shutdown() {
+        let realms = SqliteRealmList::new(test_pool(&AUTH_MIGRATOR).await);
+        let at = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        assert_eq!(realms.requested_shutdown(RealmId(1)).await.unwrap(), None);
+        realms.request_shutdown(RealmId(1), Some(at)).await.unwrap();
+        assert_eq!(
+            realms.requested_shutdown(RealmId(1)).await.unwrap(),
+            Some(at)
+        );
+        realms.request_shutdown(RealmId(1), None).await.unwrap();
+        assert_eq!(realms.requested_shutdown(RealmId(1)).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    pub async fn character_count() {
+        let realms = SqliteRealmList::new(test_pool(&AUTH_MIGRATOR).await);
+        let account = AccountId(1);
+
+        assert_eq!(realms.character_count(account).await.unwrap(), 0);
+        realms
+            .set_character_count(RealmId(1), account, 3)
+            .await
+            .unwrap();
+        realms
+            .set_character_count(RealmId(2), account, 4)
+            .await
+            .unwrap();
+        assert_eq!(realms.character_count(account).await.unwrap(), 7);
+        realms
+            .set_character_count(RealmId(1), account, 1)
+            .await
+            .unwrap();
+        assert_eq!(realms.character_count(account).await.unwrap(), 5);
+    }
+}
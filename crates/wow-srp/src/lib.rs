@@ -2,7 +2,8 @@
 //!
 //! This crate implements the SRP variation that is used in
 //! the World of Warcraft authentication protocol. It provides
-//! currently only provides a [`WowSRPServer`].
+//! a [`WowSRPServer`], as well as a [`WowSRPClient`] which is
+//! mostly useful for testing servers.
 
 #![deny(
     missing_docs,
@@ -66,25 +67,8 @@ impl From<&Verifier> for BigUint {
 impl Verifier {
     /// Create a verifier from a set of credentials and salt.
     pub fn from_credentials(username: &str, password: &str, salt: &Salt) -> Self {
-        let inner = {
-            let mut d = Sha1::new();
-            d.update(username.as_bytes());
-            d.update(":");
-            d.update(password.as_bytes());
-            d.finalize()
-        };
-
-        let mut hash = Sha1::new();
-        hash.update(salt.0);
-        hash.update(inner);
-        let hash = BigUint::from_bytes_le(&hash.finalize());
-
-        Self(
-            G.modpow(&hash, &*N)
-                .to_bytes_le()
-                .try_into()
-                .expect("correct size"),
-        )
+        let x = calculate_x(username, password, salt);
+        Self(to_array(&G.modpow(&x, &N)))
     }
 
     /// Create a verifier from raw bytes.
@@ -171,11 +155,7 @@ impl WowSRPServer {
         client_proof: &[u8; 20],
         session_key: &[u8; 40],
     ) -> [u8; 20] {
-        let mut sha = Sha1::new();
-        sha.update(a_pub);
-        sha.update(client_proof);
-        sha.update(session_key);
-        sha.finalize().try_into().expect("sha1 hashes are 20 bytes")
+        calculate_server_proof(a_pub, client_proof, session_key)
     }
 
     /// Verify the challenge response, returning a verified key if
@@ -204,32 +184,17 @@ impl WowSRPServer {
         let u = BigUint::from_bytes_le(&a_b);
         let premaster_secret = (&a_pub_num * verifier.modpow(&u, &N)).modpow(&b, &N);
 
-        // todo(arlyon): this can panic
-        let session_key = WowSRPServer::derive_session_key(
-            &premaster_secret
-                .to_bytes_le()
-                .try_into()
-                .expect("correct size"),
-        );
-
-        let hn_xor_hg: Vec<_> = Sha1::digest(&N.to_bytes_le())
-            .iter()
-            .zip(Sha1::digest(&G.to_bytes_le()))
-            .map(|(f, s)| f ^ s)
-            .collect();
+        let session_key = WowSRPServer::derive_session_key(&to_array(&premaster_secret));
 
-        let server_m = {
-            let mut sha = Sha1::new();
-            sha.update(&hn_xor_hg);
-            sha.update(&self.identity_hash);
-            sha.update(&self.salt.0);
-            sha.update(a_pub);
-            sha.update(&self.b_pub);
-            sha.update(&session_key);
-            sha.finalize()
-        };
+        let server_m = calculate_client_proof(
+            &self.identity_hash,
+            &self.salt,
+            a_pub,
+            &self.b_pub,
+            &session_key,
+        );
 
-        if server_m.as_slice() == client_m {
+        if server_m == *client_m {
             Some(session_key)
         } else {
             None
@@ -242,10 +207,7 @@ impl WowSRPServer {
         let fst = G.modpow(&BigUint::from_bytes_be(b), &N);
         let snd = BigUint::from(v) * BigUint::from(3u8);
 
-        ((fst + snd) % &*N)
-            .to_bytes_le()
-            .try_into()
-            .expect("correct size")
+        to_array(&((fst + snd) % &*N))
     }
 
     /// Calculates the session key by running it through a SHA1 interleave.
@@ -277,9 +239,158 @@ impl WowSRPServer {
     }
 }
 
+/// Provides the client-side functionality of the WoW
+/// SRP protocol. This lets a server be tested without
+/// needing a real game client.
+///
+/// ```rust
+/// use wow_srp::{WowSRPClient, WowSRPServer};
+///
+/// let (verifier, salt) = WowSRPServer::register("ARLYON", "TEST");
+/// let server = WowSRPServer::new("ARLYON", salt, verifier);
+///
+/// let client = WowSRPClient::new("ARLYON", "TEST");
+/// let (proof, session_key) = client
+///     .calculate_proof(server.get_b_pub(), server.get_salt())
+///     .unwrap();
+///
+/// assert_eq!(
+///     server.verify_challenge_response(client.get_a_pub(), &proof),
+///     Some(session_key)
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WowSRPClient {
+    username: String,
+    password: String,
+    a: [u8; 32],
+    a_pub: [u8; 32],
+}
+
+impl WowSRPClient {
+    /// Create a new client for the given credentials.
+    pub fn new(username: &str, password: &str) -> Self {
+        let mut rng = rand::thread_rng();
+        let a: [u8; 32] = rng.gen();
+        Self {
+            username: username.to_string(),
+            password: password.to_string(),
+            a_pub: to_array(&G.modpow(&BigUint::from_bytes_le(&a), &N)),
+            a,
+        }
+    }
+
+    /// Get the ephemeral public key for this client.
+    pub fn get_a_pub(&self) -> &[u8; 32] {
+        &self.a_pub
+    }
+
+    /// Answer the challenge sent by the server, returning the client
+    /// proof (M1) and the session key, or `None` if the server's
+    /// public key is not valid.
+    pub fn calculate_proof(&self, b_pub: &[u8; 32], salt: &Salt) -> Option<([u8; 20], [u8; 40])> {
+        let b_pub_num = BigUint::from_bytes_le(b_pub);
+        if (&b_pub_num % &*N).eq(&0u8.into()) {
+            return None;
+        }
+
+        let a_b = {
+            let mut sha = Sha1::new();
+            sha.update(self.a_pub);
+            sha.update(b_pub);
+            sha.finalize()
+        };
+
+        let u = BigUint::from_bytes_le(&a_b);
+        let x = calculate_x(&self.username, &self.password, salt);
+        let kv = (G.modpow(&x, &N) * BigUint::from(3u8)) % &*N;
+        let premaster_secret =
+            ((b_pub_num + &*N - kv) % &*N).modpow(&(BigUint::from_bytes_le(&self.a) + u * x), &N);
+
+        let session_key = WowSRPServer::derive_session_key(&to_array(&premaster_secret));
+        let identity_hash: [u8; 20] = Sha1::digest(self.username.as_bytes())
+            .try_into()
+            .expect("sha1 hashes are 20 bytes");
+
+        Some((
+            calculate_client_proof(&identity_hash, salt, &self.a_pub, b_pub, &session_key),
+            session_key,
+        ))
+    }
+
+    /// Get the proof (M2) that the server is expected to respond with.
+    pub fn get_server_proof(&self, client_proof: &[u8; 20], session_key: &[u8; 40]) -> [u8; 20] {
+        calculate_server_proof(&self.a_pub, client_proof, session_key)
+    }
+}
+
+/// Calculates the private key (x) from a set of credentials and salt.
+fn calculate_x(username: &str, password: &str, salt: &Salt) -> BigUint {
+    let inner = {
+        let mut d = Sha1::new();
+        d.update(username.as_bytes());
+        d.update(":");
+        d.update(password.as_bytes());
+        d.finalize()
+    };
+
+    let mut hash = Sha1::new();
+    hash.update(salt.0);
+    hash.update(inner);
+    BigUint::from_bytes_le(&hash.finalize())
+}
+
+/// Calculates the proof (M1) that the client sends to prove
+/// that it knows the session key.
+fn calculate_client_proof(
+    identity_hash: &[u8; 20],
+    salt: &Salt,
+    a_pub: &[u8; 32],
+    b_pub: &[u8; 32],
+    session_key: &[u8; 40],
+) -> [u8; 20] {
+    let hn_xor_hg: Vec<_> = Sha1::digest(&N.to_bytes_le())
+        .iter()
+        .zip(Sha1::digest(&G.to_bytes_le()))
+        .map(|(f, s)| f ^ s)
+        .collect();
+
+    let mut sha = Sha1::new();
+    sha.update(&hn_xor_hg);
+    sha.update(identity_hash);
+    sha.update(salt.0);
+    sha.update(a_pub);
+    sha.update(b_pub);
+    sha.update(session_key);
+    sha.finalize().try_into().expect("sha1 hashes are 20 bytes")
+}
+
+/// Calculates the proof (M2) that the server sends in response
+/// to a valid client proof.
+fn calculate_server_proof(
+    a_pub: &[u8; 32],
+    client_proof: &[u8; 20],
+    session_key: &[u8; 40],
+) -> [u8; 20] {
+    let mut sha = Sha1::new();
+    sha.update(a_pub);
+    sha.update(client_proof);
+    sha.update(session_key);
+    sha.finalize().try_into().expect("sha1 hashes are 20 bytes")
+}
+
+/// Converts a number smaller than N into little endian bytes,
+/// padding it with zeros if it has fewer significant bytes.
+fn to_array(n: &BigUint) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    let bytes = n.to_bytes_le();
+    out[..bytes.len()].copy_from_slice(&bytes);
+    out
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{Salt, Verifier, WowSRPServer};
+    use crate::{Salt, Verifier, WowSRPClient, WowSRPServer};
 
     #[test]
     pub fn test_session_key_derivation() {
@@ -367,4 +478,40 @@ mod test {
 
         assert_eq!(v.0, v_expected);
     }
+
+    #[test]
+    pub fn client_server_handshake() {
+        let (verifier, salt) = WowSRPServer::register("ARLYON", "TEST");
+        let server = WowSRPServer::new("ARLYON", salt, verifier);
+        let client = WowSRPClient::new("ARLYON", "TEST");
+
+        let (proof, session_key) = client
+            .calculate_proof(server.get_b_pub(), server.get_salt())
+            .unwrap();
+
+        assert_eq!(
+            server.get_server_proof(client.get_a_pub(), &proof, &session_key),
+            client.get_server_proof(&proof, &session_key)
+        );
+        assert_eq!(
+            server.verify_challenge_response(client.get_a_pub(), &proof),
+            Some(session_key)
+        );
+    }
+
+    #[test]
+    pub fn client_wrong_password() {
+        let (verifier, salt) = WowSRPServer::register("ARLYON", "TEST");
+        let server = WowSRPServer::new("ARLYON", salt, verifier);
+        let client = WowSRPClient::new("ARLYON", "WRONG");
+
+        let (proof, _) = client
+            .calculate_proof(server.get_b_pub(), server.get_salt())
+            .unwrap();
+
+        assert_eq!(
+            server.verify_challenge_response(client.get_a_pub(), &proof),
+            None
+        );
+    }
 }
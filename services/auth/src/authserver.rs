@@ -1,20 +1,24 @@
 use std::{
     collections::HashMap,
     fmt, iter,
-    net::Ipv4Addr,
+    net::{IpAddr, Ipv4Addr},
     str,
     sync::Arc,
     time::{self, Instant},
 };
 
 use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use azerust_console::{accounts, Command, Console, Listener};
 use azerust_game::{
     accounts::{AccountService, ConnectToken, ReconnectToken},
-    realms::{RealmFlags, RealmList},
+    audit::{LoginAttempt, LoginKind},
+    realms::{RealmId, RealmList},
 };
 use azerust_protocol::auth::{AuthCommand, ReturnCode};
 use azerust_utils::flatten;
 use bincode::Options;
+use chrono::Utc;
 use derivative::Derivative;
 use derive_more::Display;
 use futures_util::StreamExt;
@@ -78,6 +82,8 @@ pub struct AuthServer<T: AccountService + fmt::Debug, R: RealmList> {
     accounts: T,
     realms: R,
     heartbeat: RwLock<HashMap<u8, Instant>>,
+    /// how long to keep login attempts for, or forever if `None`
+    login_retention: Option<time::Duration>,
 }
 
 impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
@@ -86,6 +92,14 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
             accounts,
             realms,
             heartbeat: RwLock::new(HashMap::new()),
+            login_retention: None,
+        }
+    }
+
+    pub fn with_login_retention(self, login_retention: Option<time::Duration>) -> Self {
+        Self {
+            login_retention,
+            ..self
         }
     }
 
@@ -107,6 +121,17 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
                         .insert(realm_id, Instant::now());
                     trace!("got heartbeat for {realm_id} with realm pop {realm_pop}")
                 }
+                Ok((1u8, realm_id, _)) => {
+                    self.heartbeat.write().await.remove(&realm_id);
+                    info!("realm {realm_id} is shutting down");
+                    if let Err(e) = self
+                        .realms
+                        .update_status(vec![(RealmId(realm_id.into()), false)])
+                        .await
+                    {
+                        error!("error while marking realm {realm_id} offline: {e}");
+                    }
+                }
                 Ok((_, _, 0u32)) | _ => debug!("received bad buffer: {:02X?}", &buffer),
             }
         }
@@ -124,9 +149,9 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
                 data.extend(
                     write
                         .drain_filter(|_, v| now.saturating_duration_since(*v).as_secs() > 15)
-                        .map(|(k, _)| (k, RealmFlags::Offline)),
+                        .map(|(k, _)| (RealmId(k.into()), false)),
                 );
-                data.extend(write.keys().map(|&k| (k, RealmFlags::Recommended)));
+                data.extend(write.keys().map(|&k| (RealmId(k.into()), true)));
                 data
             };
             trace!("updating realm populations: {:?}", data);
@@ -137,6 +162,18 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
         Ok(())
     }
 
+    /// periodically deactivates bans that have run out
+    #[instrument(skip(self))]
+    pub async fn ban_expiry(&self) -> Result<()> {
+        let mut interval = interval(time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.accounts.expire_bans().await {
+                error!("error while expiring bans: {e}");
+            }
+        }
+    }
+
     #[instrument(skip(self, host, port))]
     pub async fn authentication(&self, host: Ipv4Addr, port: u16) -> Result<()> {
         let addr = (host, port);
@@ -154,8 +191,60 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
         Ok(())
     }
 
+    async fn record_login(&self, attempt: LoginAttempt) {
+        if let Err(e) = self.accounts.record_login(&attempt).await {
+            error!("could not record login for {}: {e}", attempt.username);
+        }
+    }
+
+    /// periodically deletes login attempts older than the retention period
+    #[instrument(skip(self))]
+    pub async fn login_history_pruner(&self) -> Result<()> {
+        let retention = match self.login_retention {
+            Some(r) => chrono::Duration::from_std(r)?,
+            None => return Ok(()),
+        };
+
+        let mut interval = interval(time::Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self
+                .accounts
+                .prune_login_history(Utc::now() - retention)
+                .await
+            {
+                error!("error while pruning login history: {e}");
+            }
+        }
+    }
+
     #[instrument(skip(self, stream))]
     async fn connect_loop(&self, stream: &mut TcpStream) -> Result<()> {
+        let ip = stream.peer_addr()?.ip();
+        let mut attempt = None;
+        let result = self.handle_requests(stream, ip, &mut attempt).await;
+
+        // the attempt ended without a result, from an error or the client leaving
+        if let Some(attempt) = attempt {
+            self.record_login(LoginAttempt {
+                result: ReturnCode::Disconnected.into(),
+                ..attempt
+            })
+            .await;
+        }
+
+        result
+    }
+
+    /// Handles the requests on a connection until it is rejected or
+    /// closed, recording the login attempt once it has a result. An
+    /// attempt that has not been recorded is left in `attempt`.
+    async fn handle_requests(
+        &self,
+        stream: &mut TcpStream,
+        ip: IpAddr,
+        attempt: &mut Option<LoginAttempt>,
+    ) -> Result<()> {
         let mut state = RequestState::Start;
 
         loop {
@@ -163,10 +252,15 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
             debug!("received message {message} in state {state}");
             state = match (state, message) {
                 (_, Message::Connect(r)) => {
-                    handle_connect_request(&r, &self.accounts, stream).await?
+                    let username = read_username(&r, stream).await?;
+                    *attempt = Some(login_attempt(LoginKind::Connect, &r, &username, ip));
+                    handle_connect_request(&r, username.as_deref(), &self.accounts, stream).await?
                 }
                 (_, Message::ReConnect(r)) => {
-                    handle_reconnect_request(&r, &self.accounts, stream).await?
+                    let username = read_username(&r, stream).await?;
+                    *attempt = Some(login_attempt(LoginKind::Reconnect, &r, &username, ip));
+                    handle_reconnect_request(&r, username.as_deref(), &self.accounts, stream)
+                        .await?
                 }
                 (RequestState::ConnectChallenge { token }, Message::Proof(proof)) => {
                     handle_connect_proof(&proof, &self.accounts, &token, stream).await?
@@ -183,6 +277,21 @@ impl<T: AccountService + fmt::Debug, R: RealmList> AuthServer<T, R> {
                 _ => bail!("received message in bad state"),
             };
 
+            let result = match &state {
+                RequestState::Rejected { reason, .. } => Some(*reason),
+                RequestState::Realmlist => Some(ReturnCode::Success),
+                _ => None,
+            };
+            if let Some(result) = result {
+                if let Some(attempt) = attempt.take() {
+                    self.record_login(LoginAttempt {
+                        result: result.into(),
+                        ..attempt
+                    })
+                    .await;
+                }
+            }
+
             if let RequestState::Rejected { command, reason } = state {
                 let mut buffer = [0u8; 3];
                 wow_bincode()
@@ -202,7 +311,13 @@ impl<
         R: 'static + RealmList + Send + Sync,
     > AuthServer<T, R>
 {
-    pub async fn start(self, host: Ipv4Addr, port: u16, heartbeat_port: u16) -> Result<()> {
+    pub async fn start(
+        self,
+        host: Ipv4Addr,
+        port: u16,
+        heartbeat_port: u16,
+        admin: Option<Listener>,
+    ) -> Result<()> {
         let server = Arc::new(self);
 
         let a = flatten(tokio::task::Builder::new().name("auth::server").spawn({
@@ -228,15 +343,110 @@ impl<
             async move { server.realmlist_updater().await.context("realmlist error") }
         }));
 
-        try_join!(a, b, c)?;
+        let d = flatten(tokio::task::Builder::new().name("auth::console").spawn({
+            let server = server.clone();
+            async move {
+                azerust_console::run(&*server, admin)
+                    .await
+                    .context("console error")
+            }
+        }));
+
+        let e = flatten(tokio::task::Builder::new().name("auth::bans").spawn({
+            let server = server.clone();
+            async move { server.ban_expiry().await.context("ban expiry error") }
+        }));
+
+        let f = flatten(tokio::task::Builder::new().name("auth::audit").spawn({
+            let server = server.clone();
+            async move {
+                server
+                    .login_history_pruner()
+                    .await
+                    .context("login history error")
+            }
+        }));
+
+        try_join!(a, b, c, d, e, f)?;
 
         Ok(())
     }
 }
 
+#[async_trait]
+impl<
+        T: 'static + AccountService + fmt::Debug + Send + Sync,
+        R: 'static + RealmList + Send + Sync,
+    > Console for AuthServer<T, R>
+{
+    async fn run(&self, command: Command) -> Result<String> {
+        match command {
+            Command::Account { command, json } => {
+                accounts::run(command, &self.accounts).await?.render(json)
+            }
+            Command::Reload => {
+                self.realms.reload().await;
+                Ok("reloaded realmlist".to_string())
+            }
+            Command::Character { .. }
+            | Command::Online
+            | Command::Kick { .. }
+            | Command::Announce { .. }
+            | Command::Shutdown { .. } => bail!("not supported by the auth server"),
+        }
+    }
+}
+
+/// Reads the username that follows a connect or reconnect request.
+/// Returns `None` if the username is not valid.
+async fn read_username(request: &ConnectRequest, stream: &mut TcpStream) -> Result<Option<String>> {
+    let mut buffer = [0u8; 16];
+    let username = buffer
+        .get_mut(..request.identifier_length as usize)
+        .context("username is too long")?;
+    stream.read_exact(username).await?;
+    match str::from_utf8(username) {
+        Ok(s) => Ok(Some(s.to_string())),
+        Err(e) => {
+            debug!("user connected with invalid username: {e}");
+            Ok(None)
+        }
+    }
+}
+
+/// Reads a four character code, such as the os or country,
+/// which the client sends reversed and null padded.
+fn four_cc(code: [u8; 4]) -> String {
+    code.iter()
+        .rev()
+        .filter(|&&c| c != 0)
+        .map(|&c| c as char)
+        .collect()
+}
+
+fn login_attempt(
+    kind: LoginKind,
+    request: &ConnectRequest,
+    username: &Option<String>,
+    ip: IpAddr,
+) -> LoginAttempt {
+    LoginAttempt {
+        kind,
+        username: username.clone().unwrap_or_default(),
+        account: None,
+        ip,
+        build: request.build,
+        os: four_cc(request.os),
+        country: four_cc(request.country),
+        result: ReturnCode::Failed.into(),
+        time: Utc::now(),
+    }
+}
+
 #[instrument(skip(request, accounts, stream))]
 async fn handle_connect_request(
     request: &ConnectRequest,
+    username: Option<&str>,
     accounts: &dyn AccountService,
     stream: &mut TcpStream,
 ) -> Result<RequestState> {
@@ -247,19 +457,13 @@ async fn handle_connect_request(
         });
     };
 
-    let mut buffer = [0u8; 16];
-    let username = {
-        let username = &mut buffer[..request.identifier_length as usize];
-        stream.read_exact(username).await?;
-        match str::from_utf8(username) {
-            Ok(s) => s,
-            Err(e) => {
-                debug!("user connected with invalid username: {e}");
-                return Ok(RequestState::Rejected {
-                    command: AuthCommand::Connect,
-                    reason: ReturnCode::Failed,
-                });
-            }
+    let username = match username {
+        Some(username) => username,
+        None => {
+            return Ok(RequestState::Rejected {
+                command: AuthCommand::Connect,
+                reason: ReturnCode::Failed,
+            })
         }
     };
 
@@ -331,6 +535,7 @@ async fn handle_connect_proof(
 #[instrument(skip(request, accounts))]
 async fn handle_reconnect_request(
     request: &ConnectRequest,
+    username: Option<&str>,
     accounts: &dyn AccountService,
     stream: &mut TcpStream,
 ) -> Result<RequestState> {
@@ -341,19 +546,13 @@ async fn handle_reconnect_request(
         });
     }
 
-    let mut buffer = [0u8; 16];
-    let username = {
-        let username = &mut buffer[..request.identifier_length as usize];
-        stream.read_exact(username).await?;
-        match str::from_utf8(username) {
-            Ok(s) => s,
-            Err(e) => {
-                debug!("user connected with invalid username: {e}");
-                return Ok(RequestState::Rejected {
-                    command: AuthCommand::ReConnect,
-                    reason: ReturnCode::Failed,
-                });
-            }
+    let username = match username {
+        Some(username) => username,
+        None => {
+            return Ok(RequestState::Rejected {
+                command: AuthCommand::ReConnect,
+                reason: ReturnCode::Failed,
+            })
         }
     };
 
@@ -427,3 +626,259 @@ async fn handle_realmlist(realms: &dyn RealmList, stream: &mut TcpStream) -> Res
     stream.write_all(&packet).await?;
     Ok(RequestState::Realmlist)
 }
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::{sync::Arc, time};
+
+    use azerust_game::accounts::{Account, AccountId, AccountService, BanStatus};
+    use azerust_memory::{fixtures, MemoryAccountService, MemoryRealmList};
+    use azerust_protocol::auth::{AuthCommand, ReturnCode};
+    use bincode::Options;
+    use chrono::{Duration, Utc};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        time::sleep,
+    };
+    use wow_srp::{Salt, WowSRPClient};
+
+    use super::AuthServer;
+    use crate::{
+        protocol::packets::{ConnectProof, ConnectRequest},
+        wow_bincode::wow_bincode,
+    };
+
+    /// Starts an auth server for the given accounts, returning the
+    /// account service along with the address to connect to.
+    async fn server(accounts: Vec<Account>) -> (MemoryAccountService, String) {
+        let accounts = MemoryAccountService::new(accounts);
+        let realms = MemoryRealmList::new(vec![fixtures::realm(1, "Azeroth", 8085)]);
+        let server = Arc::new(AuthServer::new(accounts.clone(), realms));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let _ = server.connect_loop(&mut stream).await;
+            }
+        });
+
+        (accounts, addr)
+    }
+
+    /// Connects to the server, sending a connect request for the given username.
+    async fn connect(addr: &str, username: &str) -> TcpStream {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let request = ConnectRequest {
+            error: 0,
+            size: 30 + username.len() as u16,
+            game_name: *b"WoW\0",
+            version_major: 3,
+            version_minor: 3,
+            version_patch: 5,
+            build: 12340,
+            platform: *b"68x\0",
+            os: *b"niW\0",
+            country: *b"SUne",
+            timezone_bias: 60,
+            ipv4: [127, 0, 0, 1],
+            identifier_length: username.len() as u8,
+        };
+
+        let mut packet = vec![u8::from(AuthCommand::Connect)];
+        packet.extend(wow_bincode().serialize(&request).unwrap());
+        packet.extend(username.as_bytes());
+        stream.write_all(&packet).await.unwrap();
+
+        stream
+    }
+
+    /// Reads the response to a connect request, returning the public key
+    /// and salt of the server, or the return code if it was rejected.
+    async fn challenge(stream: &mut TcpStream) -> Result<([u8; 32], Salt), u8> {
+        let mut header = [0u8; 3];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], u8::from(AuthCommand::Connect));
+        if header[2] != u8::from(ReturnCode::Success) {
+            return Err(header[2]);
+        }
+
+        // B, g, N, s, the version challenge and the security flags
+        let mut body = [0u8; 32 + 2 + 33 + 32 + 16 + 1];
+        stream.read_exact(&mut body).await.unwrap();
+        Ok((
+            body[..32].try_into().unwrap(),
+            Salt(body[67..99].try_into().unwrap()),
+        ))
+    }
+
+    /// Sends the proof for a challenge, returning the proof and session key.
+    async fn prove(
+        stream: &mut TcpStream,
+        client: &WowSRPClient,
+        b_pub: &[u8; 32],
+        salt: &Salt,
+    ) -> ([u8; 20], [u8; 40]) {
+        let (user_proof, session_key) = client.calculate_proof(b_pub, salt).unwrap();
+        let proof = ConnectProof {
+            user_public_key: *client.get_a_pub(),
+            user_proof,
+            crc_hash: [0; 20],
+            number_of_keys: 0,
+            security_flags: 0,
+        };
+
+        let mut packet = vec![u8::from(AuthCommand::Proof)];
+        packet.extend(wow_bincode().serialize(&proof).unwrap());
+        stream.write_all(&packet).await.unwrap();
+
+        (user_proof, session_key)
+    }
+
+    #[tokio::test]
+    pub async fn login_and_list_realms() {
+        let (accounts, addr) = server(vec![fixtures::account(1, "ARLYON", "TEST")]).await;
+        let mut stream = connect(&addr, "ARLYON").await;
+
+        let (b_pub, salt) = challenge(&mut stream).await.unwrap();
+        let client = WowSRPClient::new("ARLYON", "TEST");
+        let (proof, session_key) = prove(&mut stream, &client, &b_pub, &salt).await;
+
+        let mut response = [0u8; 32];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(response[..2], [u8::from(AuthCommand::Proof), 0]);
+        assert_eq!(
+            response[2..22],
+            client.get_server_proof(&proof, &session_key)
+        );
+
+        stream
+            .write_all(&[u8::from(AuthCommand::RealmList), 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let mut header = [0u8; 3];
+        stream.read_exact(&mut header).await.unwrap();
+        assert_eq!(header[0], u8::from(AuthCommand::RealmList));
+        let mut realms = vec![0u8; u16::from_le_bytes([header[1], header[2]]) as usize];
+        stream.read_exact(&mut realms).await.unwrap();
+
+        // the realm count, followed by the realms themselves
+        assert_eq!(realms[4..6], 1u16.to_le_bytes());
+        assert_eq!(&realms[9..17], b"Azeroth\0");
+
+        let account = accounts.get(AccountId(1)).await.unwrap();
+        assert_eq!(account.session_key, Some(session_key));
+
+        let logins = accounts.login_history(None, 10).await.unwrap();
+        assert_eq!(logins.len(), 1);
+        assert!(logins[0].succeeded());
+        assert_eq!(logins[0].account, Some(AccountId(1)));
+        assert_eq!(logins[0].os, "Win");
+    }
+
+    #[tokio::test]
+    pub async fn wrong_password_is_rejected() {
+        let (accounts, addr) = server(vec![fixtures::account(1, "ARLYON", "TEST")]).await;
+        let mut stream = connect(&addr, "ARLYON").await;
+
+        let (b_pub, salt) = challenge(&mut stream).await.unwrap();
+        let client = WowSRPClient::new("ARLYON", "WRONG");
+        prove(&mut stream, &client, &b_pub, &salt).await;
+
+        let mut response = [0u8; 3];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(
+            response,
+            [
+                u8::from(AuthCommand::Proof),
+                0,
+                u8::from(ReturnCode::IncorrectPassword)
+            ]
+        );
+
+        let account = accounts.get(AccountId(1)).await.unwrap();
+        assert_eq!(account.session_key, None);
+
+        let logins = accounts
+            .login_history(Some(AccountId(1)), 10)
+            .await
+            .unwrap();
+        assert_eq!(logins[0].result, u8::from(ReturnCode::IncorrectPassword));
+    }
+
+    #[tokio::test]
+    pub async fn unknown_account_is_rejected() {
+        let (accounts, addr) = server(vec![fixtures::account(1, "ARLYON", "TEST")]).await;
+        let mut stream = connect(&addr, "THRALL").await;
+
+        assert_eq!(
+            challenge(&mut stream).await,
+            Err(u8::from(ReturnCode::UnknownAccount))
+        );
+
+        let logins = accounts.login_history(None, 10).await.unwrap();
+        assert_eq!(logins[0].username, "THRALL");
+        assert_eq!(logins[0].account, None);
+    }
+
+    #[tokio::test]
+    pub async fn abandoned_login_is_recorded() {
+        let (accounts, addr) = server(vec![fixtures::account(1, "ARLYON", "TEST")]).await;
+        let mut stream = connect(&addr, "ARLYON").await;
+        challenge(&mut stream).await.unwrap();
+        drop(stream);
+
+        // the attempt is recorded once the server notices the client has gone
+        let mut logins = vec![];
+        for _ in 0..100 {
+            logins = accounts.login_history(None, 10).await.unwrap();
+            if !logins.is_empty() {
+                break;
+            }
+            sleep(time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(logins.len(), 1);
+        assert_eq!(logins[0].result, u8::from(ReturnCode::Disconnected));
+    }
+
+    #[tokio::test]
+    pub async fn banned_account_is_rejected() {
+        let account = Account {
+            ban_status: Some(BanStatus::Permanent),
+            ..fixtures::account(1, "ARLYON", "TEST")
+        };
+        let (_, addr) = server(vec![account]).await;
+        let mut stream = connect(&addr, "ARLYON").await;
+
+        assert_eq!(
+            challenge(&mut stream).await,
+            Err(u8::from(ReturnCode::Banned))
+        );
+    }
+
+    #[tokio::test]
+    pub async fn suspended_account_is_rejected() {
+        let account = Account {
+            ban_status: Some(BanStatus::Temporary {
+                until: Utc::now() + Duration::days(1),
+            }),
+            ..fixtures::account(1, "ARLYON", "TEST")
+        };
+        let (accounts, addr) = server(vec![account]).await;
+        let mut stream = connect(&addr, "ARLYON").await;
+
+        assert_eq!(
+            challenge(&mut stream).await,
+            Err(u8::from(ReturnCode::Suspended))
+        );
+
+        // once the ban is lifted, the account can log in again
+        accounts.unban(AccountId(1)).await.unwrap();
+        let mut stream = connect(&addr, "ARLYON").await;
+        assert!(challenge(&mut stream).await.is_ok());
+    }
+}
@@ -1,6 +1,7 @@
 use std::{net::Ipv4Addr, path::PathBuf};
 
 use anyhow::{Context, Result};
+use azerust_graphql::MailConfig;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -9,9 +10,26 @@ pub struct AuthServerConfig {
     pub port: u16,
     pub heartbeat_port: u16,
     pub api_port: Option<u16>,
-    pub console_port: Option<u16>,
+    /// The port to serve tokio-console on
+    #[serde(alias = "console_port")]
+    pub tokio_console_port: Option<u16>,
+    /// Where to send mail, such as password reset tokens
+    #[serde(default)]
+    pub mail: MailConfig,
+    /// The local port to accept admin console connections on
+    pub admin_port: Option<u16>,
+    /// The secret admin console connections must send before running commands
+    #[serde(default)]
+    pub admin_secret: Option<String>,
 
     pub auth_database: String,
+    /// How many days to keep the login history for. If unset, it is kept forever
+    #[serde(default)]
+    pub login_retention_days: Option<u64>,
+    /// The character databases of every realm, so that characters
+    /// can be cleaned up when an account is deleted
+    #[serde(default)]
+    pub character_databases: Vec<String>,
 }
 
 impl AuthServerConfig {
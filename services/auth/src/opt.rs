@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use azerust_console::Command;
 use structopt::StructOpt;
 
 /// An authentication server for Wrath of the Lich King.
@@ -13,32 +14,26 @@ pub struct Opt {
     pub command: Option<OptCommand>,
 }
 
+impl Opt {
+    /// Parses the options from the command line arguments.
+    pub fn parse() -> Self {
+        let matches = Self::clap().get_matches();
+        let mut opt = Self::from_clap(&matches);
+        if let (Some(OptCommand::Exec(command)), Some(matches)) =
+            (&mut opt.command, matches.subcommand_matches("exec"))
+        {
+            *command = Command::from_matches(matches);
+        }
+        opt
+    }
+}
+
 #[derive(StructOpt, Debug)]
 pub enum OptCommand {
     /// Execute a command directly.
     Exec(Command),
     /// Generate a new config file.
     Init,
-}
-
-#[derive(StructOpt, Debug)]
-pub enum Command {
-    Account {
-        #[structopt(subcommand)]
-        command: AccountCommand,
-    },
-}
-
-/// Commands for managing accounts
-#[derive(StructOpt, Debug)]
-pub enum AccountCommand {
-    /// Create a new account
-    Create {
-        /// The username of the new account
-        username: String,
-        /// The password to use
-        password: String,
-        /// The email address
-        email: String,
-    },
+    /// Create or upgrade the database schemas.
+    Migrate,
 }
@@ -211,7 +211,7 @@ impl Realm {
         Self {
             realm_type: r.realm_type.into(),
             locked,
-            flags: r.flags,
+            flags: r.flags.bits(),
             name: r.name.clone(),
             socket: format!("{}:{}", r.external_address, r.port),
             population: r.population,
@@ -359,6 +359,8 @@ mod test {
             joindate: Utc::now(),
             last_login: None,
             online: 0,
+            locked: 0,
+            expansion: 2,
         };
 
         let server = WowSRPServer::new(&account.username, account.salt, account.verifier);
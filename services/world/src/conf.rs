@@ -1,23 +1,97 @@
 use std::{net::Ipv4Addr, path::PathBuf};
 
 use anyhow::{Context, Result};
-use azerust_game::realms::RealmId;
+use azerust_game::realms::{RealmId, RealmType};
+use azerust_protocol::BannedAddon;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct WorldServerConfig {
     pub bind_address: Ipv4Addr,
-    pub port: u32,
-    pub console_port: Option<u16>,
+    pub port: u16,
+    /// The port to serve tokio-console on
+    #[serde(alias = "console_port")]
+    pub tokio_console_port: Option<u16>,
+    /// The local port to accept admin console connections on
+    pub admin_port: Option<u16>,
+    /// The secret admin console connections must send before running commands
+    #[serde(default)]
+    pub admin_secret: Option<String>,
 
     pub auth_server_address: String,
 
     pub character_database: String,
     pub auth_database: String,
-    pub world_database: String,
+    /// The TrinityCore world database to load creatures, objects
+    /// and items from. Without one, the world is empty.
+    pub world_database: Option<String>,
+    /// The directory of DBC files extracted from the client. Without
+    /// it, enchantments are not shown on the character screen.
+    #[serde(default)]
+    pub dbc_dir: Option<PathBuf>,
 
     pub realm_id: RealmId,
+    pub realm_name: String,
+    pub realm_type: RealmType,
+    pub timezone: u8,
+    pub game_build: u32,
+
+    /// The address clients use to reach this server
+    pub external_address: String,
+    /// The address clients on the local network use to reach this server
+    pub local_address: String,
+    pub local_subnet_mask: String,
+
     pub data_dir: u32,
+
+    /// The number of characters an account may have on this realm
+    #[serde(default = "default_characters_per_realm")]
+    pub characters_per_realm: usize,
+    /// The number of characters an account may have across every realm
+    #[serde(default = "default_characters_per_account")]
+    pub characters_per_account: usize,
+    /// The level an account needs a character at to create a death knight
+    #[serde(default = "default_death_knight_level")]
+    pub death_knight_level: u8,
+    /// Names that new characters may not take
+    #[serde(default)]
+    pub reserved_names: Vec<String>,
+    /// Words that may not appear in the name of a new character
+    #[serde(default)]
+    pub profane_names: Vec<String>,
+
+    /// Keep deleted characters so that they can be restored
+    #[serde(default)]
+    pub soft_delete: bool,
+    /// Characters below this level are deleted outright
+    #[serde(default)]
+    pub soft_delete_min_level: u8,
+    /// Death knights below this level are deleted outright
+    #[serde(default)]
+    pub soft_delete_death_knight_min_level: u8,
+    /// The number of days to keep deleted characters for, or 0 to keep them forever
+    #[serde(default = "default_soft_delete_keep_days")]
+    pub soft_delete_keep_days: u64,
+
+    /// Addons that clients are told to disable
+    #[serde(default)]
+    pub banned_addons: Vec<BannedAddon>,
+}
+
+fn default_characters_per_realm() -> usize {
+    10
+}
+
+fn default_characters_per_account() -> usize {
+    50
+}
+
+fn default_death_knight_level() -> u8 {
+    55
+}
+
+fn default_soft_delete_keep_days() -> u64 {
+    30
 }
 
 impl WorldServerConfig {
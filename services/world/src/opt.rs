@@ -17,4 +17,6 @@ pub struct Opt {
 pub enum OptCommand {
     /// Generate a new config file.
     Init,
+    /// Create or upgrade the database schemas.
+    Migrate,
 }
@@ -4,8 +4,11 @@ use std::{
 };
 
 use anyhow::{anyhow, bail, Result};
-use azerust_game::realms::RealmId;
-use azerust_protocol::{world::OpCode, Addon, AuthSession, ClientPacket};
+use azerust_game::{characters::AccountDataType, realms::RealmId};
+use azerust_protocol::{
+    world::{ChatType, OpCode},
+    Addon, AuthSession, ClientPacket,
+};
 use bincode::Options;
 use flate2::read::ZlibDecoder;
 use tokio::io::{AsyncRead, AsyncReadExt};
@@ -13,6 +16,9 @@ use tracing::trace;
 
 use crate::{world::Session, wow_bincode::wow_bincode};
 
+/// The largest account data the client sends, once decompressed.
+const MAX_ACCOUNT_DATA: usize = 0xFFFF;
+
 /// Reads one or more packets from a frame in the stream
 pub async fn read_packets<R: AsyncRead + Unpin>(
     reader: &mut R,
@@ -25,27 +31,36 @@ pub async fn read_packets<R: AsyncRead + Unpin>(
         bail!("connection closed");
     }
 
-    let mut buffer = &buffer[..read_len];
+    let mut buffer = buffer[..read_len].to_vec();
+    let mut start = 0;
     let mut packets = Vec::new();
 
-    while !buffer.is_empty() {
+    while start < buffer.len() {
+        fill(reader, &mut buffer, start + 6).await?;
         let header = match session {
             Some(s) => {
-                let mut x: [u8; 6] = buffer[..6].try_into().expect("correct len");
+                let mut x: [u8; 6] = buffer[start..start + 6].try_into().expect("correct len");
                 s.decrypt_headers(&mut x).await;
                 x
             }
-            None => buffer[..6].try_into().expect("correct len"),
+            None => buffer[start..start + 6].try_into().expect("correct len"),
         };
 
         // the op_code is u32 little endian from the client, so just pad it out
         let (size, op_code, _): (u16, u16, u16) = wow_bincode().deserialize(&header[..])?;
         let size = size.swap_bytes() as usize; // size is big endian
+        if size < 4 {
+            bail!("packet too short: {size} bytes");
+        }
         let code = OpCode::try_from(op_code)?;
-        let packet = &buffer[6..size + 2]; // total packet length is size + opcode
+
+        // total packet length is size + opcode, which can be more than one read
+        let end = start + size + 2;
+        fill(reader, &mut buffer, end).await?;
+        let packet = &buffer[start + header.len()..end];
 
         // move buffer along for next read
-        buffer = &buffer[header.len() + packet.len()..];
+        start = end;
 
         trace!(
             "read {:02X?} {:02X?} for code {:?}",
@@ -60,6 +75,20 @@ pub async fn read_packets<R: AsyncRead + Unpin>(
     Ok(packets)
 }
 
+/// Reads from the stream until the buffer holds at least `len` bytes.
+async fn fill<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    buffer: &mut Vec<u8>,
+    len: usize,
+) -> Result<()> {
+    if buffer.len() < len {
+        let read = buffer.len();
+        buffer.resize(len, 0);
+        reader.read_exact(&mut buffer[read..]).await?;
+    }
+    Ok(())
+}
+
 fn read_packet(code: OpCode, bytes: &[u8]) -> Result<ClientPacket> {
     match code {
         OpCode::CmsgAuthSession => {
@@ -147,6 +176,48 @@ fn read_packet(code: OpCode, bytes: &[u8]) -> Result<ClientPacket> {
             Ok(ClientPacket::Ping { latency, seq })
         }
         OpCode::CmsgReadyForAccountDataTimes => Ok(ClientPacket::ReadyForAccountDataTimes),
+        OpCode::CmsgRequestAccountData => {
+            let kind: u32 = wow_bincode().deserialize(bytes)?;
+            Ok(ClientPacket::RequestAccountData(AccountDataType::try_from(
+                u8::try_from(kind)?,
+            )?))
+        }
+        OpCode::CmsgUpdateAccountData => {
+            let (kind, time, size): (u32, u32, u32) = wow_bincode().deserialize(bytes)?;
+            let kind = AccountDataType::try_from(u8::try_from(kind)?)?;
+            let size = size as usize;
+            if size > MAX_ACCOUNT_DATA {
+                bail!("account data too large: {size} bytes");
+            }
+
+            // the client clears data by sending it with no size
+            let data = if size == 0 {
+                vec![]
+            } else {
+                use std::io::Read;
+                let compressed = bytes
+                    .get(12..)
+                    .ok_or_else(|| anyhow!("account data too short"))?;
+                let mut data = Vec::with_capacity(size);
+                ZlibDecoder::new(compressed)
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut data)?;
+                if data.len() != size {
+                    bail!(
+                        "account data not correctly decompressed, expected length {size} got {}",
+                        data.len()
+                    )
+                }
+                data
+            };
+
+            Ok(ClientPacket::UpdateAccountData { kind, time, data })
+        }
+        OpCode::CmsgTutorialFlag => Ok(ClientPacket::TutorialFlag(
+            wow_bincode().deserialize(bytes)?,
+        )),
+        OpCode::CmsgTutorialClear => Ok(ClientPacket::TutorialClear),
+        OpCode::CmsgTutorialReset => Ok(ClientPacket::TutorialReset),
         OpCode::CmsgCharEnum => Ok(ClientPacket::CharEnum),
         // todo(arlyon): read this from the packet
         OpCode::CmsgRealmSplit => Ok(ClientPacket::RealmSplit { realm: 1 }),
@@ -182,14 +253,67 @@ fn read_packet(code: OpCode, bytes: &[u8]) -> Result<ClientPacket> {
         OpCode::CmsgCharDelete => Ok(ClientPacket::CharacterDelete(
             wow_bincode().deserialize(bytes)?,
         )),
+        OpCode::CmsgCharRename => {
+            let (id, name) = wow_bincode().deserialize(bytes)?;
+            Ok(ClientPacket::CharacterRename { id, name })
+        }
+        OpCode::CmsgCharCustomize => {
+            let (id, name, gender, skin_color, hair_color, hair_style, facial_style, face) =
+                wow_bincode().deserialize(bytes)?;
+            Ok(ClientPacket::CharacterCustomize {
+                id,
+                name,
+                gender,
+                skin_color,
+                face,
+                hair_style,
+                hair_color,
+                facial_style,
+            })
+        }
+        OpCode::CmsgCharFactionChange | OpCode::CmsgCharRaceChange => {
+            let (id, name, gender, skin_color, hair_color, hair_style, facial_style, face, race) =
+                wow_bincode().deserialize(bytes)?;
+            Ok(ClientPacket::CharacterRaceChange {
+                id,
+                name,
+                race,
+                gender,
+                skin_color,
+                face,
+                hair_style,
+                hair_color,
+                facial_style,
+                faction: code == OpCode::CmsgCharFactionChange,
+            })
+        }
+        OpCode::CmsgMessagechat => {
+            let (kind, language): (u32, u32) = wow_bincode().deserialize(bytes)?;
+            let kind = ChatType::try_from(u8::try_from(kind)?)?;
+            let rest = bytes
+                .get(8..)
+                .ok_or_else(|| anyhow!("chat message too short"))?;
+            let (target, message) = match kind {
+                ChatType::Whisper | ChatType::Channel => {
+                    let (target, message) = wow_bincode().deserialize(rest)?;
+                    (Some(target), message)
+                }
+                _ => (None, wow_bincode().deserialize(rest)?),
+            };
+
+            Ok(ClientPacket::MessageChat {
+                kind,
+                language,
+                target,
+                message,
+            })
+        }
 
         OpCode::CmsgSetActiveVoiceChannel => todo!(),
         OpCode::CmsgNameQuery => todo!(),
         OpCode::CmsgPlayedTime => todo!(),
         OpCode::CmsgQueryTime => todo!(),
         OpCode::CmsgZoneupdate => todo!(),
-        OpCode::CmsgRequestAccountData => todo!(),
-        OpCode::CmsgUpdateAccountData => todo!(),
         OpCode::CmsgSetActionbarToggles => todo!(),
         OpCode::CmsgWorldStateUiTimerUpdate => todo!(),
 
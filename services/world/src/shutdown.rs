@@ -0,0 +1,163 @@
+//! shutdown
+//!
+//! Coordinates a graceful shutdown of the world server. A shutdown
+//! can be requested with a delay, during which players are warned,
+//! and cancelled at any point before the delay expires.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use tokio::{signal::ctrl_c, sync::watch};
+
+/// The points (in seconds before shutdown) at which players are warned.
+const WARNINGS: [u64; 16] = [
+    3600, 1800, 900, 600, 300, 180, 120, 60, 30, 15, 10, 5, 4, 3, 2, 1,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownState {
+    Running,
+    /// A shutdown has been requested for the given time.
+    Pending(Instant),
+    /// The server is shutting down and no longer accepting clients.
+    Stopping,
+}
+
+/// Shared handle used to request, cancel and observe a shutdown.
+#[derive(Debug)]
+pub struct Shutdown {
+    sender: watch::Sender<ShutdownState>,
+    /// kept so that the channel never closes
+    receiver: watch::Receiver<ShutdownState>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, receiver) = watch::channel(ShutdownState::Running);
+        Self { sender, receiver }
+    }
+
+    pub fn state(&self) -> ShutdownState {
+        *self.receiver.borrow()
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<ShutdownState> {
+        self.receiver.clone()
+    }
+
+    /// Request that the server shuts down after the given delay. If a
+    /// shutdown is already pending, it is rescheduled.
+    pub fn request(&self, delay: Duration) -> Result<()> {
+        if self.is_stopping() {
+            bail!("server is already shutting down");
+        }
+        self.sender
+            .send(ShutdownState::Pending(Instant::now() + delay))?;
+        Ok(())
+    }
+
+    /// Cancel a pending shutdown, returning whether there was one.
+    pub fn cancel(&self) -> Result<bool> {
+        if let ShutdownState::Pending(_) = self.state() {
+            self.sender.send(ShutdownState::Running)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Mark the server as stopping. This cannot be undone.
+    pub fn stop(&self) -> Result<()> {
+        self.sender.send(ShutdownState::Stopping)?;
+        Ok(())
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.state() == ShutdownState::Stopping
+    }
+
+    /// Resolves once the server has started stopping.
+    pub async fn stopped(&self) {
+        let mut state = self.subscribe();
+        loop {
+            let stopping = *state.borrow() == ShutdownState::Stopping;
+            if stopping || state.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Get the time to wait before the next warning should be sent,
+/// given the time remaining until shutdown.
+pub fn next_warning(remaining: Duration) -> Duration {
+    let secs = whole_seconds(remaining);
+    WARNINGS
+        .iter()
+        .find(|&&w| w < secs)
+        .map(|&w| remaining.saturating_sub(Duration::from_secs(w)))
+        .unwrap_or(remaining)
+}
+
+/// Format the remaining time the way it is shown in warnings.
+pub fn format_remaining(remaining: Duration) -> String {
+    let secs = whole_seconds(remaining);
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, m, s) => format!("{m}:{s:02}"),
+        (h, m, s) => format!("{h}:{m:02}:{s:02}"),
+    }
+}
+
+/// Rounds a duration up to whole seconds.
+fn whole_seconds(d: Duration) -> u64 {
+    d.as_secs() + if d.subsec_nanos() > 0 { 1 } else { 0 }
+}
+
+/// Resolves when the process is asked to stop, either with SIGINT
+/// (ctrl-c) or SIGTERM.
+pub async fn signal() -> Result<()> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            r = ctrl_c() => r?,
+            _ = terminate.recv() => {},
+        };
+    }
+
+    #[cfg(not(unix))]
+    ctrl_c().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use test_case::test_case;
+
+    use super::{format_remaining, next_warning};
+
+    #[test_case(Duration::from_secs(90), Duration::from_secs(30) ; "waits for the minute warning")]
+    #[test_case(Duration::from_millis(60_500), Duration::from_millis(500) ; "rounds up partial seconds")]
+    #[test_case(Duration::from_secs(60), Duration::from_secs(30) ; "skips the current warning")]
+    #[test_case(Duration::from_secs(1), Duration::from_secs(1) ; "waits out the last second")]
+    pub fn warning_interval(remaining: Duration, expected: Duration) {
+        assert_eq!(next_warning(remaining), expected);
+    }
+
+    #[test_case(Duration::from_secs(5), "0:05")]
+    #[test_case(Duration::from_millis(59_001), "1:00")]
+    #[test_case(Duration::from_secs(3725), "1:02:05")]
+    pub fn remaining_format(remaining: Duration, expected: &str) {
+        assert_eq!(format_remaining(remaining), expected);
+    }
+}
@@ -0,0 +1,131 @@
+//! The addon info sent to clients once they are authenticated, telling
+//! them how to check their addons' signatures and which are banned.
+
+use azerust_protocol::{Addon, BannedAddon};
+use crypto::{digest::Digest, md5::Md5};
+
+/// The crc of the public key that Blizzard's own addons are signed with.
+pub const STANDARD_ADDON_CRC: u32 = 0x4C1C776D;
+
+/// The public key that Blizzard's own addons are signed with, which is
+/// sent to clients that report a different crc for it.
+const ADDON_PUBLIC_KEY: [u8; 256] = [
+    0xC3, 0x5B, 0x50, 0x84, 0xB9, 0x3E, 0x32, 0x42, 0x8C, 0xD0, 0xC7, 0x48, 0xFA, 0x0E, 0x5D, 0x54,
+    0x5A, 0xA3, 0x0E, 0x14, 0xBA, 0x9E, 0x0D, 0xB9, 0x5D, 0x8B, 0xEE, 0xB6, 0x84, 0x93, 0x45, 0x75,
+    0xFF, 0x31, 0xFE, 0x2F, 0x64, 0x3F, 0x3D, 0x6D, 0x07, 0xD9, 0x44, 0x9B, 0x40, 0x85, 0x59, 0x34,
+    0x4E, 0x10, 0xE1, 0xE7, 0x43, 0x69, 0xEF, 0x7C, 0x16, 0xFC, 0xB4, 0xED, 0x1B, 0x95, 0x28, 0xA8,
+    0x23, 0x76, 0x51, 0x31, 0x57, 0x30, 0x2B, 0x79, 0x08, 0x50, 0x10, 0x1C, 0x4A, 0x1A, 0x2C, 0xC8,
+    0x8B, 0x8F, 0x05, 0x2D, 0x22, 0x3D, 0xDB, 0x5A, 0x24, 0x7A, 0x0F, 0x13, 0x50, 0x37, 0x8F, 0x5A,
+    0xCC, 0x9E, 0x04, 0x44, 0x0E, 0x87, 0x01, 0xD4, 0xA3, 0x15, 0x94, 0x16, 0x34, 0xC6, 0xC2, 0xC3,
+    0xFB, 0x49, 0xFE, 0xE1, 0xF9, 0xDA, 0x8C, 0x50, 0x3C, 0xBE, 0x2C, 0xBB, 0x57, 0xED, 0x46, 0xB9,
+    0xAD, 0x8B, 0xC6, 0xDF, 0x0E, 0xD6, 0x0F, 0xBE, 0x80, 0xB3, 0x8B, 0x1E, 0x77, 0xCF, 0xAD, 0x22,
+    0xCF, 0xB7, 0x4B, 0xCF, 0xFB, 0xF0, 0x6B, 0x11, 0x45, 0x2D, 0x7A, 0x81, 0x18, 0xF2, 0x92, 0x7E,
+    0x98, 0x56, 0x5D, 0x5E, 0x69, 0x72, 0x0A, 0x0D, 0x03, 0x0A, 0x85, 0xA2, 0x85, 0x9C, 0xCB, 0xFB,
+    0x56, 0x6E, 0x8F, 0x44, 0xBB, 0x8F, 0x02, 0x22, 0x68, 0x63, 0x97, 0xBC, 0x85, 0xBA, 0xA8, 0xF7,
+    0xB5, 0x40, 0x68, 0x3C, 0x77, 0x86, 0x6F, 0x4B, 0xD7, 0x88, 0xCA, 0x8A, 0xD7, 0xCE, 0x36, 0xF0,
+    0x45, 0x6E, 0xD5, 0x64, 0x79, 0x0F, 0x17, 0xFC, 0x64, 0xDD, 0x10, 0x6F, 0xF3, 0xF5, 0xE0, 0xA6,
+    0xC3, 0xFB, 0x1B, 0x8C, 0x29, 0xEF, 0x8E, 0xE5, 0x34, 0xCB, 0xD1, 0x2A, 0xCE, 0x79, 0xC3, 0x9A,
+    0x0D, 0x36, 0xEA, 0x01, 0xE0, 0xAA, 0x91, 0x20, 0x54, 0xF0, 0x72, 0xD8, 0x1E, 0xC7, 0x89, 0xD2,
+];
+
+/// The addon is enabled.
+const ADDON_STATE_ENABLED: u8 = 2;
+
+/// The body of an addon info packet for the addons a client reported,
+/// followed by the addons that are banned.
+pub fn addon_info(addons: &[Addon], banned: &[BannedAddon]) -> Vec<u8> {
+    let mut body = vec![];
+    for addon in addons {
+        body.extend([ADDON_STATE_ENABLED, u8::from(addon.signature)]);
+        // the client checks the signature against the key or its crc
+        if addon.signature {
+            if addon.crc == STANDARD_ADDON_CRC {
+                body.push(0);
+            } else {
+                body.push(1);
+                body.extend(ADDON_PUBLIC_KEY);
+            }
+            body.extend(0u32.to_le_bytes());
+        }
+        // no url to fetch the addon from
+        body.push(0);
+    }
+
+    body.extend((banned.len() as u32).to_le_bytes());
+    for (id, addon) in (1u32..).zip(banned) {
+        body.extend(id.to_le_bytes());
+        body.extend(md5(&addon.name));
+        body.extend(md5(&addon.version));
+        body.extend(addon.timestamp.to_le_bytes());
+        // banned
+        body.extend(1u32.to_le_bytes());
+    }
+
+    body
+}
+
+fn md5(input: &str) -> [u8; 16] {
+    let mut hasher = Md5::new();
+    hasher.input_str(input);
+    let mut out = [0u8; 16];
+    hasher.result(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use azerust_protocol::{Addon, BannedAddon};
+    use test_case::test_case;
+
+    use super::{addon_info, ADDON_PUBLIC_KEY, STANDARD_ADDON_CRC};
+
+    #[test_case(STANDARD_ADDON_CRC, None ; "standard crc")]
+    #[test_case(0x12345678, Some(ADDON_PUBLIC_KEY) ; "different crc")]
+    fn public_key(crc: u32, key: Option<[u8; 256]>) {
+        let body = addon_info(
+            &[Addon::new("Blizzard_AuctionUI".to_string(), true, crc, 0)],
+            &[],
+        );
+
+        let mut expected = vec![2, 1, u8::from(key.is_some())];
+        expected.extend(key.into_iter().flatten());
+        expected.extend([0, 0, 0, 0, 0]);
+        expected.extend(0u32.to_le_bytes());
+        assert_eq!(body, expected);
+    }
+
+    #[test_case(STANDARD_ADDON_CRC ; "standard crc")]
+    #[test_case(0x12345678 ; "different crc")]
+    fn unsigned(crc: u32) {
+        let body = addon_info(&[Addon::new("MyAddon".to_string(), false, crc, 0)], &[]);
+
+        // no key or crc check, no url and no banned addons
+        let mut expected = vec![2, 0, 0];
+        expected.extend(0u32.to_le_bytes());
+        assert_eq!(body, expected);
+    }
+
+    #[test]
+    fn banned_addons() {
+        let body = addon_info(
+            &[],
+            &[BannedAddon {
+                name: "Bot".to_string(),
+                version: String::new(),
+                timestamp: 1234,
+            }],
+        );
+
+        assert_eq!(body.len(), 4 + 4 + 16 + 16 + 4 + 4);
+        assert_eq!(body[..8], [1, 0, 0, 0, 1, 0, 0, 0]);
+        // the md5 of an empty version
+        assert_eq!(
+            body[24..40],
+            [
+                0xD4, 0x1D, 0x8C, 0xD9, 0x8F, 0x00, 0xB2, 0x04, 0xE9, 0x80, 0x09, 0x98, 0xEC, 0xF8,
+                0x42, 0x7E
+            ]
+        );
+        assert_eq!(body[40..], [0xD2, 0x04, 0, 0, 1, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,214 @@
+//! The rules a new character has to follow, checked before it is created.
+
+use azerust_protocol::world::ResponseCode;
+
+/// The longest name the client allows.
+pub const MAX_NAME_LENGTH: usize = 12;
+
+const DEATH_KNIGHT: u8 = 6;
+const BLOOD_ELF: u8 = 10;
+const DRAENEI: u8 = 11;
+
+/// The race and class combinations available in 3.3.5.
+const RACE_CLASSES: [(u8, &[u8]); 10] = [
+    (1, &[1, 2, 4, 5, 6, 8, 9]),  // human
+    (2, &[1, 3, 4, 6, 7, 9]),     // orc
+    (3, &[1, 2, 3, 4, 5, 6]),     // dwarf
+    (4, &[1, 3, 4, 5, 6, 11]),    // night elf
+    (5, &[1, 4, 5, 6, 8, 9]),     // undead
+    (6, &[1, 3, 6, 7, 11]),       // tauren
+    (7, &[1, 4, 6, 8, 9]),        // gnome
+    (8, &[1, 3, 4, 5, 6, 7, 8]),  // troll
+    (10, &[2, 3, 4, 5, 6, 8, 9]), // blood elf
+    (11, &[1, 2, 3, 5, 6, 7, 8]), // draenei
+];
+
+/// The number of options the client offers for each customisation,
+/// for the race with the most of them.
+const SKIN_COLORS: u8 = 20;
+const FACES: u8 = 16;
+const HAIR_STYLES: u8 = 24;
+const HAIR_COLORS: u8 = 16;
+const FACIAL_STYLES: u8 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Faction {
+    Alliance,
+    Horde,
+}
+
+impl Faction {
+    pub fn of(race: u8) -> Option<Self> {
+        match race {
+            1 | 3 | 4 | 7 | 11 => Some(Faction::Alliance),
+            2 | 5 | 6 | 8 | 10 => Some(Faction::Horde),
+            _ => None,
+        }
+    }
+}
+
+/// The limits on character creation for a realm.
+#[derive(Debug, Clone)]
+pub struct CharacterRules {
+    pub characters_per_realm: usize,
+    /// The number of characters an account may have across every realm
+    pub characters_per_account: usize,
+    /// The level an account needs a character at to create a death knight
+    pub death_knight_level: u8,
+    pub death_knights_per_realm: usize,
+    /// Names that nobody may take, compared case insensitively
+    pub reserved_names: Vec<String>,
+    /// Words that may not appear anywhere in a name
+    pub profane_names: Vec<String>,
+}
+
+impl Default for CharacterRules {
+    fn default() -> Self {
+        Self {
+            characters_per_realm: 10,
+            characters_per_account: 50,
+            death_knight_level: 55,
+            death_knights_per_realm: 1,
+            reserved_names: vec![],
+            profane_names: vec![],
+        }
+    }
+}
+
+impl CharacterRules {
+    /// Check a name, which must be a single capitalised word.
+    pub fn check_name(&self, name: &str) -> Result<(), ResponseCode> {
+        let length = name.chars().count();
+        if length == 0 {
+            return Err(ResponseCode::CharNameNoName);
+        } else if length < 2 {
+            return Err(ResponseCode::CharNameTooShort);
+        } else if length > MAX_NAME_LENGTH {
+            return Err(ResponseCode::CharNameTooLong);
+        }
+
+        let (mut latin, mut cyrillic) = (false, false);
+        for c in name.chars() {
+            match c as u32 {
+                _ if !c.is_alphabetic() => return Err(ResponseCode::CharNameInvalidCharacter),
+                0..=0x24F => latin = true,
+                0x400..=0x4FF => cyrillic = true,
+                _ => return Err(ResponseCode::CharNameInvalidCharacter),
+            }
+        }
+        if latin && cyrillic {
+            return Err(ResponseCode::CharNameMixedLanguages);
+        }
+
+        let lower = name.to_lowercase();
+        let chars: Vec<char> = lower.chars().collect();
+        if chars.windows(3).any(|w| w[0] == w[1] && w[1] == w[2]) {
+            return Err(ResponseCode::CharNameThreeConsecutive);
+        }
+
+        let mut proper: String = chars[0].to_uppercase().collect();
+        proper.extend(&chars[1..]);
+        if proper != name {
+            return Err(ResponseCode::CharNameFailure);
+        }
+
+        if self
+            .reserved_names
+            .iter()
+            .any(|r| r.to_lowercase() == lower)
+        {
+            return Err(ResponseCode::CharNameReserved);
+        }
+        if self
+            .profane_names
+            .iter()
+            .any(|p| lower.contains(&p.to_lowercase()))
+        {
+            return Err(ResponseCode::CharNameProfane);
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that a race and class can be played together, and that the
+/// account has the expansion they come from.
+pub fn check_race_class(race: u8, class: u8, expansion: u8) -> Result<(), ResponseCode> {
+    match RACE_CLASSES.iter().find(|(r, _)| *r == race) {
+        Some((_, classes)) if classes.contains(&class) => {}
+        _ => return Err(ResponseCode::CharCreateRestrictedRaceclass),
+    }
+
+    if matches!(race, BLOOD_ELF | DRAENEI) && expansion < 1 {
+        Err(ResponseCode::CharCreateExpansion)
+    } else if class == DEATH_KNIGHT && expansion < 2 {
+        Err(ResponseCode::CharCreateExpansionClass)
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that the appearance is one the client could have picked.
+pub fn check_appearance(
+    gender: u8,
+    skin_color: u8,
+    face: u8,
+    hair_style: u8,
+    hair_color: u8,
+    facial_style: u8,
+) -> Result<(), ResponseCode> {
+    if gender > 1
+        || skin_color >= SKIN_COLORS
+        || face >= FACES
+        || hair_style >= HAIR_STYLES
+        || hair_color >= HAIR_COLORS
+        || facial_style >= FACIAL_STYLES
+    {
+        Err(ResponseCode::CharCreateError)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn is_death_knight(class: u8) -> bool {
+    class == DEATH_KNIGHT
+}
+
+#[cfg(test)]
+mod test {
+    use azerust_protocol::world::ResponseCode;
+    use test_case::test_case;
+
+    use super::{check_race_class, CharacterRules};
+
+    #[test_case("Thrall", Ok(()) ; "valid")]
+    #[test_case("", Err(ResponseCode::CharNameNoName) ; "empty")]
+    #[test_case("T", Err(ResponseCode::CharNameTooShort) ; "too short")]
+    #[test_case("Thrallthrallthrall", Err(ResponseCode::CharNameTooLong) ; "too long")]
+    #[test_case("Thr4ll", Err(ResponseCode::CharNameInvalidCharacter) ; "digit")]
+    #[test_case("Thrall Jr", Err(ResponseCode::CharNameInvalidCharacter) ; "space")]
+    #[test_case("Трallл", Err(ResponseCode::CharNameMixedLanguages) ; "mixed")]
+    #[test_case("Тралл", Ok(()) ; "cyrillic")]
+    #[test_case("Thraaall", Err(ResponseCode::CharNameThreeConsecutive) ; "three consecutive")]
+    #[test_case("thrall", Err(ResponseCode::CharNameFailure) ; "lowercase")]
+    #[test_case("ThRall", Err(ResponseCode::CharNameFailure) ; "mixed case")]
+    #[test_case("Arthas", Err(ResponseCode::CharNameReserved) ; "reserved")]
+    #[test_case("Grumpyork", Err(ResponseCode::CharNameProfane) ; "profane")]
+    pub fn check_name(name: &str, expected: Result<(), ResponseCode>) {
+        let rules = CharacterRules {
+            reserved_names: vec!["arthas".to_string()],
+            profane_names: vec!["GRUMPY".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(rules.check_name(name), expected);
+    }
+
+    #[test_case(2, 1, 2, Ok(()) ; "orc warrior")]
+    #[test_case(2, 2, 2, Err(ResponseCode::CharCreateRestrictedRaceclass) ; "orc paladin")]
+    #[test_case(9, 1, 2, Err(ResponseCode::CharCreateRestrictedRaceclass) ; "goblin")]
+    #[test_case(10, 8, 0, Err(ResponseCode::CharCreateExpansion) ; "blood elf on classic")]
+    #[test_case(1, 6, 1, Err(ResponseCode::CharCreateExpansionClass) ; "death knight on tbc")]
+    pub fn race_class(race: u8, class: u8, expansion: u8, expected: Result<(), ResponseCode>) {
+        assert_eq!(check_race_class(race, class, expansion), expected);
+    }
+}
@@ -0,0 +1,72 @@
+//! The rules for whether a deleted character is kept so that it can
+//! be restored, and for how long.
+
+use std::time::Duration;
+
+use azerust_game::characters::Character;
+
+use super::create::is_death_knight;
+
+/// How characters are deleted on a realm.
+#[derive(Debug, Clone)]
+pub struct DeleteRules {
+    /// Whether deleted characters are kept so that they can be restored
+    pub soft: bool,
+    /// Characters below this level are deleted outright
+    pub min_level: u8,
+    /// Death knights below this level are deleted outright
+    pub death_knight_min_level: u8,
+    /// How long deleted characters are kept before they are purged,
+    /// or forever if `None`
+    pub keep: Option<Duration>,
+}
+
+impl Default for DeleteRules {
+    fn default() -> Self {
+        Self {
+            soft: false,
+            min_level: 0,
+            death_knight_min_level: 0,
+            keep: Some(Duration::from_secs(60 * 60 * 24 * 30)),
+        }
+    }
+}
+
+impl DeleteRules {
+    /// Whether a character should be kept when it is deleted.
+    pub fn is_soft(&self, character: &Character) -> bool {
+        let min_level = if is_death_knight(character.class) {
+            self.death_knight_min_level
+        } else {
+            self.min_level
+        };
+        self.soft && character.level >= min_level
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use azerust_game::accounts::AccountId;
+    use azerust_memory::fixtures;
+    use test_case::test_case;
+
+    use super::DeleteRules;
+
+    #[test_case(false, 1, 7, false ; "hard deletes")]
+    #[test_case(true, 1, 7, false ; "below the min level")]
+    #[test_case(true, 10, 7, true ; "at the min level")]
+    #[test_case(true, 55, 6, false ; "death knight below the min level")]
+    #[test_case(true, 60, 6, true ; "death knight at the min level")]
+    fn is_soft(soft: bool, level: u8, class: u8, expected: bool) {
+        let rules = DeleteRules {
+            soft,
+            min_level: 10,
+            death_knight_min_level: 60,
+            keep: None,
+        };
+        let mut character = fixtures::character(1, AccountId(1), "Thrall");
+        character.level = level;
+        character.class = class;
+        assert_eq!(rules.is_soft(&character), expected);
+    }
+}
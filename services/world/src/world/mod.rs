@@ -1,5 +1,10 @@
+mod addons;
+mod create;
+mod delete;
 mod session;
 mod world;
 
+pub use create::CharacterRules;
+pub use delete::DeleteRules;
 pub use session::Session;
 pub use world::World;
@@ -8,13 +8,17 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use azerust_game::characters::Character;
+use azerust_game::{
+    characters::{AtLoginFlags, Character},
+    EntityType, WowId,
+};
 use azerust_protocol::{
     header_crypto::HeaderCrypto,
     world::{OpCode, ResponseCode},
-    Addon, ClientPacket, ClientVersion, ServerPacket,
+    Addon, BannedAddon, ClientPacket, ClientVersion, ServerPacket,
 };
 use bincode::Options;
+use flate2::{write::ZlibEncoder, Compression};
 use tokio::{
     io::AsyncWriteExt,
     net::tcp::OwnedWriteHalf,
@@ -24,7 +28,7 @@ use tracing::trace;
 
 use crate::{
     client::{Client, ClientId},
-    world::world::GLOBAL_CACHE_MASK,
+    world::addons::addon_info,
     wow_bincode::wow_bincode,
 };
 
@@ -39,6 +43,7 @@ pub struct Session {
     latency: AtomicU32,
     timeout: Mutex<Instant>,
     addons: Vec<Addon>,
+    banned_addons: Vec<BannedAddon>,
 
     character: Arc<RwLock<Option<Character>>>,
 }
@@ -50,6 +55,8 @@ impl Session {
         session_key: [u8; 40],
         sender: Sender<(ClientId, ClientPacket)>,
         addons: Vec<Addon>,
+        banned_addons: Vec<BannedAddon>,
+        tutorials: [u32; 8],
     ) -> Result<Self, (anyhow::Error, OwnedWriteHalf)> {
         let client_id = client.read().await.id;
         let x = Self {
@@ -59,11 +66,12 @@ impl Session {
             encryption: Mutex::new(HeaderCrypto::new(session_key)),
             sender,
             addons,
+            banned_addons,
             latency: AtomicU32::new(0),
             timeout: Mutex::new(Instant::now()),
             character: Arc::new(RwLock::new(None)),
         };
-        match x.finalize().await {
+        match x.finalize(tutorials).await {
             Ok(_) => Ok(x),
             Err(e) => Err((e, x.stream.into_inner())),
         }
@@ -79,6 +87,17 @@ impl Session {
         Ok(())
     }
 
+    /// The character that is currently logged in, if any.
+    pub async fn character(&self) -> Option<Character> {
+        self.character.read().await.clone()
+    }
+
+    /// Flush and close the connection to the client.
+    pub async fn close(&self) -> Result<()> {
+        self.stream.lock().await.shutdown().await?;
+        Ok(())
+    }
+
     pub async fn reset_timeout(&self) -> Result<()> {
         // todo(arlyon): different timeouts for in game vs character screen
         let mut x = self.timeout.lock().await;
@@ -111,16 +130,9 @@ impl Session {
                 )
                 .await?;
             }
-            ServerPacket::AddonInfo(addons) => {
-                let addon_data = addons.iter().flat_map(|a| {
-                    let diff_pub: u8 = if a.crc != 0x4C1C776D { 1 } else { 0 };
-                    [2u8, 1u8, diff_pub, 0u8, 0u8, 0u8, 0u8, 0u8]
-                });
-                self.write_packet(
-                    OpCode::SmsgAddonInfo,
-                    &addon_data.chain(iter::once(0)).collect::<Vec<_>>(),
-                )
-                .await?;
+            ServerPacket::AddonInfo { addons, banned } => {
+                self.write_packet(OpCode::SmsgAddonInfo, &addon_info(&addons, &banned))
+                    .await?;
             }
             ServerPacket::ClientCacheVersion(version) => {
                 self.write_packet(
@@ -129,10 +141,10 @@ impl Session {
                 )
                 .await?;
             }
-            ServerPacket::TutorialData => {
+            ServerPacket::TutorialData(tutorials) => {
                 self.write_packet(
                     OpCode::SmsgTutorialFlags,
-                    &wow_bincode().serialize(&[0u32; 8])?,
+                    &wow_bincode().serialize(&tutorials)?,
                 )
                 .await?;
             }
@@ -144,13 +156,18 @@ impl Session {
                 let length = iter::once(characters.len() as u8);
                 trace!(
                     "sending characters {:?}",
-                    characters.iter().map(|(c, _)| &c.name).collect::<Vec<_>>()
+                    characters
+                        .iter()
+                        .map(|l| &l.character.name)
+                        .collect::<Vec<_>>()
                 );
-                let char_data = characters.into_iter().flat_map(|(c, items)| {
+                let char_data = characters.into_iter().flat_map(|l| {
+                    let c = l.character;
+                    let pet = c.pet.unwrap_or_default();
                     wow_bincode()
                         .serialize(&(
                             c.id,
-                            c.name,
+                            &c.name,
                             c.race,
                             c.class,
                             [
@@ -162,21 +179,14 @@ impl Session {
                                 c.facial_style,
                             ],
                             c.level,
-                            [
-                                c.zone as u32,
-                                c.map as u32,
-                                c.position_x as u32,
-                                c.position_y as u32,
-                                c.position_z as u32,
-                            ],
-                            0u32, // guild
-                            0u32, // flags
-                            0u32,
-                            0u8,  // todo(arlyon): first login
-                            0u32, //
-                            0u32, // pet data
-                            0u32, //
-                            items,
+                            (c.zone as u32, c.map as u32),
+                            [c.position_x, c.position_y, c.position_z],
+                            c.guild,
+                            c.flags(l.locked).bits(),
+                            c.customize_flags().bits(),
+                            c.at_login.contains(AtLoginFlags::FirstLogin) as u8,
+                            (pet.display, pet.level as u32, l.pet_family as u32),
+                            l.items,
                         ))
                         .expect("data is correct")
                 });
@@ -187,25 +197,50 @@ impl Session {
                 )
                 .await?;
             }
-            ServerPacket::AccountDataTimes(account_data) => {
+            ServerPacket::AccountDataTimes { mask, data } => {
                 let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() as u32;
 
-                let mut buffer = [0u8; 4 + 1 + 4 + 4 * 3];
-                wow_bincode().serialize_into(
-                    &mut buffer[..],
-                    &(
-                        now,
-                        0u8,
-                        GLOBAL_CACHE_MASK,
-                        account_data.config.global.map(|c| c.time).unwrap_or(0),
-                        account_data.bindings.global.map(|c| c.time).unwrap_or(0),
-                        account_data.macros.global.map(|c| c.time).unwrap_or(0),
-                    ),
-                )?;
+                // the time of each type in the mask, in order
+                let times = data
+                    .items()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(kind, _)| mask & (1 << kind) != 0)
+                    .map(|(_, cache)| cache.map(|c| c.time).unwrap_or(0))
+                    .collect::<Vec<u32>>();
+
+                let mut buffer = wow_bincode().serialize(&(now, 0u8, mask))?;
+                for time in times {
+                    buffer.extend(wow_bincode().serialize(&time)?);
+                }
 
                 self.write_packet(OpCode::SmsgAccountDataTimes, &buffer)
                     .await?;
             }
+            ServerPacket::UpdateAccountData { id, kind, data } => {
+                use std::io::Write;
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(&data.data)?;
+                let compressed = encoder.finish()?;
+
+                let mut buffer = wow_bincode().serialize(&(
+                    id.unwrap_or_else(|| WowId::new(EntityType::Player, 0, 0)),
+                    u32::from(u8::from(kind)),
+                    data.time,
+                    data.data.len() as u32,
+                ))?;
+                buffer.extend(compressed);
+
+                self.write_packet(OpCode::SmsgUpdateAccountData, &buffer)
+                    .await?;
+            }
+            ServerPacket::UpdateAccountDataComplete(kind) => {
+                self.write_packet(
+                    OpCode::SmsgUpdateAccountDataComplete,
+                    &wow_bincode().serialize(&(u32::from(u8::from(kind)), 0u32))?,
+                )
+                .await?;
+            }
             ServerPacket::RealmSplit { realm } => {
                 self.write_packet(
                     OpCode::SmsgRealmSplit,
@@ -224,6 +259,102 @@ impl Session {
                 self.write_packet(OpCode::SmsgCharDelete, &[code as u8])
                     .await?;
             }
+            ServerPacket::CharacterRename(result) => {
+                let body = match result {
+                    Ok((id, name)) => {
+                        wow_bincode().serialize(&(ResponseCode::ResponseSuccess as u8, id, name))?
+                    }
+                    Err(code) => vec![code as u8],
+                };
+                self.write_packet(OpCode::SmsgCharRename, &body).await?;
+            }
+            ServerPacket::CharacterCustomize(result) => {
+                let body = match result {
+                    Ok(c) => wow_bincode().serialize(&(
+                        ResponseCode::ResponseSuccess as u8,
+                        c.id,
+                        &c.name,
+                        [
+                            c.gender,
+                            c.skin_color,
+                            c.face,
+                            c.hair_style,
+                            c.hair_color,
+                            c.facial_style,
+                        ],
+                    ))?,
+                    Err(code) => vec![code as u8],
+                };
+                self.write_packet(OpCode::SmsgCharCustomize, &body).await?;
+            }
+            ServerPacket::CharacterRaceChange(result) => {
+                let body = match result {
+                    Ok(c) => wow_bincode().serialize(&(
+                        ResponseCode::ResponseSuccess as u8,
+                        c.id,
+                        &c.name,
+                        [
+                            c.gender,
+                            c.skin_color,
+                            c.face,
+                            c.hair_style,
+                            c.hair_color,
+                            c.facial_style,
+                            c.race,
+                        ],
+                    ))?,
+                    Err(code) => vec![code as u8],
+                };
+                self.write_packet(OpCode::SmsgCharFactionChange, &body)
+                    .await?;
+            }
+            ServerPacket::ServerMessage(kind, message) => {
+                self.write_packet(
+                    OpCode::SmsgServerMessage,
+                    &wow_bincode().serialize(&(kind, message))?,
+                )
+                .await?;
+            }
+            ServerPacket::CharacterLoginFailed(code) => {
+                self.write_packet(OpCode::SmsgCharacterLoginFailed, &[code as u8])
+                    .await?;
+            }
+            ServerPacket::MessageChat {
+                kind,
+                language,
+                sender,
+                receiver,
+                message,
+            } => {
+                self.write_packet(
+                    OpCode::SmsgMessagechat,
+                    &wow_bincode().serialize(&(
+                        kind,
+                        language,
+                        sender,
+                        0u32, // flags
+                        receiver,
+                        message.len() as u32 + 1, // including the null terminator
+                        message,
+                        0u8, // chat tag
+                    ))?,
+                )
+                .await?;
+            }
+            ServerPacket::ChatPlayerNotFound(name) => {
+                self.write_packet(
+                    OpCode::SmsgChatPlayerNotFound,
+                    &wow_bincode().serialize(&name)?,
+                )
+                .await?;
+            }
+            ServerPacket::Notification(message) => {
+                self.write_packet(
+                    OpCode::SmsgNotification,
+                    &wow_bincode().serialize(&message)?,
+                )
+                .await?;
+            }
         };
         trace!("packet sent!");
 
@@ -234,13 +365,17 @@ impl Session {
         self.latency.store(latency, Ordering::Relaxed)
     }
 
-    pub async fn finalize(&self) -> Result<()> {
+    pub async fn finalize(&self, tutorials: [u32; 8]) -> Result<()> {
         self.send_packet(ServerPacket::AuthResponse).await?;
-        self.send_packet(ServerPacket::AddonInfo(self.addons.clone()))
-            .await?;
+        self.send_packet(ServerPacket::AddonInfo {
+            addons: self.addons.clone(),
+            banned: self.banned_addons.clone(),
+        })
+        .await?;
         self.send_packet(ServerPacket::ClientCacheVersion(0))
             .await?;
-        self.send_packet(ServerPacket::TutorialData).await
+        self.send_packet(ServerPacket::TutorialData(tutorials))
+            .await
     }
 
     async fn write_packet(&self, opcode: OpCode, bytes: &[u8]) -> Result<usize> {
@@ -257,10 +392,11 @@ impl Session {
         packet.extend_from_slice(bytes);
 
         trace!("writing!");
-        let out = self.stream.lock().await.write(&packet).await?;
+        // large packets, such as account data, may not fit in one write
+        self.stream.lock().await.write_all(&packet).await?;
 
         trace!("done");
-        Ok(out)
+        Ok(packet.len())
     }
 
     pub async fn encrypt_headers(&self, header: &mut [u8; 4]) {
@@ -6,11 +6,21 @@ use std::{
 
 use anyhow::{anyhow, Context, Result};
 use azerust_game::{
-    accounts::AccountService,
-    characters::{AccountData, CharacterCreate, CharacterService},
-    realms::{RealmId, RealmList},
+    accounts::{AccountId, AccountService},
+    characters::{
+        AccountData, AccountDataCache, AtLoginFlags, Character, CharacterCreate, CharacterId,
+        CharacterService, EquippedItem, EQUIPMENT_SLOTS,
+    },
+    guid::GuidAllocator,
+    realms::{RealmId, RealmList, RealmType},
+    world::WorldData,
+    EntityType, WowId,
+};
+use azerust_protocol::{
+    world::{ChatType, ResponseCode, ServerMessageType},
+    Addon, BannedAddon, CharacterListing, ClientPacket, Item, ServerPacket,
 };
-use azerust_protocol::{world::ResponseCode, Addon, ClientPacket, Item, ServerPacket};
+use chrono::Utc;
 use tokio::{
     join,
     net::tcp::OwnedWriteHalf,
@@ -20,18 +30,42 @@ use tokio::{
     },
     time::{interval, Interval},
 };
-use tracing::{error, trace};
+use tracing::{error, info, trace};
 
-use super::Session;
+use super::{
+    create::{check_appearance, check_race_class, is_death_knight, Faction},
+    CharacterRules, DeleteRules, Session,
+};
 use crate::client::{Client, ClientId};
 
+/// The account data types kept for the whole account.
 pub const GLOBAL_CACHE_MASK: u32 = 0x15;
+/// The account data types kept for each character.
+pub const PER_CHARACTER_CACHE_MASK: u32 = 0xEA;
+
+/// The name and appearance a character is changing to, and their
+/// race if it is changing.
+struct Customize {
+    name: String,
+    race: Option<u8>,
+    gender: u8,
+    skin_color: u8,
+    face: u8,
+    hair_style: u8,
+    hair_color: u8,
+    facial_style: u8,
+}
 
 pub struct World<A: AccountService, R: RealmList, C: CharacterService> {
     id: RealmId,
     accounts: A,
     realms: R,
     characters: C,
+    data: Arc<WorldData>,
+    guids: GuidAllocator,
+    rules: CharacterRules,
+    delete_rules: DeleteRules,
+    banned_addons: Vec<BannedAddon>,
     receiver: Mutex<Receiver<(ClientId, ClientPacket)>>,
     sender: Sender<(ClientId, ClientPacket)>,
     sessions: Arc<RwLock<HashMap<ClientId, Arc<Session>>>>,
@@ -40,13 +74,25 @@ pub struct World<A: AccountService, R: RealmList, C: CharacterService> {
 }
 
 impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
-    pub fn new(id: RealmId, accounts: A, realms: R, characters: C) -> Self {
+    pub fn new(
+        id: RealmId,
+        accounts: A,
+        realms: R,
+        characters: C,
+        data: WorldData,
+        guids: GuidAllocator,
+    ) -> Self {
         let (sender, receiver) = unbounded_channel();
         Self {
             id,
             accounts,
             realms,
             characters,
+            data: Arc::new(data),
+            guids,
+            rules: CharacterRules::default(),
+            delete_rules: DeleteRules::default(),
+            banned_addons: vec![],
             sender,
             receiver: Mutex::new(receiver),
             sessions: Default::default(),
@@ -55,6 +101,31 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
         }
     }
 
+    /// Set the rules new characters are checked against.
+    pub fn with_character_rules(self, rules: CharacterRules) -> Self {
+        Self { rules, ..self }
+    }
+
+    /// Set the rules for how characters are deleted.
+    pub fn with_delete_rules(self, delete_rules: DeleteRules) -> Self {
+        Self {
+            delete_rules,
+            ..self
+        }
+    }
+
+    /// Set the addons that clients are told to disable.
+    pub fn with_banned_addons(self, banned_addons: Vec<BannedAddon>) -> Self {
+        Self {
+            banned_addons,
+            ..self
+        }
+    }
+
+    pub fn characters(&self) -> &C {
+        &self.characters
+    }
+
     /// runs background tasks
     pub async fn timers(&self) -> Result<()> {
         let mut timers = WorldTimers::new();
@@ -74,7 +145,31 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
             }
         };
 
-        join!(ping_db, uptime);
+        let purge_deleted = async {
+            let keep = match self.delete_rules.keep.map(chrono::Duration::from_std) {
+                Some(Ok(keep)) => keep,
+                Some(Err(e)) => {
+                    error!("not purging deleted characters, invalid keep duration: {e}");
+                    return;
+                }
+                None => return,
+            };
+            loop {
+                timers.purge_deleted.tick().await;
+                let before = match Utc::now().checked_sub_signed(keep) {
+                    Some(before) => before,
+                    None => {
+                        error!("not purging deleted characters, keep duration is too long");
+                        continue;
+                    }
+                };
+                if let Err(e) = self.characters.purge_deleted(before).await {
+                    error!("error when purging deleted characters: {e}");
+                }
+            }
+        };
+
+        join!(ping_db, uptime, purge_deleted);
         // todo(arlyon): update uptime and population
         // todo(arlyon): ping database
 
@@ -116,15 +211,116 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
                 let data = match session.client.read().await.account {
                     Some(id) => self
                         .characters
-                        .account_data(id)
+                        .account_data(id, None)
                         .await
                         .map_err(|_| anyhow!("unable to get character account data"))?,
                     None => AccountData::default(),
                 };
 
                 session
-                    .send_packet(ServerPacket::AccountDataTimes(Box::new(data)))
+                    .send_packet(ServerPacket::AccountDataTimes {
+                        mask: GLOBAL_CACHE_MASK,
+                        data: Box::new(data),
+                    })
+                    .await
+            }
+            ClientPacket::RequestAccountData(kind) => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let character = if kind.is_global() {
+                    None
+                } else {
+                    session.character().await.map(|c| c.id)
+                };
+                let data = self
+                    .characters
+                    .account_data(account, character.map(CharacterId::try_from).transpose()?)
+                    .await
+                    .context("unable to get account data")?
+                    .get(kind)
+                    .cloned()
+                    .unwrap_or_default();
+
+                session
+                    .send_packet(ServerPacket::UpdateAccountData {
+                        id: character,
+                        kind,
+                        data,
+                    })
+                    .await
+            }
+            ClientPacket::UpdateAccountData { kind, time, data } => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let character = if kind.is_global() {
+                    None
+                } else {
+                    match session.character().await {
+                        Some(c) => Some(CharacterId::try_from(c.id)?),
+                        None => {
+                            trace!("ignoring {kind:?} sent before logging in");
+                            return Ok(());
+                        }
+                    }
+                };
+
+                self.characters
+                    .set_account_data(account, character, kind, AccountDataCache { time, data })
+                    .await
+                    .context("unable to save account data")?;
+                session
+                    .send_packet(ServerPacket::UpdateAccountDataComplete(kind))
+                    .await
+            }
+            ClientPacket::TutorialFlag(flag) => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let mut tutorials = self
+                    .characters
+                    .tutorials(account)
+                    .await
+                    .context("unable to get tutorials")?;
+                match tutorials.get_mut(flag as usize / 32) {
+                    Some(tutorial) => *tutorial |= 1 << (flag % 32),
+                    None => {
+                        trace!("ignoring unknown tutorial {flag}");
+                        return Ok(());
+                    }
+                }
+
+                self.characters
+                    .set_tutorials(account, tutorials)
+                    .await
+                    .context("unable to save tutorials")
+            }
+            ClientPacket::TutorialClear | ClientPacket::TutorialReset => {
+                let account = session
+                    .client
+                    .read()
                     .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let tutorials = match packet {
+                    ClientPacket::TutorialClear => [u32::MAX; 8],
+                    _ => [0; 8],
+                };
+
+                self.characters
+                    .set_tutorials(account, tutorials)
+                    .await
+                    .context("unable to save tutorials")
             }
             ClientPacket::CharEnum => {
                 let id = session
@@ -138,16 +334,40 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
                     .get_by_account(id)
                     .await
                     .map_err(|_| anyhow!("unable to get character list"))?;
-                let items = [Item {
-                    display: 0,
-                    inventory: 0,
-                    aura: 0,
-                }; 23];
-                session
-                    .send_packet(ServerPacket::CharEnum(
-                        characters.into_iter().map(|c| (c, items)).collect(),
-                    ))
-                    .await
+                self.update_character_count(id).await;
+
+                let mut list = Vec::with_capacity(characters.len());
+                for mut character in characters {
+                    let id = CharacterId::try_from(character.id)?;
+                    let equipment = self
+                        .characters
+                        .equipment(id)
+                        .await
+                        .map_err(|_| anyhow!("unable to get character equipment"))?;
+                    let locked = self
+                        .characters
+                        .character_ban(id)
+                        .await
+                        .map_err(|_| anyhow!("unable to get character ban"))?
+                        .is_some();
+
+                    // pets are only shown if we know what they are
+                    let pet_template = character
+                        .pet
+                        .and_then(|p| self.data.creature_templates.get(&p.entry));
+                    if pet_template.is_none() {
+                        character.pet = None;
+                    }
+
+                    list.push(CharacterListing {
+                        character,
+                        locked,
+                        pet_family: pet_template.map_or(0, |t| t.family as u8),
+                        items: self.displayed_items(&equipment),
+                    });
+                }
+
+                session.send_packet(ServerPacket::CharEnum(list)).await
             }
             ClientPacket::RealmSplit { realm } => {
                 session
@@ -165,38 +385,49 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
                 hair_color,
                 facial_style,
             } => {
-                let available = self.characters.name_available(&name).await.unwrap_or(false);
-                if !available {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+
+                if let Err(code) = self
+                    .check_create(account, &name, race, class)
+                    .await?
+                    .and_then(|_| {
+                        check_appearance(
+                            gender,
+                            skin_color,
+                            face,
+                            hair_style,
+                            hair_color,
+                            facial_style,
+                        )
+                    })
+                {
                     return session
-                        .send_packet(ServerPacket::CharacterCreate(
-                            ResponseCode::CharCreateNameInUse,
-                        ))
+                        .send_packet(ServerPacket::CharacterCreate(code))
                         .await;
                 }
 
-                let mut name_proper = name.to_ascii_lowercase();
-                if let Some(r) = name_proper.get_mut(0..1) {
-                    r.make_ascii_uppercase();
-                }
-
-                if let Some(x) = match &name {
-                    n if name_proper.ne(n) => Some(ResponseCode::CharNameFailure),
-                    n if n.is_empty() => Some(ResponseCode::CharNameNoName),
-                    n if n.len() < 2 => Some(ResponseCode::CharNameTooShort),
-                    _ => None,
-                } {
-                    return session.send_packet(ServerPacket::CharacterCreate(x)).await;
-                }
+                let start = match self.data.character_start(race, class, &self.guids)? {
+                    Some(start) => start,
+                    None => {
+                        error!("no start position for race {race} and class {class}");
+                        return session
+                            .send_packet(ServerPacket::CharacterCreate(
+                                ResponseCode::CharCreateError,
+                            ))
+                            .await;
+                    }
+                };
 
                 self.characters
                     .create_character(
-                        session
-                            .client
-                            .read()
-                            .await
-                            .account
-                            .ok_or_else(|| anyhow!("no account"))?,
+                        account,
                         CharacterCreate {
+                            id: self.guids.next(EntityType::Player)?,
                             name,
                             race,
                             class,
@@ -206,15 +437,21 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
                             hair_style,
                             hair_color,
                             facial_style,
-                            map: 0,               //
-                            zone: 1,              //
-                            position_x: -6240.32, // dwarf start zone
-                            position_y: 331.033,  //
-                            position_z: 382.758,  //
+                            map: start.info.map,
+                            zone: start.info.zone,
+                            position_x: start.info.position_x,
+                            position_y: start.info.position_y,
+                            position_z: start.info.position_z,
+                            orientation: start.info.orientation,
+                            items: start.items,
+                            spells: start.spells,
+                            skills: start.skills,
+                            action_buttons: start.action_buttons,
                         },
                     )
                     .await
                     .map_err(|_| anyhow!("unable to create character"))?;
+                self.update_character_count(account).await;
 
                 session
                     .send_packet(ServerPacket::CharacterCreate(
@@ -223,35 +460,596 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
                     .await
             }
             ClientPacket::PlayerLogin(id) => {
-                let character = self
+                let id = id.try_into()?;
+                if let Some(ban) = self
+                    .characters
+                    .character_ban(id)
+                    .await
+                    .context("unable to get character ban")?
+                {
+                    info!("banned character {id:?} tried to log in: {ban:?}");
+                    return session
+                        .send_packet(ServerPacket::CharacterLoginFailed(
+                            ResponseCode::CharLoginLockedByBilling,
+                        ))
+                        .await;
+                }
+
+                let mut character = self
                     .characters
-                    .get(id.try_into()?)
+                    .get(id)
                     .await
                     .context("unable to get character list")?;
-                session.login(character).await
+                // deleted characters are kept off their account
+                if session.client.read().await.account != Some(character.account) {
+                    return session
+                        .send_packet(ServerPacket::CharacterLoginFailed(
+                            ResponseCode::CharLoginNoCharacter,
+                        ))
+                        .await;
+                }
+                if character.at_login.contains(AtLoginFlags::FirstLogin) {
+                    character.at_login.remove(AtLoginFlags::FirstLogin);
+                    self.characters
+                        .set_at_login(id, character.at_login)
+                        .await
+                        .context("unable to clear first login")?;
+                }
+                let data = self
+                    .characters
+                    .account_data(character.account, Some(id))
+                    .await
+                    .context("unable to get account data")?;
+                session.login(character).await?;
+                session
+                    .send_packet(ServerPacket::AccountDataTimes {
+                        mask: PER_CHARACTER_CACHE_MASK,
+                        data: Box::new(data),
+                    })
+                    .await
+            }
+            ClientPacket::CharacterDelete(id) => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                match self
+                    .delete(account, id.try_into().context("invalid guid provided")?)
+                    .await
+                {
+                    Ok(_) => {
+                        session
+                            .send_packet(ServerPacket::CharacterDelete(
+                                ResponseCode::CharDeleteSuccess,
+                            ))
+                            .await
+                    }
+                    Err(e) => {
+                        error!("failed to delete character {id:?}: {e}");
+                        session
+                            .send_packet(ServerPacket::CharacterDelete(
+                                ResponseCode::CharDeleteFailed,
+                            ))
+                            .await
+                    }
+                }
+            }
+            ClientPacket::CharacterRename { id, name } => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let result = self.rename(account, id, name).await?;
+                session
+                    .send_packet(ServerPacket::CharacterRename(result))
+                    .await
+            }
+            ClientPacket::CharacterCustomize {
+                id,
+                name,
+                gender,
+                skin_color,
+                face,
+                hair_style,
+                hair_color,
+                facial_style,
+            } => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let result = self
+                    .customize(
+                        account,
+                        id,
+                        Customize {
+                            name,
+                            race: None,
+                            gender,
+                            skin_color,
+                            face,
+                            hair_style,
+                            hair_color,
+                            facial_style,
+                        },
+                        AtLoginFlags::Customize,
+                    )
+                    .await?;
+                session
+                    .send_packet(ServerPacket::CharacterCustomize(result.map(Box::new)))
+                    .await
             }
-            ClientPacket::CharacterDelete(id) => match self
+            ClientPacket::CharacterRaceChange {
+                id,
+                name,
+                race,
+                gender,
+                skin_color,
+                face,
+                hair_style,
+                hair_color,
+                facial_style,
+                faction,
+            } => {
+                let account = session
+                    .client
+                    .read()
+                    .await
+                    .account
+                    .ok_or_else(|| anyhow!("no account"))?;
+                let flag = if faction {
+                    AtLoginFlags::ChangeFaction
+                } else {
+                    AtLoginFlags::ChangeRace
+                };
+                let result = self
+                    .customize(
+                        account,
+                        id,
+                        Customize {
+                            name,
+                            race: Some(race),
+                            gender,
+                            skin_color,
+                            face,
+                            hair_style,
+                            hair_color,
+                            facial_style,
+                        },
+                        flag,
+                    )
+                    .await?;
+                session
+                    .send_packet(ServerPacket::CharacterRaceChange(result.map(Box::new)))
+                    .await
+            }
+            ClientPacket::MessageChat {
+                kind,
+                language,
+                target,
+                message,
+            } => self.chat(&session, kind, language, target, message).await,
+        }
+    }
+
+    /// The items shown on the character screen, looked up in the item
+    /// templates. Items without a template are left out.
+    fn displayed_items(&self, equipment: &[EquippedItem]) -> [Item; EQUIPMENT_SLOTS as usize] {
+        let mut items = [Item {
+            display: 0,
+            inventory: 0,
+            aura: 0,
+        }; EQUIPMENT_SLOTS as usize];
+        for equipped in equipment {
+            let (item, template) = match (
+                items.get_mut(usize::from(equipped.slot)),
+                self.data.item_templates.get(&equipped.entry),
+            ) {
+                (Some(item), Some(template)) => (item, template),
+                _ => continue,
+            };
+            *item = Item {
+                display: template.display_id,
+                inventory: template.inventory_type,
+                aura: self
+                    .data
+                    .enchant_auras
+                    .get(&equipped.enchant)
+                    .copied()
+                    .unwrap_or(0),
+            };
+        }
+        items
+    }
+
+    /// Checks that an account may create a character with the given
+    /// name, race and class on this realm, returning the reason if not.
+    async fn check_create(
+        &self,
+        account: AccountId,
+        name: &str,
+        race: u8,
+        class: u8,
+    ) -> Result<Result<(), ResponseCode>> {
+        if let Err(code) = self.rules.check_name(name) {
+            return Ok(Err(code));
+        }
+
+        let expansion = self
+            .accounts
+            .get(account)
+            .await
+            .context("unable to get account")?
+            .expansion;
+        if let Err(code) = check_race_class(race, class, expansion) {
+            return Ok(Err(code));
+        }
+
+        if !self
+            .characters
+            .name_available(name)
+            .await
+            .context("unable to check name")?
+        {
+            return Ok(Err(ResponseCode::CharCreateNameInUse));
+        }
+
+        if self
+            .characters
+            .count_by_account(account)
+            .await
+            .context("unable to count characters")?
+            >= self.rules.characters_per_realm
+        {
+            return Ok(Err(ResponseCode::CharCreateServerLimit));
+        }
+
+        if self
+            .realms
+            .character_count(account)
+            .await
+            .context("unable to count characters on every realm")?
+            >= self.rules.characters_per_account
+        {
+            return Ok(Err(ResponseCode::CharCreateAccountLimit));
+        }
+
+        let existing = self
+            .characters
+            .get_by_account(account)
+            .await
+            .context("unable to get character list")?;
+
+        if self.is_pvp().await
+            && existing
+                .iter()
+                .any(|c| Faction::of(c.race) != Faction::of(race))
+        {
+            return Ok(Err(ResponseCode::CharCreatePvpTeamsViolation));
+        }
+
+        if is_death_knight(class) {
+            if !existing
+                .iter()
+                .any(|c| c.level >= self.rules.death_knight_level)
+            {
+                return Ok(Err(ResponseCode::CharCreateLevelRequirement));
+            }
+            if existing.iter().filter(|c| is_death_knight(c.class)).count()
+                >= self.rules.death_knights_per_realm
+            {
+                return Ok(Err(ResponseCode::CharCreateUniqueClassLimit));
+            }
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Whether characters on this realm have to stick to one faction.
+    async fn is_pvp(&self) -> bool {
+        self.realms
+            .realms()
+            .await
+            .into_iter()
+            .find(|r| r.id == self.id)
+            .map(|r| matches!(r.realm_type, RealmType::PVP | RealmType::RPPvP))
+            .unwrap_or(false)
+    }
+
+    /// Gets a character on the account that has been allowed the
+    /// change in the at login flag, checking the name they want.
+    async fn check_change(
+        &self,
+        account: AccountId,
+        id: WowId,
+        flag: AtLoginFlags,
+        name: &str,
+    ) -> Result<Result<Character, ResponseCode>> {
+        let character = match CharacterId::try_from(id) {
+            Ok(id) => self.characters.get(id).await.ok(),
+            Err(_) => None,
+        };
+        let character = match character {
+            Some(c) if c.account == account && c.at_login.contains(flag) => c,
+            _ => return Ok(Err(ResponseCode::CharCreateError)),
+        };
+
+        if let Err(code) = self.rules.check_name(name) {
+            return Ok(Err(code));
+        }
+        if !name.eq_ignore_ascii_case(&character.name)
+            && !self
                 .characters
-                .delete_character(id.try_into().context("invalid guid provided")?)
+                .name_available(name)
                 .await
-                .context("unable to delete character")
-            {
-                Ok(_) => {
-                    session
-                        .send_packet(ServerPacket::CharacterDelete(
-                            ResponseCode::CharDeleteSuccess,
-                        ))
-                        .await
+                .context("unable to check name")?
+        {
+            return Ok(Err(ResponseCode::CharCreateNameInUse));
+        }
+
+        Ok(Ok(character))
+    }
+
+    /// Deletes one of an account's characters, keeping it so that it
+    /// can be restored if the delete rules call for it.
+    async fn delete(&self, account: AccountId, id: CharacterId) -> Result<()> {
+        let character = self
+            .characters
+            .get(id)
+            .await
+            .context("unable to get character")?;
+        if character.account != account {
+            return Err(anyhow!("character is on another account"));
+        }
+
+        if self.delete_rules.is_soft(&character) {
+            info!("soft deleting {}", character.name);
+            self.characters
+                .soft_delete_character(id)
+                .await
+                .context("unable to soft delete character")?;
+        } else {
+            info!("deleting {}", character.name);
+            self.characters
+                .delete_character(id)
+                .await
+                .context("unable to delete character")?;
+        }
+        self.update_character_count(account).await;
+
+        Ok(())
+    }
+
+    /// Record how many characters the account has on this realm, so
+    /// that the other realms can hold it to the account wide limit.
+    async fn update_character_count(&self, account: AccountId) {
+        let count = match self.characters.count_by_account(account).await {
+            Ok(count) => count.try_into().unwrap_or(u8::MAX),
+            Err(e) => {
+                error!("unable to count the characters of account {account}: {e}");
+                return;
+            }
+        };
+        if let Err(e) = self
+            .realms
+            .set_character_count(self.id, account, count)
+            .await
+        {
+            error!("unable to record the characters of account {account}: {e}");
+        }
+    }
+
+    /// Renames a character that has been flagged for it.
+    async fn rename(
+        &self,
+        account: AccountId,
+        id: WowId,
+        name: String,
+    ) -> Result<Result<(WowId, String), ResponseCode>> {
+        let mut character = match self
+            .check_change(account, id, AtLoginFlags::Rename, &name)
+            .await?
+        {
+            Ok(c) => c,
+            Err(code) => return Ok(Err(code)),
+        };
+
+        info!("renaming {} to {name}", character.name);
+        character.name = name;
+        character.at_login.remove(AtLoginFlags::Rename);
+        self.characters
+            .customize_character(&character)
+            .await
+            .context("unable to rename character")?;
+
+        Ok(Ok((character.id, character.name)))
+    }
+
+    /// Changes the name and appearance of a character that has been
+    /// flagged for it, and their race if they are changing race or
+    /// faction.
+    ///
+    /// note: a faction change only changes the race. Reputations, spells
+    /// and items are not swapped for the other faction's, and the
+    /// character stays in their guild.
+    async fn customize(
+        &self,
+        account: AccountId,
+        id: WowId,
+        customize: Customize,
+        flag: AtLoginFlags,
+    ) -> Result<Result<Character, ResponseCode>> {
+        let mut character = match self
+            .check_change(account, id, flag, &customize.name)
+            .await?
+        {
+            Ok(c) => c,
+            Err(code) => return Ok(Err(code)),
+        };
+        if let Err(code) = check_appearance(
+            customize.gender,
+            customize.skin_color,
+            customize.face,
+            customize.hair_style,
+            customize.hair_color,
+            customize.facial_style,
+        ) {
+            return Ok(Err(code));
+        }
+
+        if let Some(race) = customize.race {
+            let faction = Faction::of(race);
+            let changes_faction = faction != Faction::of(character.race);
+            if flag == AtLoginFlags::ChangeRace && changes_faction {
+                return Ok(Err(ResponseCode::CharCreateCharacterRaceOnly));
+            }
+            if flag == AtLoginFlags::ChangeFaction && !changes_faction {
+                return Ok(Err(ResponseCode::CharCreateCharacterSwapFaction));
+            }
+
+            let expansion = self
+                .accounts
+                .get(account)
+                .await
+                .context("unable to get account")?
+                .expansion;
+            if let Err(code) = check_race_class(race, character.class, expansion) {
+                return Ok(Err(code));
+            }
+
+            if changes_faction && self.is_pvp().await {
+                let others = self
+                    .characters
+                    .get_by_account(account)
+                    .await
+                    .context("unable to get character list")?;
+                if others
+                    .iter()
+                    .any(|c| c.id != character.id && Faction::of(c.race) != faction)
+                {
+                    return Ok(Err(ResponseCode::CharCreatePvpTeamsViolation));
                 }
-                Err(_) => {
-                    error!("failed to delete character: {:?}", id);
-                    session
-                        .send_packet(ServerPacket::CharacterDelete(
-                            ResponseCode::CharDeleteFailed,
-                        ))
-                        .await
+            }
+
+            character.race = race;
+        }
+
+        info!("customizing {} as {}", character.name, customize.name);
+        character.name = customize.name;
+        character.gender = customize.gender;
+        character.skin_color = customize.skin_color;
+        character.face = customize.face;
+        character.hair_style = customize.hair_style;
+        character.hair_color = customize.hair_color;
+        character.facial_style = customize.facial_style;
+        character.at_login.remove(flag);
+        self.characters
+            .customize_character(&character)
+            .await
+            .context("unable to customize character")?;
+
+        Ok(Ok(character))
+    }
+
+    /// Delivers a chat message from a session. Mutes are read from the
+    /// database on every message so that they apply immediately, no
+    /// matter where they were set from.
+    ///
+    /// note: there is no notion of distance yet, so say, yell and
+    /// emote are sent to everyone in the world.
+    async fn chat(
+        &self,
+        session: &Session,
+        kind: ChatType,
+        language: u32,
+        target: Option<String>,
+        message: String,
+    ) -> Result<()> {
+        let character = session
+            .character()
+            .await
+            .ok_or_else(|| anyhow!("not logged in"))?;
+        let account = session
+            .client
+            .read()
+            .await
+            .account
+            .ok_or_else(|| anyhow!("no account"))?;
+
+        if let Some(until) = self
+            .accounts
+            .muted_until(account)
+            .await
+            .context("unable to get mute")?
+        {
+            return session
+                .send_packet(ServerPacket::Notification(format!(
+                    "You are muted until {}.",
+                    until.format("%Y-%m-%d %H:%M UTC")
+                )))
+                .await;
+        }
+
+        match (kind, target) {
+            (ChatType::Say | ChatType::Yell | ChatType::Emote, _) => {
+                for other in self.sessions().await {
+                    if other.character().await.is_none() {
+                        continue;
+                    }
+                    let packet = ServerPacket::MessageChat {
+                        kind,
+                        language,
+                        sender: character.id,
+                        receiver: character.id,
+                        message: message.clone(),
+                    };
+                    if let Err(e) = other.send_packet(packet).await {
+                        error!(
+                            "could not send message to client {:?}: {e}",
+                            other.client_id
+                        );
+                    }
                 }
-            },
+                Ok(())
+            }
+            (ChatType::Whisper, Some(name)) => {
+                let (other, receiver) = match self.find_character(&name).await {
+                    Some(found) => found,
+                    None => {
+                        return session
+                            .send_packet(ServerPacket::ChatPlayerNotFound(name))
+                            .await
+                    }
+                };
+                other
+                    .send_packet(ServerPacket::MessageChat {
+                        kind: ChatType::Whisper,
+                        language,
+                        sender: character.id,
+                        receiver: receiver.id,
+                        message: message.clone(),
+                    })
+                    .await?;
+                session
+                    .send_packet(ServerPacket::MessageChat {
+                        kind: ChatType::WhisperInform,
+                        language,
+                        sender: receiver.id,
+                        receiver: character.id,
+                        message,
+                    })
+                    .await
+            }
+            (kind, _) => {
+                trace!("ignoring unsupported chat message {kind:?}");
+                Ok(())
+            }
         }
     }
 
@@ -260,6 +1058,87 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
         // update game time
     }
 
+    /// Sends a server message to every session in the world.
+    pub async fn announce(&self, kind: ServerMessageType, message: &str) {
+        let sessions = self.sessions().await;
+        for session in sessions {
+            if let Err(e) = session
+                .send_packet(ServerPacket::ServerMessage(kind, message.to_string()))
+                .await
+            {
+                error!(
+                    "could not send message to client {:?}: {e}",
+                    session.client_id
+                );
+            }
+        }
+    }
+
+    /// Lists every session in the world, along with its account
+    /// and the name of the character it is playing.
+    pub async fn online(&self) -> Vec<(ClientId, Option<AccountId>, Option<String>)> {
+        let sessions = self.sessions().await;
+        let mut online = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let account = session.client.read().await.account;
+            let character = session.character().await.map(|c| c.name);
+            online.push((session.client_id, account, character));
+        }
+        online
+    }
+
+    /// Disconnects the session playing the character with the given
+    /// name, returning whether one was found.
+    pub async fn kick(&self, name: &str) -> Result<bool> {
+        match self.find_character(name).await {
+            Some((session, _)) => {
+                self.disconnect(&session).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Finds the session playing the character with the given name.
+    async fn find_character(&self, name: &str) -> Option<(Arc<Session>, Character)> {
+        for session in self.sessions().await {
+            if let Some(character) = session.character().await {
+                if character.name.eq_ignore_ascii_case(name) {
+                    return Some((session, character));
+                }
+            }
+        }
+        None
+    }
+
+    /// Takes a snapshot of the sessions currently in the world.
+    async fn sessions(&self) -> Vec<Arc<Session>> {
+        self.sessions.read().await.values().cloned().collect()
+    }
+
+    /// Saves every logged in character and closes all sessions.
+    pub async fn shutdown(&self) {
+        let sessions = self.sessions().await;
+        for session in sessions {
+            if let Err(e) = self.disconnect(&session).await {
+                error!("could not disconnect client {:?}: {e}", session.client_id);
+            }
+        }
+    }
+
+    /// Removes a session from the world, saving its character
+    /// and closing the connection.
+    async fn disconnect(&self, session: &Session) -> Result<()> {
+        self.sessions.write().await.remove(&session.client_id);
+        if let Some(character) = session.character().await {
+            self.characters
+                .save_character(&character)
+                .await
+                .with_context(|| format!("could not save character {}", character.name))?;
+        }
+        session.close().await
+    }
+
     pub async fn create_session(
         &self,
         client: Arc<RwLock<Client>>,
@@ -267,8 +1146,27 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
         session_key: [u8; 40],
         addons: Vec<Addon>,
     ) -> Result<Arc<Session>, (anyhow::Error, OwnedWriteHalf)> {
+        let account = client.read().await.account;
+        let tutorials = match account {
+            Some(account) => match self.characters.tutorials(account).await {
+                Ok(tutorials) => tutorials,
+                Err(e) => return Err((anyhow!("unable to get tutorials: {e}"), writer)),
+            },
+            None => [0; 8],
+        };
+
         let session = Arc::new(
-            match Session::new(client, writer, session_key, self.sender.clone(), addons).await {
+            match Session::new(
+                client,
+                writer,
+                session_key,
+                self.sender.clone(),
+                addons,
+                self.banned_addons.clone(),
+                tutorials,
+            )
+            .await
+            {
                 Ok(s) => s,
                 Err((e, w)) => return Err((e, w)),
             },
@@ -284,6 +1182,7 @@ impl<A: AccountService, R: RealmList, C: CharacterService> World<A, R, C> {
 struct WorldTimers {
     uptime: Interval,
     ping_db: Interval,
+    purge_deleted: Interval,
 }
 
 impl WorldTimers {
@@ -291,6 +1190,7 @@ impl WorldTimers {
         Self {
             uptime: interval(Duration::from_secs(60)),
             ping_db: interval(Duration::from_secs(60 * 10)),
+            purge_deleted: interval(Duration::from_secs(60 * 60 * 24)),
         }
     }
 }
@@ -1,22 +1,27 @@
 use std::{
     collections::HashMap,
     convert::TryInto,
+    net::Ipv4Addr,
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc,
     },
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use azerust_console::{accounts, characters, CharacterCommand, Command, Console, Listener};
 use azerust_game::{
     accounts::AccountService,
     characters::CharacterService,
+    guid::GuidAllocator,
     realms::{RealmId, RealmList},
+    world::WorldData,
 };
 use azerust_protocol::{
-    world::{OpCode, ResponseCode},
-    AuthSession, ClientPacket,
+    world::{OpCode, ResponseCode, ServerMessageType},
+    AuthSession, BannedAddon, ClientPacket,
 };
 use azerust_utils::flatten;
 use bincode::Options;
@@ -24,9 +29,10 @@ use rand::Rng;
 use sha1::Digest;
 use tokio::{
     io::{AsyncRead, AsyncWriteExt},
-    net::{tcp::OwnedWriteHalf, TcpListener, UdpSocket},
+    net::{tcp::OwnedWriteHalf, TcpListener, TcpStream, UdpSocket},
+    select,
     sync::RwLock,
-    time::interval,
+    time::{interval, sleep},
     try_join,
 };
 use tokio_stream::{
@@ -38,7 +44,8 @@ use tracing::{debug, error, info, instrument, trace, warn};
 use crate::{
     client::{Client, ClientId},
     protocol::read_packets,
-    world::{Session, World},
+    shutdown::{self, Shutdown, ShutdownState},
+    world::{CharacterRules, DeleteRules, Session, World},
     wow_bincode::wow_bincode,
 };
 
@@ -47,6 +54,7 @@ pub struct WorldServer<A: AccountService, R: RealmList, C: CharacterService> {
     accounts: A,
     realms: R,
     auth_server_address: String,
+    listen_address: (Ipv4Addr, u16),
     realm_seed: [u8; 4],
     clients: RwLock<HashMap<ClientId, Arc<RwLock<Client>>>>,
     pub world: World<A, R, C>,
@@ -55,7 +63,7 @@ pub struct WorldServer<A: AccountService, R: RealmList, C: CharacterService> {
     update_interval: u16,
     update_counter: AtomicU64,
 
-    running: bool,
+    pub shutdown: Shutdown,
 }
 
 impl<A: AccountService + Clone, R: RealmList + Clone, C: CharacterService> WorldServer<A, R, C> {
@@ -64,38 +72,68 @@ impl<A: AccountService + Clone, R: RealmList + Clone, C: CharacterService> World
         accounts: A,
         realms: R,
         characters: C,
+        data: WorldData,
+        guids: GuidAllocator,
         auth_server_address: String,
+        listen_address: (Ipv4Addr, u16),
     ) -> Self {
         WorldServer::with_world(
             realm_id,
             accounts.clone(),
             realms.clone(),
-            World::new(realm_id, accounts, realms, characters),
+            World::new(realm_id, accounts, realms, characters, data, guids),
             auth_server_address,
+            listen_address,
         )
     }
 }
 
 impl<A: AccountService, R: RealmList, C: CharacterService> WorldServer<A, R, C> {
+    /// Set the rules new characters are checked against.
+    pub fn with_character_rules(self, rules: CharacterRules) -> Self {
+        Self {
+            world: self.world.with_character_rules(rules),
+            ..self
+        }
+    }
+
+    /// Set the rules for how characters are deleted.
+    pub fn with_delete_rules(self, rules: DeleteRules) -> Self {
+        Self {
+            world: self.world.with_delete_rules(rules),
+            ..self
+        }
+    }
+
+    /// Set the addons that clients are told to disable.
+    pub fn with_banned_addons(self, banned_addons: Vec<BannedAddon>) -> Self {
+        Self {
+            world: self.world.with_banned_addons(banned_addons),
+            ..self
+        }
+    }
+
     pub fn with_world(
         realm_id: RealmId,
         accounts: A,
         realms: R,
         world: World<A, R, C>,
         auth_server_address: String,
+        listen_address: (Ipv4Addr, u16),
     ) -> Self {
         Self {
             world,
             accounts,
             realms,
             auth_server_address,
+            listen_address,
             id: realm_id,
             realm_seed: rand::thread_rng().gen(),
             clients: Default::default(),
 
             update_interval: 100,
             update_counter: AtomicU64::new(0),
-            running: true,
+            shutdown: Shutdown::new(),
         }
     }
 
@@ -110,6 +148,9 @@ impl<A: AccountService, R: RealmList, C: CharacterService> WorldServer<A, R, C>
         let mut interval = interval(Duration::from_secs(5));
         loop {
             interval.tick().await;
+            if self.shutdown.is_stopping() {
+                break Ok(());
+            }
             trace!("sending population heartbeat {population}");
             let mut buffer = [0u8; 6];
             wow_bincode().serialize_into(&mut buffer[..], &(0u8, self.id.0 as u8, population))?;
@@ -122,49 +163,62 @@ impl<A: AccountService, R: RealmList, C: CharacterService> WorldServer<A, R, C>
     /// Allows the world server to accept new clients
     #[instrument(skip(self))]
     pub async fn accept_clients(&self) -> Result<()> {
-        let addr = ("0.0.0.0", 8085);
+        let addr = self.listen_address;
         let listener = TcpListener::bind(&addr).await?;
 
         info!("listening on {:?}", &addr);
 
         let mut connections = TcpListenerStream::new(listener).filter_map(|s| s.ok());
-        while let Some(stream) = connections.next().await {
-            let (reader, mut writer) = stream.into_split();
-            let (id, challenge): (ClientId, [u8; 32]) = {
-                let mut rng = rand::thread_rng();
-                rng.gen()
-            };
-
-            self.clients
-                .write()
-                .await
-                .insert(id, Arc::new(RwLock::new(Client { id, account: None })));
-
-            let packet = (
-                42u16.swap_bytes(),
-                OpCode::SmsgAuthChallenge,
-                1u32,
-                self.realm_seed,
-                challenge,
-            );
-            writer.write_all(&wow_bincode().serialize(&packet)?).await?;
-
-            if let Err(e) = self.connect_loop(reader, writer, id).await {
+        while let Some(stream) = select! {
+            s = connections.next() => s,
+            _ = self.shutdown.stopped() => None,
+        } {
+            if let Err(e) = self.accept(stream).await {
                 error!("error handling request: {e}");
             }
-
-            self.clients.write().await.remove(&id);
         }
 
+        info!("no longer accepting clients");
+
         Ok(())
     }
 
+    /// Challenges a newly connected client to authenticate, and
+    /// then handles its packets until it disconnects.
+    async fn accept(&self, stream: TcpStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let (id, challenge): (ClientId, [u8; 32]) = {
+            let mut rng = rand::thread_rng();
+            rng.gen()
+        };
+
+        let packet = (
+            42u16.swap_bytes(),
+            OpCode::SmsgAuthChallenge,
+            1u32,
+            self.realm_seed,
+            challenge,
+        );
+        writer.write_all(&wow_bincode().serialize(&packet)?).await?;
+
+        self.clients
+            .write()
+            .await
+            .insert(id, Arc::new(RwLock::new(Client { id, account: None })));
+
+        let result = self.connect_loop(reader, writer, id).await;
+
+        self.clients.write().await.remove(&id);
+
+        result
+    }
+
     /// Runs the world update tick
     #[instrument(skip(self))]
     pub async fn update(&self) -> Result<()> {
         let mut interval =
             IntervalStream::new(interval(Duration::from_millis(self.update_interval.into())))
-                .take_while(|_| self.running);
+                .take_while(|_| !self.shutdown.is_stopping());
 
         let mut prev_time = Instant::now();
         while interval.next().await.is_some() {
@@ -182,6 +236,129 @@ impl<A: AccountService, R: RealmList, C: CharacterService> WorldServer<A, R, C>
         Ok(())
     }
 
+    /// Waits for a shutdown to be requested, warning players as the
+    /// deadline approaches, and then brings the server down cleanly.
+    #[instrument(skip(self))]
+    pub async fn shutdown_handler(&self) -> Result<()> {
+        let mut state = self.shutdown.subscribe();
+        let mut previous = ShutdownState::Running;
+        loop {
+            let current = *state.borrow();
+            match (previous, current) {
+                (_, ShutdownState::Stopping) => break,
+                (ShutdownState::Pending(_), ShutdownState::Running) => {
+                    info!("shutdown cancelled");
+                    self.world
+                        .announce(ServerMessageType::ShutdownCancelled, "")
+                        .await;
+                }
+                _ => {}
+            }
+            previous = current;
+
+            let deadline = match current {
+                ShutdownState::Pending(deadline) => deadline,
+                _ => {
+                    state.changed().await?;
+                    continue;
+                }
+            };
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+
+            info!("shutting down in {remaining:?}");
+            self.world
+                .announce(
+                    ServerMessageType::ShutdownTime,
+                    &shutdown::format_remaining(remaining),
+                )
+                .await;
+
+            select! {
+                _ = sleep(shutdown::next_warning(remaining)) => {},
+                r = state.changed() => r?,
+            };
+        }
+
+        self.shutdown.stop()?;
+        info!("shutting down");
+
+        self.world.shutdown().await;
+
+        if let Err(e) = self.realms.update_status(vec![(self.id, false)]).await {
+            error!("could not mark realm as offline: {e}");
+        }
+        if let Err(e) = self.realms.request_shutdown(self.id, None).await {
+            error!("could not clear the shutdown request: {e}");
+        }
+
+        // let the auth server know immediately, rather than waiting for the heartbeat to lapse
+        let socket = UdpSocket::bind("127.0.0.1:0").await?;
+        socket.connect(&self.auth_server_address).await?;
+        let mut buffer = [0u8; 6];
+        wow_bincode().serialize_into(&mut buffer[..], &(1u8, self.id.0 as u8, 0u32))?;
+        if let Err(_e) = socket.send(&buffer).await {
+            warn!("could not notify {} of shutdown", self.auth_server_address);
+        }
+
+        Ok(())
+    }
+
+    /// Requests a shutdown when the process receives a signal. A second
+    /// signal forces the server to exit immediately.
+    pub async fn signals(&self) -> Result<()> {
+        shutdown::signal().await?;
+        info!("received signal, starting shutdown");
+        self.shutdown.request(Duration::ZERO)?;
+
+        shutdown::signal().await?;
+        bail!("received second signal, exiting immediately")
+    }
+
+    /// Polls the realm list for shutdowns requested through the api,
+    /// scheduling or cancelling a shutdown when the request changes.
+    #[instrument(skip(self))]
+    pub async fn shutdown_requests(&self) -> Result<()> {
+        // a request left over from a previous run is stale
+        self.realms.request_shutdown(self.id, None).await?;
+
+        let mut requested = None;
+        let mut interval = interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            if self.shutdown.is_stopping() {
+                break Ok(());
+            }
+
+            let current = match self.realms.requested_shutdown(self.id).await {
+                Ok(current) => current,
+                Err(e) => {
+                    warn!("could not check for shutdown requests: {e}");
+                    continue;
+                }
+            };
+            if current == requested {
+                continue;
+            }
+
+            match current {
+                Some(at) => {
+                    let delay = at.duration_since(SystemTime::now()).unwrap_or_default();
+                    info!("shutdown requested in {delay:?}");
+                    self.shutdown.request(delay)?;
+                }
+                None => {
+                    info!("shutdown request withdrawn");
+                    self.shutdown.cancel()?;
+                }
+            }
+            requested = current;
+        }
+    }
+
     /// handles authentication, WorldSession creation,
     /// and pipes packets into the World
     #[instrument(skip(self, reader, writer))]
@@ -245,38 +422,142 @@ impl<
     > WorldServer<A, R, C>
 {
     /// Start the world server, running the various tasks that it is comprised of
-    pub async fn start(self) -> Result<()> {
+    /// until the server is shut down.
+    pub async fn start(self, admin: Option<Listener>) -> Result<()> {
         let server = Arc::new(self);
 
-        try_join!(
-            flatten(tokio::task::Builder::new().name("world::heartbeat").spawn({
-                let cloned = server.clone();
-                async move {
-                    cloned
-                        .auth_server_heartbeat()
-                        .await
-                        .context("heartbeat error")
-                }
-            })),
-            flatten(tokio::task::Builder::new().name("world::clients").spawn({
-                let cloned = server.clone();
-                async move { cloned.accept_clients().await.context("client error") }
-            })),
-            flatten(tokio::task::Builder::new().name("world::update").spawn({
-                let cloned = server.clone();
-                async move { cloned.update().await.context("update error") }
-            })),
-            flatten(tokio::task::Builder::new().name("world::packets").spawn({
-                let cloned = server.clone();
-                async move { cloned.world.handle_packets().await.context("packet error") }
-            })),
-            flatten(tokio::task::Builder::new().name("world::timers").spawn({
-                let cloned = server.clone();
-                async move { cloned.world.timers().await.context("timer error") }
-            }))
-        )?;
+        let tasks = async {
+            try_join!(
+                flatten(tokio::task::Builder::new().name("world::heartbeat").spawn({
+                    let cloned = server.clone();
+                    async move {
+                        cloned
+                            .auth_server_heartbeat()
+                            .await
+                            .context("heartbeat error")
+                    }
+                })),
+                flatten(tokio::task::Builder::new().name("world::clients").spawn({
+                    let cloned = server.clone();
+                    async move { cloned.accept_clients().await.context("client error") }
+                })),
+                flatten(tokio::task::Builder::new().name("world::update").spawn({
+                    let cloned = server.clone();
+                    async move { cloned.update().await.context("update error") }
+                })),
+                flatten(tokio::task::Builder::new().name("world::packets").spawn({
+                    let cloned = server.clone();
+                    async move { cloned.world.handle_packets().await.context("packet error") }
+                })),
+                flatten(tokio::task::Builder::new().name("world::timers").spawn({
+                    let cloned = server.clone();
+                    async move { cloned.world.timers().await.context("timer error") }
+                })),
+                flatten(
+                    tokio::task::Builder::new()
+                        .name("world::shutdown_requests")
+                        .spawn({
+                            let cloned = server.clone();
+                            async move {
+                                cloned
+                                    .shutdown_requests()
+                                    .await
+                                    .context("shutdown request error")
+                            }
+                        })
+                ),
+                flatten(tokio::task::Builder::new().name("world::signals").spawn({
+                    let cloned = server.clone();
+                    async move { cloned.signals().await.context("signal error") }
+                })),
+                flatten(tokio::task::Builder::new().name("world::console").spawn({
+                    let cloned = server.clone();
+                    async move {
+                        azerust_console::run(&*cloned, admin)
+                            .await
+                            .context("console error")
+                    }
+                }))
+            )
+        };
 
-        Ok(())
+        let shutdown = flatten(tokio::task::Builder::new().name("world::shutdown").spawn({
+            let cloned = server.clone();
+            async move { cloned.shutdown_handler().await.context("shutdown error") }
+        }));
+
+        // the remaining tasks run forever, so we are done once the shutdown completes
+        select! {
+            r = tasks => r.map(|_| ()),
+            r = shutdown => r,
+        }
+    }
+}
+
+#[async_trait]
+impl<
+        A: 'static + AccountService + Send + Sync,
+        R: 'static + RealmList + Send + Sync,
+        C: 'static + CharacterService + Send + Sync,
+    > Console for WorldServer<A, R, C>
+{
+    async fn run(&self, command: Command) -> Result<String> {
+        match command {
+            Command::Account { command, json } => {
+                accounts::run(command, &self.accounts).await?.render(json)
+            }
+            Command::Character { command } => {
+                let banned = match &command {
+                    CharacterCommand::Ban { name, .. } => Some(name.clone()),
+                    _ => None,
+                };
+                let output = characters::run(command, self.world.characters()).await?;
+                match banned {
+                    Some(name) if self.world.kick(&name).await? => Ok(output + " and kicked them"),
+                    _ => Ok(output),
+                }
+            }
+            Command::Online => {
+                let online = self.world.online().await;
+                let mut output = format!("{} online", online.len());
+                for (client, account, character) in online {
+                    output += &format!(
+                        "\n{client:?} account {} playing {}",
+                        account.map(|a| a.to_string()).unwrap_or_default(),
+                        character.unwrap_or_default()
+                    );
+                }
+                Ok(output)
+            }
+            Command::Kick { name } => {
+                if self.world.kick(&name).await? {
+                    Ok(format!("kicked {name}"))
+                } else {
+                    bail!("{name} is not online")
+                }
+            }
+            Command::Announce { message } => {
+                self.world
+                    .announce(ServerMessageType::String, &message.join(" "))
+                    .await;
+                Ok("message sent".to_string())
+            }
+            Command::Shutdown { cancel: true, .. } => {
+                if self.shutdown.cancel()? {
+                    Ok("shutdown cancelled".to_string())
+                } else {
+                    bail!("no shutdown is pending")
+                }
+            }
+            Command::Shutdown { delay, .. } => {
+                self.shutdown.request(Duration::from_secs(delay))?;
+                Ok(format!("shutting down in {delay} seconds"))
+            }
+            Command::Reload => {
+                self.realms.reload().await;
+                Ok("reloaded realmlist".to_string())
+            }
+        }
     }
 }
 
@@ -350,3 +631,497 @@ async fn handle_auth_session<A: AccountService, R: RealmList, C: CharacterServic
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::{
+        io::{Read, Write},
+        net::Ipv4Addr,
+        sync::Arc,
+    };
+
+    use azerust_game::{
+        accounts::{Account, AccountId},
+        characters::{AtLoginFlags, Character, CharacterId, CharacterService, PlayerFlags},
+        guid::GuidAllocator,
+        realms::{RealmId, RealmList},
+        world::WorldData,
+    };
+    use azerust_memory::{fixtures, MemoryAccountService, MemoryCharacterService, MemoryRealmList};
+    use azerust_protocol::{
+        header_crypto::HeaderCrypto,
+        world::{OpCode, ResponseCode},
+    };
+    use bincode::Options;
+    use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+    use sha1::Digest;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::WorldServer;
+    use crate::wow_bincode::wow_bincode;
+
+    const SESSION_KEY: [u8; 40] = [7; 40];
+
+    /// Starts a world server for realm 1, returning the address to connect to.
+    async fn server(accounts: Vec<Account>, characters: MemoryCharacterService) -> String {
+        let realms = MemoryRealmList::new(vec![fixtures::realm(1, "Azeroth", 8085)]);
+        server_with_realms(accounts, realms, characters).await
+    }
+
+    /// Starts a world server for realm 1 of the given realmlist.
+    async fn server_with_realms(
+        accounts: Vec<Account>,
+        realms: MemoryRealmList,
+        characters: MemoryCharacterService,
+    ) -> String {
+        let guids = GuidAllocator::load(&characters).await.unwrap();
+        let server = Arc::new(WorldServer::new(
+            RealmId(1),
+            MemoryAccountService::new(accounts),
+            realms,
+            characters,
+            WorldData::default(),
+            guids,
+            "127.0.0.1:1234".to_string(),
+            (Ipv4Addr::LOCALHOST, 0),
+        ));
+
+        tokio::spawn({
+            let server = server.clone();
+            async move { server.world.handle_packets().await }
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let _ = server.accept(stream).await;
+            }
+        });
+
+        addr
+    }
+
+    /// An account that has logged in to the auth server.
+    fn account() -> Account {
+        Account {
+            session_key: Some(SESSION_KEY),
+            ..fixtures::account(1, "ARLYON", "TEST")
+        }
+    }
+
+    /// Connects to the server, sending an auth session for the given
+    /// username and session key. Returns the stream, along with the
+    /// header crypto for the session.
+    async fn connect(
+        addr: &str,
+        username: &str,
+        session_key: [u8; 40],
+    ) -> (TcpStream, HeaderCrypto) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+
+        let mut challenge = [0u8; 44];
+        stream.read_exact(&mut challenge).await.unwrap();
+        assert_eq!(
+            challenge[2..4],
+            u16::from(OpCode::SmsgAuthChallenge).to_le_bytes()
+        );
+        let realm_seed = &challenge[8..12];
+
+        let local_challenge = [1u8, 2, 3, 4];
+        let client_proof: [u8; 20] = {
+            let mut sha = sha1::Sha1::new();
+            sha.update(username.as_bytes());
+            sha.update([0u8; 4]);
+            sha.update(local_challenge);
+            sha.update(realm_seed);
+            sha.update(session_key);
+            sha.finalize().into()
+        };
+
+        // no addons
+        let addons = {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&0u32.to_le_bytes()).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        let mut body = wow_bincode()
+            .serialize(&(
+                12340u32,
+                0u32,
+                username,
+                0u32,
+                local_challenge,
+                0u32,
+                0u32,
+                1u32,
+                0u64,
+                client_proof,
+            ))
+            .unwrap();
+        body.extend(4u32.to_le_bytes());
+        body.extend(addons);
+
+        let mut packet = header(OpCode::CmsgAuthSession, body.len());
+        packet.extend(body);
+        stream.write_all(&packet).await.unwrap();
+
+        (stream, HeaderCrypto::new(session_key))
+    }
+
+    /// Creates the header of a client packet.
+    fn header(opcode: OpCode, len: usize) -> Vec<u8> {
+        wow_bincode()
+            .serialize(&((len as u16 + 4).swap_bytes(), u32::from(u16::from(opcode))))
+            .unwrap()
+    }
+
+    /// Sends a packet with an encrypted header.
+    ///
+    /// note: the client encrypts with the same key stream that the server
+    ///       decrypts with, so the crypto is used back to front.
+    async fn send(stream: &mut TcpStream, crypto: &mut HeaderCrypto, opcode: OpCode, body: &[u8]) {
+        let mut header: [u8; 6] = header(opcode, body.len()).try_into().unwrap();
+        crypto.decrypt(&mut header);
+        let mut packet = header.to_vec();
+        packet.extend_from_slice(body);
+        stream.write_all(&packet).await.unwrap();
+    }
+
+    /// Reads a packet with an encrypted header, returning its opcode and body.
+    async fn receive(stream: &mut TcpStream, crypto: &mut HeaderCrypto) -> (OpCode, Vec<u8>) {
+        let mut header = [0u8; 4];
+        stream.read_exact(&mut header).await.unwrap();
+        crypto.encrypt(&mut header);
+
+        let size = u16::from_be_bytes([header[0], header[1]]) as usize;
+        let opcode = OpCode::try_from(u16::from_le_bytes([header[2], header[3]])).unwrap();
+        let mut body = vec![0u8; size - 2];
+        stream.read_exact(&mut body).await.unwrap();
+        (opcode, body)
+    }
+
+    /// Reads packets until one with the given opcode arrives.
+    async fn expect(stream: &mut TcpStream, crypto: &mut HeaderCrypto, opcode: OpCode) -> Vec<u8> {
+        loop {
+            let (received, body) = receive(stream, crypto).await;
+            if received == opcode {
+                return body;
+            }
+        }
+    }
+
+    /// Reads the unencrypted response sent when authentication fails.
+    async fn rejection(stream: &mut TcpStream) -> u32 {
+        let mut response = [0u8; 8];
+        stream.read_exact(&mut response).await.unwrap();
+        assert_eq!(
+            response[2..4],
+            u16::from(OpCode::SmsgAuthResponse).to_le_bytes()
+        );
+        u32::from_le_bytes(response[4..].try_into().unwrap())
+    }
+
+    #[tokio::test]
+    pub async fn session_lists_characters() {
+        let characters = MemoryCharacterService::new(vec![
+            fixtures::character(1, AccountId(1), "Thrall"),
+            fixtures::character(2, AccountId(2), "Jaina"),
+        ]);
+        let addr = server(vec![account()], characters).await;
+
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+
+        let (opcode, body) = receive(&mut stream, &mut crypto).await;
+        assert_eq!(opcode, OpCode::SmsgAuthResponse);
+        assert_eq!(body[..4], u32::from(ResponseCode::AuthOk).to_le_bytes());
+
+        send(&mut stream, &mut crypto, OpCode::CmsgCharEnum, &[]).await;
+        let body = expect(&mut stream, &mut crypto, OpCode::SmsgCharEnum).await;
+
+        // one character, followed by its guid and name
+        assert_eq!(body[0], 1);
+        assert_eq!(&body[9..16], b"Thrall\0");
+    }
+
+    #[tokio::test]
+    pub async fn account_data_is_stored_and_served() {
+        let addr = server(vec![account()], MemoryCharacterService::default()).await;
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgAuthResponse).await;
+
+        // macros that do not compress well, so the packet spans several reads
+        let mut seed = 1u32;
+        let macros = (0..4000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect::<Vec<_>>();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&macros).unwrap();
+        let mut body = wow_bincode()
+            .serialize(&(4u32, 1234u32, macros.len() as u32))
+            .unwrap();
+        body.extend(encoder.finish().unwrap());
+        assert!(body.len() > 2048);
+
+        send(
+            &mut stream,
+            &mut crypto,
+            OpCode::CmsgUpdateAccountData,
+            &body,
+        )
+        .await;
+        let body = expect(
+            &mut stream,
+            &mut crypto,
+            OpCode::SmsgUpdateAccountDataComplete,
+        )
+        .await;
+        assert_eq!(body[..4], 4u32.to_le_bytes());
+
+        send(
+            &mut stream,
+            &mut crypto,
+            OpCode::CmsgRequestAccountData,
+            &4u32.to_le_bytes(),
+        )
+        .await;
+        let body = expect(&mut stream, &mut crypto, OpCode::SmsgUpdateAccountData).await;
+        let (guid, kind, time, size): (u64, u32, u32, u32) =
+            wow_bincode().deserialize(&body[..20]).unwrap();
+        assert_eq!((guid, kind, time, size), (0, 4, 1234, 4000));
+
+        let mut data = Vec::new();
+        ZlibDecoder::new(&body[20..])
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, macros);
+    }
+
+    #[tokio::test]
+    pub async fn tutorials_are_stored_and_sent() {
+        let characters = MemoryCharacterService::default();
+        characters
+            .set_tutorials(AccountId(1), [1, 0, 0, 0, 0, 0, 0, 0])
+            .await
+            .unwrap();
+        let addr = server(vec![account()], characters.clone()).await;
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+
+        let body = expect(&mut stream, &mut crypto, OpCode::SmsgTutorialFlags).await;
+        let tutorials: [u32; 8] = wow_bincode().deserialize(&body).unwrap();
+        assert_eq!(tutorials, [1, 0, 0, 0, 0, 0, 0, 0]);
+
+        // packets are handled in order, so the pong means the flag is saved
+        for flag in [33u32, 256] {
+            send(
+                &mut stream,
+                &mut crypto,
+                OpCode::CmsgTutorialFlag,
+                &flag.to_le_bytes(),
+            )
+            .await;
+        }
+        send(&mut stream, &mut crypto, OpCode::CmsgPing, &[0u8; 8]).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgPong).await;
+        assert_eq!(
+            characters.tutorials(AccountId(1)).await.unwrap(),
+            [1, 2, 0, 0, 0, 0, 0, 0]
+        );
+
+        send(&mut stream, &mut crypto, OpCode::CmsgTutorialClear, &[]).await;
+        send(&mut stream, &mut crypto, OpCode::CmsgPing, &[0u8; 8]).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgPong).await;
+        assert_eq!(
+            characters.tutorials(AccountId(1)).await.unwrap(),
+            [u32::MAX; 8]
+        );
+
+        send(&mut stream, &mut crypto, OpCode::CmsgTutorialReset, &[]).await;
+        send(&mut stream, &mut crypto, OpCode::CmsgPing, &[0u8; 8]).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgPong).await;
+        assert_eq!(characters.tutorials(AccountId(1)).await.unwrap(), [0; 8]);
+    }
+
+    #[tokio::test]
+    pub async fn character_list_shows_flags() {
+        let thrall = Character {
+            player_flags: PlayerFlags::HideHelm.into(),
+            at_login: AtLoginFlags::Rename | AtLoginFlags::FirstLogin,
+            guild: 7,
+            ..fixtures::character(1, AccountId(1), "Thrall")
+        };
+        let characters = MemoryCharacterService::new(vec![thrall.clone()]);
+        characters
+            .ban_character(
+                CharacterId::try_from(thrall.id).unwrap(),
+                "test",
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        let addr = server(vec![account()], characters).await;
+
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgAuthResponse).await;
+        send(&mut stream, &mut crypto, OpCode::CmsgCharEnum, &[]).await;
+        let body = expect(&mut stream, &mut crypto, OpCode::SmsgCharEnum).await;
+
+        // after the name come the race, class, appearance, level, zone and map
+        let body = &body[16 + 9 + 8..];
+        assert_eq!(body[..4], thrall.position_x.to_le_bytes());
+        assert_eq!(body[12..16], 7u32.to_le_bytes());
+        assert_eq!(
+            body[16..20],
+            (0x400u32 | 0x4000 | 0x0100_0000).to_le_bytes()
+        );
+        assert_eq!(body[20..24], 0u32.to_le_bytes());
+        assert_eq!(body[24], 1);
+    }
+
+    #[tokio::test]
+    pub async fn banned_character_cannot_log_in() {
+        let thrall = fixtures::character(1, AccountId(1), "Thrall");
+        let characters = MemoryCharacterService::new(vec![thrall.clone()]);
+        characters
+            .ban_character(
+                CharacterId::try_from(thrall.id).unwrap(),
+                "test",
+                None,
+                Some("testing"),
+            )
+            .await
+            .unwrap();
+        let addr = server(vec![account()], characters).await;
+
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgAuthResponse).await;
+
+        let guid = wow_bincode().serialize(&thrall.id).unwrap();
+        send(&mut stream, &mut crypto, OpCode::CmsgPlayerLogin, &guid).await;
+        let body = expect(&mut stream, &mut crypto, OpCode::SmsgCharacterLoginFailed).await;
+
+        assert_eq!(body, [ResponseCode::CharLoginLockedByBilling as u8]);
+    }
+
+    #[tokio::test]
+    pub async fn wrong_session_key_is_rejected() {
+        let addr = server(vec![account()], MemoryCharacterService::default()).await;
+
+        let (mut stream, _) = connect(&addr, "ARLYON", [8; 40]).await;
+
+        assert_eq!(
+            rejection(&mut stream).await,
+            u32::from(ResponseCode::AuthReject)
+        );
+    }
+
+    #[tokio::test]
+    pub async fn expired_session_is_rejected() {
+        let addr = server(
+            vec![fixtures::account(1, "ARLYON", "TEST")],
+            MemoryCharacterService::default(),
+        )
+        .await;
+
+        let (mut stream, _) = connect(&addr, "ARLYON", SESSION_KEY).await;
+
+        assert_eq!(
+            rejection(&mut stream).await,
+            u32::from(ResponseCode::AuthSessionExpired)
+        );
+    }
+
+    #[tokio::test]
+    pub async fn unknown_account_is_rejected() {
+        let addr = server(vec![account()], MemoryCharacterService::default()).await;
+
+        let (mut stream, _) = connect(&addr, "THRALL", SESSION_KEY).await;
+
+        assert_eq!(
+            rejection(&mut stream).await,
+            u32::from(ResponseCode::AuthUnknownAccount)
+        );
+    }
+
+    /// Asks the server to create a character, returning the response code.
+    async fn create(
+        stream: &mut TcpStream,
+        crypto: &mut HeaderCrypto,
+        name: &str,
+        race: u8,
+        class: u8,
+    ) -> u8 {
+        let body = wow_bincode()
+            .serialize(&(name, race, class, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8, 0u8))
+            .unwrap();
+        send(stream, crypto, OpCode::CmsgCharCreate, &body).await;
+        expect(stream, crypto, OpCode::SmsgCharCreate).await[0]
+    }
+
+    #[tokio::test]
+    pub async fn character_create_is_validated() {
+        let characters =
+            MemoryCharacterService::new(vec![fixtures::character(1, AccountId(1), "Thrall")]);
+        let addr = server(vec![account()], characters.clone()).await;
+
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgAuthResponse).await;
+
+        for (name, race, class, code) in [
+            ("Thrall", 2, 1, ResponseCode::CharCreateNameInUse),
+            ("Garrosh", 2, 2, ResponseCode::CharCreateRestrictedRaceclass),
+            ("Garr0sh", 2, 1, ResponseCode::CharNameInvalidCharacter),
+            ("Garrosh", 2, 6, ResponseCode::CharCreateLevelRequirement),
+            ("Garrosh", 2, 1, ResponseCode::CharCreateSuccess),
+        ] {
+            assert_eq!(
+                create(&mut stream, &mut crypto, name, race, class).await,
+                code as u8,
+                "{name} {race} {class}"
+            );
+        }
+
+        assert_eq!(characters.count_by_account(AccountId(1)).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    pub async fn character_count_is_limited_across_realms() {
+        let realms = MemoryRealmList::new(vec![
+            fixtures::realm(1, "Azeroth", 8085),
+            fixtures::realm(2, "Outland", 8086),
+        ]);
+        realms
+            .set_character_count(RealmId(2), AccountId(1), 50)
+            .await
+            .unwrap();
+        let characters = MemoryCharacterService::new(vec![]);
+        let addr = server_with_realms(vec![account()], realms.clone(), characters).await;
+
+        let (mut stream, mut crypto) = connect(&addr, "ARLYON", SESSION_KEY).await;
+        expect(&mut stream, &mut crypto, OpCode::SmsgAuthResponse).await;
+        assert_eq!(
+            create(&mut stream, &mut crypto, "Garrosh", 2, 1).await,
+            ResponseCode::CharCreateAccountLimit as u8
+        );
+
+        realms
+            .set_character_count(RealmId(2), AccountId(1), 49)
+            .await
+            .unwrap();
+        assert_eq!(
+            create(&mut stream, &mut crypto, "Garrosh", 2, 1).await,
+            ResponseCode::CharCreateSuccess as u8
+        );
+        assert_eq!(realms.character_count(AccountId(1)).await.unwrap(), 50);
+    }
+}
@@ -0,0 +1,4 @@
+-- The empty databases that the docker compose servers connect to.
+-- Their tables are created by the `migrate` command of either server.
+CREATE DATABASE IF NOT EXISTS `auth` DEFAULT CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+CREATE DATABASE IF NOT EXISTS `characters` DEFAULT CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;